@@ -1,4 +1,28 @@
 fn main() {
     let now = chrono::Local::now();
     println!("cargo:rustc-env=BUILD_DATE={}", now.format("%Y-%m-%d"));
+
+    // Target and socket/process library versions, for `kav version`
+    println!(
+        "cargo:rustc-env=BUILD_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+    let lock = std::fs::read_to_string("Cargo.lock").unwrap_or_default();
+    for (name, var) in [
+        ("sysinfo", "SYSINFO_VERSION"),
+        ("netstat2", "NETSTAT2_VERSION"),
+    ] {
+        println!(
+            "cargo:rustc-env={}={}",
+            var,
+            locked_version(&lock, name).unwrap_or("unknown")
+        );
+    }
+}
+
+/// Version of `name` pinned in Cargo.lock
+fn locked_version<'a>(lock: &'a str, name: &str) -> Option<&'a str> {
+    let header = format!("name = \"{}\"\nversion = \"", name);
+    let start = lock.find(&header)? + header.len();
+    lock[start..].split('"').next()
 }
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+use crate::models::{PortEntry, Protocol};
+
+/// Minimum gap between two bells, so a burst of new ports rings once
+pub const BELL_DEBOUNCE: Duration = Duration::from_secs(10);
+
+/// Which new entries trigger an alert, picked with `list --alert-on`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AlertOn {
+    /// Entries reachable from the network (not bound only to loopback)
+    Public,
+    /// Any new entry
+    Any,
+}
+
+/// Something worth alerting about in the latest scan
+pub struct Alert {
+    /// One line describing every new entry
+    pub message: String,
+    /// Ring the terminal bell; false while debounced
+    pub bell: bool,
+}
+
+/// Tracks entries between watch-mode scans and reports new ones
+pub struct Alerter {
+    on: AlertOn,
+    known: HashSet<(Protocol, u16, u32)>,
+    last_bell: Option<Instant>,
+}
+
+impl Alerter {
+    /// `baseline` is the first scan; what's already there never alerts
+    pub fn new(on: AlertOn, baseline: &[PortEntry]) -> Self {
+        Self {
+            on,
+            known: baseline.iter().map(key).collect(),
+            last_bell: None,
+        }
+    }
+
+    /// Compare against the previous scan. Entries that appeared and match
+    /// `on` are summarized in one alert.
+    pub fn check(&mut self, entries: &[PortEntry]) -> Option<Alert> {
+        let fresh: Vec<&PortEntry> = entries
+            .iter()
+            .filter(|e| !self.known.contains(&key(e)))
+            .filter(|e| self.on == AlertOn::Any || e.is_public())
+            .collect();
+        self.known = entries.iter().map(key).collect();
+        if fresh.is_empty() {
+            return None;
+        }
+
+        let kind = match self.on {
+            AlertOn::Public => "public ",
+            AlertOn::Any => "",
+        };
+        let list: Vec<String> = fresh
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} {} ({}, PID {})",
+                    e.addr_display(),
+                    e.protocol,
+                    e.process_name,
+                    e.pid
+                )
+            })
+            .collect();
+        let message = format!(
+            "New {}{}: {}",
+            kind,
+            if fresh.len() == 1 { "port" } else { "ports" },
+            list.join(", ")
+        );
+
+        let bell = self
+            .last_bell
+            .is_none_or(|at| at.elapsed() >= BELL_DEBOUNCE);
+        if bell {
+            self.last_bell = Some(Instant::now());
+        }
+        Some(Alert { message, bell })
+    }
+}
+
+fn key(e: &PortEntry) -> (Protocol, u16, u32) {
+    (e.protocol, e.port, e.pid)
+}
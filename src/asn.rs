@@ -0,0 +1,98 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+/// Who announces an IP range
+#[derive(Debug, Clone)]
+pub struct AsnRecord {
+    pub asn: u32,
+    /// Two-letter country code, or "None" when the dataset has none
+    pub country: String,
+    pub org: String,
+}
+
+impl fmt::Display for AsnRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AS{} {}", self.asn, self.org)?;
+        if self.country != "None" && !self.country.is_empty() {
+            write!(f, ", {}", self.country)?;
+        }
+        Ok(())
+    }
+}
+
+/// Offline IP-to-ASN table loaded from an iptoasn.com TSV dump
+/// (`ip2asn-combined.tsv`, `ip2asn-v4.tsv` or `ip2asn-v6.tsv`). Lookups never
+/// touch the network.
+pub struct AsnDb {
+    /// `(first, last, record)` sorted by `first`; IPv4 ranges are stored as
+    /// IPv4-mapped IPv6 so both families share one table
+    ranges: Vec<(u128, u128, usize)>,
+    records: Vec<AsnRecord>,
+}
+
+impl AsnDb {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("in {}", path.display()))
+    }
+
+    /// Parse `range_start  range_end  AS_number  country_code  AS_description`
+    /// tab-separated lines. Unrouted ranges (AS 0) are skipped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut ranges = Vec::new();
+        let mut records = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 5 {
+                bail!("line {}: expected 5 tab-separated fields", i + 1);
+            }
+            let first = parse_ip(fields[0]).with_context(|| format!("line {}", i + 1))?;
+            let last = parse_ip(fields[1]).with_context(|| format!("line {}", i + 1))?;
+            let asn: u32 = fields[2]
+                .parse()
+                .with_context(|| format!("line {}: bad AS number `{}`", i + 1, fields[2]))?;
+            if asn == 0 {
+                continue;
+            }
+
+            ranges.push((first, last, records.len()));
+            records.push(AsnRecord {
+                asn,
+                country: fields[3].to_string(),
+                org: fields[4].to_string(),
+            });
+        }
+
+        ranges.sort_by_key(|&(first, _, _)| first);
+        Ok(Self { ranges, records })
+    }
+
+    pub fn lookup(&self, ip: IpAddr) -> Option<&AsnRecord> {
+        let ip = ip_key(ip);
+        let idx = self.ranges.partition_point(|&(first, _, _)| first <= ip);
+        let &(_, last, record) = self.ranges.get(idx.checked_sub(1)?)?;
+        (ip <= last).then(|| &self.records[record])
+    }
+}
+
+fn parse_ip(s: &str) -> Result<u128> {
+    let ip: IpAddr = s
+        .parse()
+        .with_context(|| format!("bad IP address `{}`", s))?;
+    Ok(ip_key(ip))
+}
+
+fn ip_key(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(v4) => u128::from(v4.to_ipv6_mapped()),
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
@@ -1,4 +1,15 @@
-use clap::{Parser, Subcommand};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+
+use crate::alert::AlertOn;
+use crate::group::GroupBy;
+use crate::models::{PortEntry, ServiceCategory, SortField};
+use crate::output::MIN_TABLE_WIDTH;
+use crate::theme::{BorderStyle, Palette, UdpStyle};
 
 #[derive(Parser)]
 #[command(
@@ -10,30 +21,429 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Command>,
+
+    /// Include kaval's own process in results (hidden by default)
+    #[arg(long = "self", global = true)]
+    pub include_self: bool,
+
+    /// Include UDP sockets on ephemeral ports, which are usually clients (hidden by default)
+    #[arg(long, alias = "ephemeral-udp", global = true)]
+    pub all_udp: bool,
+
+    /// Show each process's thread count in the TUI detail pane and `kav check`
+    /// (Linux only; other platforms show "n/a")
+    #[arg(long, global = true)]
+    pub include_threads: bool,
+
+    /// Force kill in the TUI without asking first (protected ports still ask);
+    /// same as `[protect] confirm_force_kill = false`
+    #[arg(long, global = true)]
+    pub yolo: bool,
+
+    /// Inspect only: no kills or restarts from the TUI, and `kav kill` / `kav restart`
+    /// refuse to run (setting the KAVAL_READONLY environment variable does the same)
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Show the socket inode in the TUI detail pane and `kav check`, to match
+    /// against `/proc/<pid>/fd` and lsof (Linux only; left out elsewhere)
+    #[arg(long, global = true)]
+    pub show_inode: bool,
+
+    /// Also count every open TCP/UDP socket (any state) in the list summary and TUI header
+    #[arg(long, global = true)]
+    pub total_sockets: bool,
+
+    /// Show entries hidden by the `[ignore]` config list
+    #[arg(long, global = true)]
+    pub show_ignored: bool,
+
+    /// Offline iptoasn.com TSV dump used to label remote peers with their network (AS)
+    /// in the TUI detail pane
+    #[arg(long, value_name = "PATH")]
+    pub geoip: Option<std::path::PathBuf>,
+
+    /// Tie-breaker when the main sort field is equal (TUI and `list --sort`)
+    #[arg(long, global = true, value_enum, default_value_t = SortField::Port)]
+    pub sort_secondary: SortField,
+
+    /// Category colors: `colorblind` uses distinguishable hues and adds a glyph per category
+    #[arg(long, global = true, value_enum, default_value_t = Palette::Default)]
+    pub palette: Palette,
+
+    /// TUI border style; `ascii` draws boxes with + - | for terminals that garble
+    /// line drawing (overrides `[theme] border`)
+    #[arg(long, global = true, value_enum, value_name = "STYLE")]
+    pub border: Option<BorderStyle>,
+
+    /// Plain ASCII for terminals without Unicode: no inline CPU/MEM bars in the
+    /// TUI, and ASCII borders unless --border says otherwise
+    #[arg(long, global = true)]
+    pub ascii: bool,
+
+    /// Print every well-known port kaval labels (port, service, category) and exit
+    #[arg(long)]
+    pub list_known: bool,
+
+    /// Turn off colors (setting the NO_COLOR environment variable does the same)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// How to show UDP rows: `dim` mutes them, `hide` leaves them out unless asked for
+    /// (overrides `[theme] udp_style`)
+    #[arg(long, global = true, value_enum, value_name = "STYLE")]
+    pub udp_style: Option<UdpStyle>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProtocolArg {
+    Tcp,
+    Udp,
+    Both,
+}
+
+impl ProtocolArg {
+    /// `(tcp, udp)` flags for the scanner
+    pub fn flags(self) -> (bool, bool) {
+        match self {
+            ProtocolArg::Tcp => (true, false),
+            ProtocolArg::Udp => (false, true),
+            ProtocolArg::Both => (true, true),
+        }
+    }
+}
+
+/// `list --format`: text formats beyond the colored table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    /// Pipe table for GitHub issues and docs
+    Markdown,
+    /// The `--json` array on a single line, for piping and embedding
+    JsonCompact,
+}
+
+/// What `kav kill` aims at: a port, or one bind of it written as
+/// `127.0.0.1:3000` / `[::1]:3000` when several addresses share the port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KillTarget {
+    pub addr: Option<IpAddr>,
+    pub port: u16,
+}
+
+impl KillTarget {
+    pub fn parse(s: &str) -> Result<Self> {
+        if let Ok(port) = s.parse() {
+            return Ok(Self { addr: None, port });
+        }
+        let bind: SocketAddr = s.parse().ok().with_context(|| {
+            format!(
+                "expected PORT or ADDRESS:PORT (e.g. 127.0.0.1:3000 or [::1]:3000), found \"{}\"",
+                s
+            )
+        })?;
+        Ok(Self {
+            addr: Some(bind.ip()),
+            port: bind.port(),
+        })
+    }
+
+    pub fn matches(&self, entry: &PortEntry) -> bool {
+        entry.port == self.port && self.addr.is_none_or(|addr| entry.local_addr == addr)
+    }
+}
+
+impl fmt::Display for KillTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.addr {
+            Some(addr) => write!(f, "{}", SocketAddr::new(addr, self.port)),
+            None => write!(f, "port {}", self.port),
+        }
+    }
 }
 
 #[derive(Subcommand)]
 pub enum Command {
     /// List all listening ports (one-shot table output)
+    #[command(group(clap::ArgGroup::new("watch").args(["every", "digest"])))]
     List {
         /// Output as JSON
-        #[arg(long)]
+        #[arg(long, conflicts_with = "plain")]
         json: bool,
+
+        /// With --json: wrap the entries in an object with the scan time, host,
+        /// protocols and whether results were truncated (also readable by `kav diff`)
+        #[arg(long, requires = "json")]
+        meta: bool,
+
+        /// Tab-separated output without color, for piping into sort/awk/cut
+        #[arg(long)]
+        plain: bool,
+
+        /// JSON for strict ingestion (BigQuery, CSV converters): every key on every
+        /// entry and no nulls; unknown text is "", unknown numbers are 0
+        #[arg(
+            long,
+            conflicts_with_all = ["json", "plain", "template", "format", "a11y", "wide", "expand", "group_by", "max_width", "every", "digest", "pager", "watch_port"]
+        )]
+        flat_json: bool,
+
+        /// Which sockets to scan [default: both, or tcp with `--udp-style hide`]
+        #[arg(long, value_enum)]
+        protocol: Option<ProtocolArg>,
+
+        /// Sort by this field (CPU and memory sort biggest first; uptime sorts the
+        /// longest-running process first)
+        #[arg(long, value_enum, default_value_t = SortField::Port)]
+        sort: SortField,
+
+        /// Only entries whose port, process, state or service contains TEXT
+        #[arg(long, value_name = "TEXT")]
+        filter: Option<String>,
+
+        /// Hide entries bound only to loopback (127.0.0.1 / ::1)
+        #[arg(long)]
+        exposed: bool,
+
+        /// Only entries using more than PCT percent CPU
+        #[arg(long, value_name = "PCT")]
+        min_cpu: Option<f32>,
+
+        /// Only entries using more than MB megabytes of memory
+        #[arg(long, value_name = "MB")]
+        min_mem: Option<f64>,
+
+        /// Only entries whose process you can kill: your own, or all of them as
+        /// root (everything, with a note, where owners can't be read)
+        #[arg(long)]
+        killable: bool,
+
+        /// Only entries kaval couldn't identify a service for, to see what's
+        /// worth adding to the config (add --wide for their command lines)
+        #[arg(long)]
+        unknown_only: bool,
+
+        /// Scan another machine over SSH (runs `kav list --json` there, or `ss` if
+        /// kav isn't installed)
+        #[arg(long, value_name = "USER@HOST")]
+        host: Option<String>,
+
+        /// One line per entry from a format string, e.g. '{port}\t{process}\t{pid}'.
+        /// Fields: port, proto, process, service, pid, cpu, mem, uptime, addr
+        #[arg(long, value_name = "FMT", conflicts_with_all = ["json", "plain"])]
+        template: Option<String>,
+
+        /// Print the table in another format: `markdown` for pasting into issues and
+        /// docs (no color; add --wide for the COMMAND column), or `json-compact` for
+        /// the --json entries on one line
+        #[arg(
+            long,
+            value_enum,
+            value_name = "FORMAT",
+            conflicts_with_all = ["json", "plain", "template", "a11y", "max_width", "watch", "watch_port", "pager"]
+        )]
+        format: Option<ListFormat>,
+
+        /// Add a COMMAND column with each process's full command line
+        #[arg(long, conflicts_with_all = ["json", "plain", "template"])]
+        wide: bool,
+
+        /// One row per entry, even for browser services and worker clusters
+        /// (same process name, several PIDs) that `[group]` collapses
+        #[arg(long, conflicts_with_all = ["json", "plain", "template", "format", "a11y"])]
+        expand: bool,
+
+        /// Split the table into sections, each under a subheader with its port count
+        /// and combined CPU and memory: `category` or `pid` (owning process)
+        #[arg(
+            long,
+            value_enum,
+            value_name = "FIELD",
+            conflicts_with_all = ["json", "plain", "template", "format", "a11y"]
+        )]
+        group_by: Option<GroupBy>,
+
+        /// Screen-reader friendly: one plain sentence per entry, no color or table
+        #[arg(
+            long,
+            conflicts_with_all = ["json", "plain", "template", "wide", "every", "digest"]
+        )]
+        a11y: bool,
+
+        /// Never draw the table wider than COLS columns, even when piped (e.g. for CI logs)
+        #[arg(
+            long,
+            value_name = "COLS",
+            value_parser = clap::value_parser!(u16).range(MIN_TABLE_WIDTH as i64..),
+            conflicts_with_all = ["json", "plain", "template", "a11y"]
+        )]
+        max_width: Option<u16>,
+
+        /// Omit the header line in --plain output
+        #[arg(long, requires = "plain")]
+        no_header: bool,
+
+        /// Re-scan and redraw the table every SECS seconds until q or Ctrl-C
+        #[arg(
+            long,
+            value_name = "SECS",
+            value_parser = clap::value_parser!(u64).range(1..),
+            conflicts_with_all = ["json", "plain", "template"]
+        )]
+        every: Option<u64>,
+
+        /// Every SECS seconds, print a one-line summary of ports that appeared or
+        /// disappeared, by category (e.g. "+2 dev, -1 database")
+        #[arg(
+            long,
+            value_name = "SECS",
+            value_parser = clap::value_parser!(u64).range(1..),
+            conflicts_with_all = ["json", "plain", "template", "every"]
+        )]
+        digest: Option<u64>,
+
+        /// Page the table inside the terminal when it's longer than the screen, keeping
+        /// its colors (q quits; arrows, space and b scroll). Ignored when piped
+        #[arg(
+            long,
+            conflicts_with_all = ["json", "plain", "template", "a11y", "watch", "watch_port"]
+        )]
+        pager: bool,
+
+        /// Poll just this port every second and print a timestamped line each time its
+        /// holder changes (appears, goes away, or a new PID takes it). The filters and
+        /// `[ignore]` apply; holders are listed by PID
+        #[arg(
+            long,
+            value_name = "PORT",
+            conflicts_with_all = ["json", "plain", "template", "a11y", "watch", "sort"]
+        )]
+        watch_port: Option<u16>,
+
+        /// Write the output to FILE instead of the terminal, without color codes
+        /// (missing parent directories are created)
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["every", "digest", "watch_port", "pager"]
+        )]
+        output: Option<std::path::PathBuf>,
+
+        /// With --every or --digest: ring the bell and print a highlighted line when a
+        /// new entry appears (`public`: only ones reachable from the network)
+        #[arg(long, value_enum, value_name = "WHICH", requires = "watch")]
+        alert_on: Option<AlertOn>,
     },
 
     /// Check what's running on a specific port
     Check {
         /// Port number to check
-        port: u16,
+        #[arg(required_unless_present = "from", conflicts_with = "from")]
+        port: Option<u16>,
+
+        /// Print the matching entries as a JSON array, like `list --json` (`[]` when
+        /// nothing is listening)
+        #[arg(long)]
+        json: bool,
+
+        /// Check every port in FILE instead: one `port[/proto] [service]` per line
+        /// (e.g. `5432/tcp PostgreSQL`, `#` comments). Exits 1 if any is missing or
+        /// held by another service
+        #[arg(long, value_name = "FILE", conflicts_with = "json")]
+        from: Option<std::path::PathBuf>,
     },
 
     /// Kill the process listening on a port
     Kill {
-        /// Port number whose process to kill
-        port: u16,
+        /// Port number whose process to kill, or ADDRESS:PORT (e.g. 127.0.0.1:3000,
+        /// [::1]:3000) for just the socket bound to that address
+        #[arg(
+            value_name = "PORT",
+            value_parser = KillTarget::parse,
+            required_unless_present = "category",
+            conflicts_with = "category"
+        )]
+        target: Option<KillTarget>,
+
+        /// Kill every process in this category instead (e.g. all dev servers), after
+        /// listing them and asking once
+        #[arg(long, value_enum, value_name = "CATEGORY")]
+        category: Option<ServiceCategory>,
 
         /// Force kill (SIGKILL) without confirmation
         #[arg(short, long)]
         force: bool,
+
+        /// Also kill every process the owner started, children first, after listing
+        /// them all and asking once
+        #[arg(long, conflicts_with_all = ["category", "json"])]
+        tree: bool,
+
+        /// Print a JSON result ({"port","killed":[{pid,name}],"failed":[{pid,error}]})
+        /// and exit 1 if any kill failed. Protected entries are reported as failed
+        /// instead of prompting.
+        #[arg(long, conflicts_with = "category")]
+        json: bool,
     },
+
+    /// Stop the process on a port and start its command again, detached. Re-runs the
+    /// captured command line verbatim, in the same directory and environment
+    Restart {
+        /// Port number whose process to restart
+        port: u16,
+
+        /// Don't ask before restarting (protected entries still need the port typed)
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Report likely problems, such as idle dev servers holding ports
+    Audit,
+
+    /// One line per common dev port: what's on it, or "free"
+    Dev,
+
+    /// Compare two `kav list --json` snapshots: added, removed and re-owned ports
+    Diff {
+        /// Earlier snapshot
+        before: std::path::PathBuf,
+        /// Later snapshot
+        after: std::path::PathBuf,
+    },
+
+    /// Check what kaval can see on this system (sockets, PIDs, CPU, config)
+    Doctor,
+
+    /// Version plus build target, socket/process library versions and which
+    /// scan features this platform has (for bug reports)
+    Version,
+
+    /// Print a shell completion script for every subcommand and flag, e.g.
+    /// `kav completions zsh > ~/.zfunc/_kav`
+    Completions {
+        /// bash, zsh, fish, powershell or elvish
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Create, locate or check the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Write a commented config with every default to the standard path
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print where the config file is read from
+    Path,
+
+    /// Parse the config file and report any errors
+    Validate,
 }
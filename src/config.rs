@@ -0,0 +1,830 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use crate::models::PortEntry;
+use crate::theme::{BorderStyle, ThemeOverrides, UdpStyle};
+
+/// Commented config written by `kav config init`; every value is the default
+pub const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
+/// Accepted range for `[columns]` widths
+const MIN_COLUMN_WIDTH: usize = 4;
+const MAX_COLUMN_WIDTH: usize = 500;
+
+/// A parsed TOML value (only the subset kaval's config needs)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "string",
+            Value::Int(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "boolean",
+            Value::Array(_) => "array",
+        }
+    }
+
+    /// A string, or an array of strings, as a list
+    fn as_str_list(&self, key: &str) -> Result<Vec<String>> {
+        match self {
+            Value::Str(s) => Ok(vec![s.clone()]),
+            Value::Array(items) => items
+                .iter()
+                .map(|v| match v {
+                    Value::Str(s) => Ok(s.clone()),
+                    other => bail!("{}: expected string, found {}", key, other.type_name()),
+                })
+                .collect(),
+            other => bail!(
+                "{}: expected string or array, found {}",
+                key,
+                other.type_name()
+            ),
+        }
+    }
+
+    fn as_str(&self, key: &str) -> Result<&str> {
+        match self {
+            Value::Str(s) => Ok(s),
+            other => bail!("{}: expected string, found {}", key, other.type_name()),
+        }
+    }
+
+    /// A `"#rrggbb"` color
+    fn as_color(&self, key: &str) -> Result<ratatui::style::Color> {
+        let s = self.as_str(key)?;
+        let hex = s
+            .strip_prefix('#')
+            .filter(|h| h.len() == 6 && h.chars().all(|c| c.is_ascii_hexdigit()))
+            .with_context(|| {
+                format!(
+                    "{}: expected a color like \"#06b6d4\", found \"{}\"",
+                    key, s
+                )
+            })?;
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        Ok(ratatui::style::Color::Rgb(
+            channel(0),
+            channel(2),
+            channel(4),
+        ))
+    }
+
+    /// A column width in characters
+    fn as_width(&self, key: &str) -> Result<usize> {
+        match self {
+            Value::Int(n) => usize::try_from(*n)
+                .ok()
+                .filter(|w| (MIN_COLUMN_WIDTH..=MAX_COLUMN_WIDTH).contains(w))
+                .with_context(|| {
+                    format!(
+                        "{}: expected a width from {} to {}, found {}",
+                        key, MIN_COLUMN_WIDTH, MAX_COLUMN_WIDTH, n
+                    )
+                }),
+            other => bail!("{}: expected integer, found {}", key, other.type_name()),
+        }
+    }
+
+    /// An array of port numbers
+    fn as_port_list(&self, key: &str) -> Result<Vec<u16>> {
+        match self {
+            Value::Array(items) => items
+                .iter()
+                .map(|v| match v {
+                    Value::Int(n) => u16::try_from(*n)
+                        .ok()
+                        .filter(|&p| p > 0)
+                        .with_context(|| format!("{}: {} is not a valid port", key, n)),
+                    other => bail!("{}: expected port number, found {}", key, other.type_name()),
+                })
+                .collect(),
+            other => bail!(
+                "{}: expected array of ports, found {}",
+                key,
+                other.type_name()
+            ),
+        }
+    }
+}
+
+/// A parsed config file: `section -> key -> value`, with top-level keys under
+/// the "" section, plus the line each header and key is on for error messages
+#[derive(Debug, Default)]
+pub struct Document {
+    pub sections: BTreeMap<String, BTreeMap<String, Value>>,
+    /// `(section, key)` to its line number; key "" is the section header
+    lines: HashMap<(String, String), usize>,
+}
+
+impl Document {
+    /// Line of `key` in `section`, or of the `[section]` header for an empty key
+    pub fn line(&self, section: &str, key: &str) -> usize {
+        self.lines
+            .get(&(section.to_string(), key.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Sections `Config::parse` understands
+const SECTIONS: &[&str] = &[
+    "keys", "protect", "dev", "ignore", "columns", "history", "refresh", "group", "watch", "theme",
+];
+
+/// User configuration. Every section is optional; missing values keep defaults.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// `[keys]` — action name to one or more key combos
+    pub keys: BTreeMap<String, Vec<String>>,
+    /// `[protect]` — entries that need a typed confirmation before killing
+    pub protect: Protection,
+    /// `[dev] ports` — extra ports for `kav dev`, on top of the built-in list
+    pub dev_ports: Vec<u16>,
+    /// `[theme]` — protocol colors and how UDP rows are shown
+    pub theme: ThemeOverrides,
+    /// `[ignore] entries` — known-benign entries hidden unless `--show-ignored`
+    pub ignore: Vec<IgnoreRule>,
+    /// `[columns]` — how much of process names and commands to show
+    pub columns: Columns,
+    /// `[history]` — the TUI's record of when each port + process was first seen
+    pub history: History,
+    /// `[refresh]` — how the TUI's auto-refresh slows down while idle
+    pub refresh: Refresh,
+    /// `[group]` — which entries `kav list` collapses into summary rows
+    pub group: Grouping,
+    /// `[watch]` — ports to make stand out in the table and TUI
+    pub watch: Watch,
+}
+
+/// Settings for the first-seen record kept in [`state_dir`]
+#[derive(Debug, Clone, Copy)]
+pub struct History {
+    pub enabled: bool,
+    /// Forget a port + process pair after this many days without seeing it
+    pub forget_after_days: u32,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            forget_after_days: 30,
+        }
+    }
+}
+
+/// TUI auto-refresh: after a few unchanged scans with no keypress, the
+/// interval doubles up to `max_interval_secs`
+#[derive(Debug, Clone, Copy)]
+pub struct Refresh {
+    pub idle_backoff: bool,
+    pub max_interval_secs: u64,
+}
+
+impl Default for Refresh {
+    fn default() -> Self {
+        Self {
+            idle_backoff: true,
+            max_interval_secs: 30,
+        }
+    }
+}
+
+/// Summary rows in the `kav list` table: one per browser service, and one
+/// per worker cluster (a process name running as several PIDs under one parent)
+#[derive(Debug, Clone, Copy)]
+pub struct Grouping {
+    pub browsers: bool,
+    pub workers: bool,
+}
+
+impl Default for Grouping {
+    fn default() -> Self {
+        Self {
+            browsers: true,
+            workers: true,
+        }
+    }
+}
+
+/// Ports that always stand out: a `★` and the accent color in `kav list` and
+/// the TUI. Unlike `[ignore]`, nothing is hidden.
+#[derive(Debug, Clone, Default)]
+pub struct Watch {
+    pub ports: Vec<u16>,
+    /// Watched ports come first, whatever the sort
+    pub float: bool,
+}
+
+impl Watch {
+    pub fn covers(&self, entry: &PortEntry) -> bool {
+        self.ports.contains(&entry.port)
+    }
+
+    /// With `float`, move watched entries to the front, keeping the sort
+    /// order among them and among the rest
+    pub fn float_to_top(&self, entries: &mut [PortEntry]) {
+        if self.float && !self.ports.is_empty() {
+            entries.sort_by_key(|e| !self.covers(e));
+        }
+    }
+}
+
+/// Widest PROCESS and COMMAND text before it's cut with `…`. Unset widths
+/// keep the built-in layout (which fits the table to the terminal).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Columns {
+    pub process: Option<usize>,
+    pub command: Option<usize>,
+}
+
+/// Ports and services where a mistaken kill is costly (e.g. SSH on a remote box)
+#[derive(Debug, Clone)]
+pub struct Protection {
+    pub ports: Vec<u16>,
+    /// Known-service labels, matched case-insensitively
+    pub services: Vec<String>,
+    /// The TUI's force kill asks first, like a normal kill
+    pub confirm_force_kill: bool,
+}
+
+impl Default for Protection {
+    fn default() -> Self {
+        Self {
+            ports: vec![22],
+            services: vec!["SSH".to_string()],
+            confirm_force_kill: true,
+        }
+    }
+}
+
+impl Protection {
+    pub fn covers(&self, entry: &PortEntry) -> bool {
+        self.ports.contains(&entry.port)
+            || entry
+                .known_service
+                .is_some_and(|svc| self.services.iter().any(|s| s.eq_ignore_ascii_case(svc)))
+    }
+}
+
+/// Hides entries on `port` whose process name contains `process`
+/// (case-insensitive), so the rule survives restarts and PID changes.
+/// Written as `"5432:postgres"`, or `"5432"` for any process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreRule {
+    pub port: u16,
+    pub process: String,
+}
+
+impl IgnoreRule {
+    pub fn parse(s: &str) -> Result<Self> {
+        let (port, process) = s.split_once(':').unwrap_or((s, ""));
+        let port = port
+            .trim()
+            .parse()
+            .ok()
+            .filter(|&p: &u16| p > 0)
+            .with_context(|| format!("expected \"PORT:PROCESS\", found \"{}\"", s))?;
+        Ok(Self {
+            port,
+            process: process.trim().to_string(),
+        })
+    }
+
+    pub fn matches(&self, entry: &PortEntry) -> bool {
+        entry.port == self.port
+            && entry
+                .process_name
+                .to_lowercase()
+                .contains(&self.process.to_lowercase())
+    }
+}
+
+impl fmt::Display for IgnoreRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.process.is_empty() {
+            write!(f, "{}", self.port)
+        } else {
+            write!(f, "{}:{}", self.port, self.process)
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file if it exists, or defaults if it doesn't.
+    pub fn load() -> Result<Self> {
+        match config_path() {
+            Some(path) if path.exists() => {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("reading {}", path.display()))?;
+                Self::parse(&text).with_context(|| format!("in {}", path.display()))
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let doc = parse_toml(text)?;
+        let mut config = Self::default();
+
+        for (section, table) in &doc.sections {
+            if !section.is_empty() && !SECTIONS.contains(&section.as_str()) {
+                bail!(
+                    "line {}: unknown section [{}]",
+                    doc.line(section, ""),
+                    section
+                );
+            }
+            for (key, value) in table {
+                config
+                    .set(section, key, value)
+                    .with_context(|| format!("line {}", doc.line(section, key)))?;
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Apply one `key = value` from `[section]`
+    fn set(&mut self, section: &str, key: &str, value: &Value) -> Result<()> {
+        match section {
+            "keys" => {
+                let combos = value.as_str_list(&format!("keys.{}", key))?;
+                self.keys.insert(key.to_string(), combos);
+            }
+            "protect" => match key {
+                "ports" => self.protect.ports = value.as_port_list("protect.ports")?,
+                "services" => self.protect.services = value.as_str_list("protect.services")?,
+                "confirm_force_kill" => match value {
+                    Value::Bool(b) => self.protect.confirm_force_kill = *b,
+                    other => bail!(
+                        "protect.confirm_force_kill: expected boolean, found {}",
+                        other.type_name()
+                    ),
+                },
+                other => bail!("unknown key '{}' in [protect]", other),
+            },
+            "dev" => match key {
+                "ports" => self.dev_ports = value.as_port_list("dev.ports")?,
+                other => bail!("unknown key '{}' in [dev]", other),
+            },
+            "ignore" => match key {
+                "entries" => {
+                    self.ignore = value
+                        .as_str_list("ignore.entries")?
+                        .iter()
+                        .map(|s| IgnoreRule::parse(s))
+                        .collect::<Result<_>>()
+                        .context("ignore.entries")?
+                }
+                other => bail!("unknown key '{}' in [ignore]", other),
+            },
+            "columns" => match key {
+                "process" => {
+                    self.columns.process = Some(value.as_width("columns.process")?)
+                }
+                "command" => {
+                    self.columns.command = Some(value.as_width("columns.command")?)
+                }
+                other => bail!("unknown key '{}' in [columns]", other),
+            },
+            "history" => match key {
+                "enabled" => match value {
+                    Value::Bool(b) => self.history.enabled = *b,
+                    other => bail!(
+                        "history.enabled: expected boolean, found {}",
+                        other.type_name()
+                    ),
+                },
+                "forget_after_days" => {
+                    self.history.forget_after_days = match value {
+                        Value::Int(n) => u32::try_from(*n)
+                            .ok()
+                            .filter(|&d| d > 0)
+                            .with_context(|| {
+                                format!(
+                                    "history.forget_after_days: expected a positive number of days, found {}",
+                                    n
+                                )
+                            })?,
+                        other => bail!(
+                            "history.forget_after_days: expected integer, found {}",
+                            other.type_name()
+                        ),
+                    }
+                }
+                other => bail!("unknown key '{}' in [history]", other),
+            },
+            "refresh" => match key {
+                "idle_backoff" => match value {
+                    Value::Bool(b) => self.refresh.idle_backoff = *b,
+                    other => bail!(
+                        "refresh.idle_backoff: expected boolean, found {}",
+                        other.type_name()
+                    ),
+                },
+                "max_interval" => {
+                    self.refresh.max_interval_secs = match value {
+                        Value::Int(n) => u64::try_from(*n)
+                            .ok()
+                            .filter(|&s| s > 0)
+                            .with_context(|| {
+                                format!(
+                                    "refresh.max_interval: expected a positive number of seconds, found {}",
+                                    n
+                                )
+                            })?,
+                        other => bail!(
+                            "refresh.max_interval: expected integer, found {}",
+                            other.type_name()
+                        ),
+                    }
+                }
+                other => bail!("unknown key '{}' in [refresh]", other),
+            },
+            "group" => {
+                let flag = match key {
+                    "browsers" => &mut self.group.browsers,
+                    "workers" => &mut self.group.workers,
+                    other => bail!("unknown key '{}' in [group]", other),
+                };
+                match value {
+                    Value::Bool(b) => *flag = *b,
+                    other => bail!(
+                        "group.{}: expected boolean, found {}",
+                        key,
+                        other.type_name()
+                    ),
+                }
+            }
+            "watch" => match key {
+                "ports" => self.watch.ports = value.as_port_list("watch.ports")?,
+                "float" => match value {
+                    Value::Bool(b) => self.watch.float = *b,
+                    other => bail!(
+                        "watch.float: expected boolean, found {}",
+                        other.type_name()
+                    ),
+                },
+                other => bail!("unknown key '{}' in [watch]", other),
+            },
+            "theme" => match key {
+                "tcp" => self.theme.tcp = Some(value.as_color("theme.tcp")?),
+                "udp" => self.theme.udp = Some(value.as_color("theme.udp")?),
+                "udp_style" => {
+                    let style = value.as_str("theme.udp_style")?;
+                    self.theme.udp_style =
+                        Some(UdpStyle::parse(style).with_context(|| {
+                            format!(
+                                "theme.udp_style: expected normal, dim or hide, found \"{}\"",
+                                style
+                            )
+                        })?);
+                }
+                "border" => {
+                    let style = value.as_str("theme.border")?;
+                    self.theme.border =
+                        Some(BorderStyle::parse(style).with_context(|| {
+                            format!(
+                                "theme.border: expected plain, rounded, ascii or none, found \"{}\"",
+                                style
+                            )
+                        })?);
+                }
+                other => bail!("unknown key '{}' in [theme]", other),
+            },
+            "" => bail!("unexpected top-level key '{}'", key),
+            other => bail!("unknown section [{}]", other),
+        }
+        Ok(())
+    }
+}
+
+/// Where the config file is read from: `$KAVAL_CONFIG`, else
+/// `$XDG_CONFIG_HOME/kaval/config.toml`, else `~/.config/kaval/config.toml`.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("KAVAL_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(".config")))?;
+    Some(base.join("kaval").join("config.toml"))
+}
+
+/// Where kaval keeps state between runs: `$XDG_STATE_HOME/kaval`, else
+/// `~/.local/state/kaval`.
+pub fn state_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(".local").join("state")))?;
+    Some(base.join("kaval"))
+}
+
+/// `kav config init`: write [`DEFAULT_CONFIG`] to the config path. An existing
+/// file is only replaced with `force`.
+pub fn init(force: bool) -> Result<PathBuf> {
+    let path = config_path().context("no config location (HOME is not set)")?;
+    if path.exists() && !force {
+        bail!(
+            "{} already exists (use --force to overwrite)",
+            path.display()
+        );
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    std::fs::write(&path, DEFAULT_CONFIG).with_context(|| format!("writing {}", path.display()))?;
+    Ok(path)
+}
+
+/// Add `rule` to `[ignore] entries` in the config file, creating the file or
+/// section as needed. Only that one line is rewritten, so comments and the
+/// rest of the file are kept. Returns the file's path.
+pub fn add_ignore(rule: &IgnoreRule) -> Result<PathBuf> {
+    let path = config_path().context("no config location (HOME is not set)")?;
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    let mut rules = Config::parse(&text)
+        .with_context(|| format!("in {}", path.display()))?
+        .ignore;
+    if rules.contains(rule) {
+        return Ok(path);
+    }
+    rules.push(rule.clone());
+    let quoted: Vec<String> = rules.iter().map(|r| quote(&r.to_string())).collect();
+    let entries_line = format!("entries = [{}]", quoted.join(", "));
+
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    let is_header = |line: &str| strip_comment(line).trim().starts_with('[');
+    match lines
+        .iter()
+        .position(|l| strip_comment(l).trim() == "[ignore]")
+    {
+        Some(header) => {
+            let end = lines[header + 1..]
+                .iter()
+                .position(|l| is_header(l))
+                .map_or(lines.len(), |i| header + 1 + i);
+            let existing = (header + 1..end).find(|&i| {
+                strip_comment(&lines[i])
+                    .split_once('=')
+                    .is_some_and(|(key, _)| unquote_key(key.trim()) == "entries")
+            });
+            match existing {
+                Some(i) => lines[i] = entries_line,
+                None => lines.insert(header + 1, entries_line),
+            }
+        }
+        None => {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push("[ignore]".to_string());
+            lines.push(entries_line);
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    std::fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(path)
+}
+
+/// Quote a string as a TOML basic string
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// Parse the small TOML subset kaval uses: `[section]` headers, `key = value`
+/// pairs with strings, integers, floats, booleans and single-line arrays,
+/// and `#` comments.
+pub fn parse_toml(text: &str) -> Result<Document> {
+    let mut doc = Document::default();
+    let mut section = String::new();
+    doc.sections.insert(section.clone(), BTreeMap::new());
+
+    for (i, raw) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = strip_comment(raw).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix('[') {
+            let name = rest
+                .strip_suffix(']')
+                .with_context(|| format!("line {}: unterminated section header", line_no))?
+                .trim();
+            if name.is_empty() {
+                bail!("line {}: empty section name", line_no);
+            }
+            section = name.to_string();
+            doc.sections.entry(section.clone()).or_default();
+            doc.lines
+                .entry((section.clone(), String::new()))
+                .or_insert(line_no);
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .with_context(|| format!("line {}: expected `key = value`", line_no))?;
+        let key = unquote_key(key.trim());
+        if key.is_empty() {
+            bail!("line {}: missing key", line_no);
+        }
+        let value = parse_value(value.trim())
+            .with_context(|| format!("line {}: key '{}'", line_no, key))?;
+
+        let table = doc.sections.entry(section.clone()).or_default();
+        if table.insert(key.clone(), value).is_some() {
+            bail!("line {}: duplicate key '{}'", line_no, key);
+        }
+        doc.lines.insert((section.clone(), key), line_no);
+    }
+
+    Ok(doc)
+}
+
+fn unquote_key(key: &str) -> String {
+    key.trim_matches('"').to_string()
+}
+
+/// Drop a trailing `# comment`, ignoring `#` inside quoted strings
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && q == '"' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &line[..i],
+            None => {}
+        }
+    }
+    line
+}
+
+fn parse_value(s: &str) -> Result<Value> {
+    if s.is_empty() {
+        bail!("missing value");
+    }
+    if let Some(inner) = s.strip_prefix('[') {
+        let inner = inner
+            .strip_suffix(']')
+            .context("unterminated array (arrays must fit on one line)")?;
+        return split_array(inner)?
+            .iter()
+            .map(|item| parse_value(item))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array);
+    }
+    if let Some(inner) = s.strip_prefix('"') {
+        let inner = inner.strip_suffix('"').context("unterminated string")?;
+        return unescape(inner).map(Value::Str);
+    }
+    if let Some(inner) = s.strip_prefix('\'') {
+        let inner = inner.strip_suffix('\'').context("unterminated string")?;
+        return Ok(Value::Str(inner.to_string()));
+    }
+    match s {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+    let digits = s.replace('_', "");
+    if let Ok(n) = digits.parse::<i64>() {
+        return Ok(Value::Int(n));
+    }
+    if let Ok(f) = digits.parse::<f64>() {
+        return Ok(Value::Float(f));
+    }
+    bail!("invalid value `{}` (strings must be quoted)", s)
+}
+
+/// Split array contents on top-level commas, respecting quotes
+fn split_array(s: &str) -> Result<Vec<String>> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for c in s.chars() {
+        match quote {
+            Some(q) => {
+                current.push(c);
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && q == '"' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            None if c == '[' => bail!("nested arrays are not supported"),
+            None if c == ',' => {
+                items.push(current.trim().to_string());
+                current.clear();
+            }
+            None => current.push(c),
+        }
+    }
+    if quote.is_some() {
+        bail!("unterminated string in array");
+    }
+    let last = current.trim();
+    if !last.is_empty() {
+        items.push(last.to_string());
+    }
+    if items.iter().any(|item| item.is_empty()) {
+        bail!("empty array element");
+    }
+    Ok(items)
+}
+
+fn unescape(s: &str) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => bail!("unsupported escape `\\{}`", other),
+            None => bail!("dangling backslash"),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_name_the_line() {
+        let err = |text| format!("{:#}", Config::parse(text).unwrap_err());
+        let text = "\
+# colors
+[theme]
+tcp = \"#06b6d4\"
+
+udp_style = \"loud\"
+";
+        assert!(
+            err(text).starts_with("line 5: theme.udp_style"),
+            "{}",
+            err(text)
+        );
+        assert_eq!(
+            err("[dev]\nports = [3000]\n[bogus]\n"),
+            "line 3: unknown section [bogus]"
+        );
+        assert_eq!(
+            err("[dev]\nport = 3000\n"),
+            "line 2: unknown key 'port' in [dev]"
+        );
+        assert!(Config::parse(DEFAULT_CONFIG).is_ok());
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Context, Result};
+
+use crate::json::{self, Json};
+use crate::models::{PortEntry, Protocol, ServiceCategory};
+
+/// How many entries of one category appeared and disappeared between scans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CategoryChange {
+    pub category: ServiceCategory,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// What identifies "the same" entry across two scans
+fn entry_key(e: &PortEntry) -> (Protocol, u16, u32) {
+    (e.protocol, e.port, e.pid)
+}
+
+/// Per-category counts of entries added and removed going from `old` to
+/// `new`. Categories with no changes are left out.
+pub fn category_changes(old: &[PortEntry], new: &[PortEntry]) -> Vec<CategoryChange> {
+    let old_keys: HashSet<_> = old.iter().map(entry_key).collect();
+    let new_keys: HashSet<_> = new.iter().map(entry_key).collect();
+
+    ServiceCategory::ALL
+        .into_iter()
+        .map(|category| CategoryChange {
+            category,
+            added: new
+                .iter()
+                .filter(|e| e.category == category && !old_keys.contains(&entry_key(e)))
+                .count(),
+            removed: old
+                .iter()
+                .filter(|e| e.category == category && !new_keys.contains(&entry_key(e)))
+                .count(),
+        })
+        .filter(|c| c.added > 0 || c.removed > 0)
+        .collect()
+}
+
+/// Compact summary like `+2 dev, -1 database`, or `no changes`
+pub fn digest_line(changes: &[CategoryChange]) -> String {
+    let mut parts = Vec::new();
+    for c in changes {
+        if c.added > 0 {
+            parts.push(format!("+{} {}", c.added, c.category.label()));
+        }
+        if c.removed > 0 {
+            parts.push(format!("-{} {}", c.removed, c.category.label()));
+        }
+    }
+    if parts.is_empty() {
+        "no changes".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// One entry read back from a `kav list --json` snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotEntry {
+    pub port: u16,
+    pub protocol: String,
+    pub process: String,
+    pub pid: u32,
+    pub service: Option<String>,
+}
+
+/// Snapshot format version this build reads
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Read a snapshot: either the bare array `kav list --json` prints, or an
+/// object with `version` and `entries`.
+pub fn parse_snapshot(text: &str) -> Result<Vec<SnapshotEntry>> {
+    let doc = json::parse(text).context("not valid JSON")?;
+    let items = match &doc {
+        Json::Array(items) => items.as_slice(),
+        Json::Object(_) => {
+            if let Some(version) = doc.get("version") {
+                let version = version.as_u32().context("`version` must be a number")?;
+                if version != SNAPSHOT_VERSION {
+                    bail!(
+                        "snapshot version {} is not supported (this kav reads version {})",
+                        version,
+                        SNAPSHOT_VERSION
+                    );
+                }
+            }
+            doc.get("entries")
+                .and_then(Json::as_array)
+                .context("not a kav snapshot: expected an `entries` array")?
+        }
+        _ => bail!("not a kav snapshot: expected a JSON array from `kav list --json`"),
+    };
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let field = |name: &str| {
+                item.get(name)
+                    .with_context(|| format!("entry {}: missing `{}`", i + 1, name))
+            };
+            let port = field("port")?
+                .as_u32()
+                .and_then(|p| u16::try_from(p).ok())
+                .with_context(|| format!("entry {}: `port` is not a port number", i + 1))?;
+            let text = |name: &str| -> Result<String> {
+                field(name)?
+                    .as_str()
+                    .map(str::to_string)
+                    .with_context(|| format!("entry {}: `{}` must be a string", i + 1, name))
+            };
+            Ok(SnapshotEntry {
+                port,
+                protocol: text("protocol")?,
+                process: text("process")?,
+                pid: field("pid")?
+                    .as_u32()
+                    .with_context(|| format!("entry {}: `pid` must be a number", i + 1))?,
+                service: item
+                    .get("service")
+                    .and_then(Json::as_str)
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// What changed between two snapshots, keyed by (port, protocol)
+#[derive(Debug, Default)]
+pub struct SnapshotDiff {
+    pub added: Vec<SnapshotEntry>,
+    pub removed: Vec<SnapshotEntry>,
+    /// Same port and protocol, different owning PID: `(before, after)`
+    pub pid_changed: Vec<(SnapshotEntry, SnapshotEntry)>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.pid_changed.is_empty()
+    }
+}
+
+pub fn compare_snapshots(before: &[SnapshotEntry], after: &[SnapshotEntry]) -> SnapshotDiff {
+    let mut keys: Vec<(u16, &str)> = before
+        .iter()
+        .chain(after)
+        .map(|e| (e.port, e.protocol.as_str()))
+        .collect();
+    keys.sort_unstable();
+    keys.dedup();
+
+    let mut diff = SnapshotDiff::default();
+    for (port, protocol) in keys {
+        let on = |entries: &[SnapshotEntry], others: &[SnapshotEntry]| -> Vec<SnapshotEntry> {
+            entries
+                .iter()
+                .filter(|e| e.port == port && e.protocol == protocol)
+                .filter(|e| {
+                    !others
+                        .iter()
+                        .any(|o| o.port == port && o.protocol == protocol && o.pid == e.pid)
+                })
+                .cloned()
+                .collect()
+        };
+        let gone = on(before, after);
+        let new = on(after, before);
+
+        // A port that lost one owner and gained another changed hands
+        let paired = gone.len().min(new.len());
+        diff.pid_changed.extend(
+            gone[..paired]
+                .iter()
+                .cloned()
+                .zip(new[..paired].iter().cloned()),
+        );
+        diff.removed.extend(gone[paired..].iter().cloned());
+        diff.added.extend(new[paired..].iter().cloned());
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(port: u16, pid: u32, category: ServiceCategory) -> PortEntry {
+        PortEntry {
+            category,
+            ..PortEntry::fixture(port, pid, "server")
+        }
+    }
+
+    #[test]
+    fn digest_counts_changes_per_category() {
+        let old = vec![
+            entry(3000, 10, ServiceCategory::DevServer),
+            entry(5432, 20, ServiceCategory::Database),
+            entry(6379, 30, ServiceCategory::Cache),
+        ];
+        let new = vec![
+            entry(3000, 10, ServiceCategory::DevServer),
+            entry(5173, 11, ServiceCategory::DevServer),
+            entry(8080, 12, ServiceCategory::DevServer),
+            entry(6379, 30, ServiceCategory::Cache),
+            // Same port, new PID: one removed and one added
+            entry(5432, 21, ServiceCategory::Database),
+        ];
+
+        let changes = category_changes(&old, &new);
+        assert_eq!(
+            changes,
+            [
+                CategoryChange {
+                    category: ServiceCategory::DevServer,
+                    added: 2,
+                    removed: 0,
+                },
+                CategoryChange {
+                    category: ServiceCategory::Database,
+                    added: 1,
+                    removed: 1,
+                },
+            ]
+        );
+        assert_eq!(digest_line(&changes), "+2 dev, +1 database, -1 database");
+        assert_eq!(digest_line(&category_changes(&new, &new)), "no changes");
+    }
+}
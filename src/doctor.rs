@@ -0,0 +1,208 @@
+use std::io::{self, Write};
+use std::thread;
+
+use anyhow::Result;
+use crossterm::style::{Color, ResetColor, SetForegroundColor};
+use sysinfo::{System, MINIMUM_CPU_UPDATE_INTERVAL};
+
+use crate::config::{config_path, Config};
+use crate::keymap::KeyMap;
+use crate::scanner::{scan, ScanOptions};
+
+/// Result of one capability probe
+struct Check {
+    label: &'static str,
+    /// `None` for informational lines that can't fail
+    ok: Option<bool>,
+    detail: String,
+}
+
+impl Check {
+    fn info(label: &'static str, detail: String) -> Self {
+        Self {
+            label,
+            ok: None,
+            detail,
+        }
+    }
+
+    fn pass(label: &'static str, detail: String) -> Self {
+        Self {
+            label,
+            ok: Some(true),
+            detail,
+        }
+    }
+
+    fn fail(label: &'static str, detail: String) -> Self {
+        Self {
+            label,
+            ok: Some(false),
+            detail,
+        }
+    }
+}
+
+/// `kav doctor`: probe what this platform lets kaval see, for bug reports.
+pub fn run() -> Result<()> {
+    let mut checks = vec![check_os(), check_privileges()];
+    checks.extend(check_sockets());
+    checks.push(check_cpu());
+    checks.push(check_config());
+
+    let green = Color::Rgb {
+        r: 34,
+        g: 197,
+        b: 94,
+    };
+    let red = Color::Rgb {
+        r: 239,
+        g: 68,
+        b: 68,
+    };
+    let muted = Color::Rgb {
+        r: 120,
+        g: 120,
+        b: 125,
+    };
+
+    let out = io::stdout();
+    let mut w = out.lock();
+    for check in &checks {
+        let (mark, color) = match check.ok {
+            Some(true) => ("✓", green),
+            Some(false) => ("✗", red),
+            None => ("·", muted),
+        };
+        writeln!(
+            w,
+            "{}{}{} {:<20} {}",
+            SetForegroundColor(color),
+            mark,
+            ResetColor,
+            check.label,
+            check.detail
+        )?;
+    }
+    Ok(())
+}
+
+fn check_os() -> Check {
+    let version = System::long_os_version().unwrap_or_else(|| std::env::consts::OS.to_string());
+    Check::info(
+        "Platform",
+        format!("{} ({})", version, std::env::consts::ARCH),
+    )
+}
+
+fn check_privileges() -> Check {
+    let detail = match is_root() {
+        Some(true) => "running as root".to_string(),
+        Some(false) => "running as a regular user (other users' sockets may be hidden)".to_string(),
+        None => "unknown on this platform".to_string(),
+    };
+    Check::info("Privileges", detail)
+}
+
+#[cfg(unix)]
+fn is_root() -> Option<bool> {
+    let mut sys = System::new();
+    let pid = sysinfo::get_current_pid().ok()?;
+    sys.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&[pid]),
+        true,
+        sysinfo::ProcessRefreshKind::new().with_user(sysinfo::UpdateKind::Always),
+    );
+    let root: sysinfo::Uid = "0".parse().ok()?;
+    Some(sys.process(pid)?.effective_user_id()? == &root)
+}
+
+#[cfg(not(unix))]
+fn is_root() -> Option<bool> {
+    None
+}
+
+fn check_sockets() -> Vec<Check> {
+    let opts = ScanOptions {
+        established: true,
+        include_self: true,
+        ephemeral_udp: true,
+        ..ScanOptions::default()
+    };
+    let result = match scan(&opts) {
+        Ok(result) => result,
+        Err(e) => {
+            return vec![Check::fail(
+                "Socket enumeration",
+                format!("failed: {:#}", e),
+            )]
+        }
+    };
+
+    let resolved = result
+        .entries
+        .iter()
+        .filter(|e| e.pid != 0 && !e.partial)
+        .count();
+    let pid_check = if resolved == 0 {
+        Check::fail(
+            "PID association",
+            "no socket could be mapped to a process".to_string(),
+        )
+    } else if result.underprivileged {
+        Check::fail(
+            "PID association",
+            format!(
+                "partial: {} mapped, {} without an owner (try sudo)",
+                resolved, result.unresolved
+            ),
+        )
+    } else {
+        Check::pass("PID association", format!("{} sockets mapped", resolved))
+    };
+
+    vec![
+        Check::pass(
+            "Socket enumeration",
+            format!("{} sockets", result.entries.len() + result.unresolved),
+        ),
+        pid_check,
+    ]
+}
+
+fn check_cpu() -> Check {
+    let mut sys = System::new();
+    sys.refresh_cpu_usage();
+    thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_cpu_usage();
+
+    if sys.cpus().is_empty() {
+        return Check::fail("CPU sampling", "no CPUs reported".to_string());
+    }
+    Check::pass(
+        "CPU sampling",
+        format!(
+            "{} cores, {:.1}% busy",
+            sys.cpus().len(),
+            sys.global_cpu_usage()
+        ),
+    )
+}
+
+fn check_config() -> Check {
+    let Some(path) = config_path() else {
+        return Check::info("Config", "no location (HOME is not set)".to_string());
+    };
+    if !path.exists() {
+        return Check::info(
+            "Config",
+            format!("{} (not present, using defaults)", path.display()),
+        );
+    }
+
+    let loaded = Config::load().and_then(|config| KeyMap::from_config(&config.keys).map(|_| ()));
+    match loaded {
+        Ok(()) => Check::pass("Config", path.display().to_string()),
+        Err(e) => Check::fail("Config", format!("{:#}", e)),
+    }
+}
@@ -0,0 +1,151 @@
+use crate::config::IgnoreRule;
+use crate::models::PortEntry;
+
+/// Which entries to show. Shared by the CLI and the TUI so both agree on what
+/// each filter means; every criterion left unset matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Lowercased search text
+    text: String,
+    hide_loopback: bool,
+    min_cpu: Option<f32>,
+    min_mem: Option<f64>,
+    /// Only entries this user can signal; the inner `None` is an unknown user
+    killable: Option<Option<u32>>,
+    unknown_only: bool,
+    ignore: Vec<IgnoreRule>,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Case-insensitive substring match on port, process, state and service
+    pub fn text(mut self, query: &str) -> Self {
+        self.text = query.to_lowercase();
+        self
+    }
+
+    /// Drop entries bound only to 127.0.0.1 / ::1
+    pub fn hide_loopback(mut self, hide: bool) -> Self {
+        self.hide_loopback = hide;
+        self
+    }
+
+    /// Keep entries using strictly more than `pct` percent CPU
+    pub fn min_cpu(mut self, pct: Option<f32>) -> Self {
+        self.min_cpu = pct;
+        self
+    }
+
+    /// Keep entries using strictly more than `mb` megabytes of memory
+    pub fn min_mem(mut self, mb: Option<f64>) -> Self {
+        self.min_mem = mb;
+        self
+    }
+
+    /// Keep entries the user with ID `me` can kill (see
+    /// [`PortEntry::is_killable_by`]); `None` turns this off
+    pub fn killable(mut self, me: Option<Option<u32>>) -> Self {
+        self.killable = me;
+        self
+    }
+
+    /// Keep only entries no service was recognized for (see
+    /// [`PortEntry::is_unidentified`])
+    pub fn unknown_only(mut self, only: bool) -> Self {
+        self.unknown_only = only;
+        self
+    }
+
+    /// Drop entries matching any of these rules
+    pub fn ignore(mut self, rules: Vec<IgnoreRule>) -> Self {
+        self.ignore = rules;
+        self
+    }
+
+    /// Hidden by the ignore list, regardless of the other criteria
+    pub fn is_ignored(&self, e: &PortEntry) -> bool {
+        self.ignore.iter().any(|rule| rule.matches(e))
+    }
+
+    pub fn matches(&self, e: &PortEntry) -> bool {
+        !(self.hide_loopback && e.is_loopback())
+            && self.min_cpu.is_none_or(|min| e.cpu_percent > min)
+            && self.min_mem.is_none_or(|min| e.memory_mb > min)
+            && self.killable.is_none_or(|me| e.is_killable_by(me))
+            && (!self.unknown_only || e.is_unidentified())
+            && !self.is_ignored(e)
+            && self.matches_text(e)
+    }
+
+    fn matches_text(&self, e: &PortEntry) -> bool {
+        let query = &self.text;
+        query.is_empty()
+            || e.port.to_string().contains(query)
+            || e.process_name.to_lowercase().contains(query)
+            || e.state_display().to_lowercase().contains(query)
+            || e.known_service
+                .map(|s| s.to_lowercase().contains(query))
+                .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_thresholds_are_strict() {
+        let entry = PortEntry {
+            cpu_percent: 5.0,
+            memory_mb: 256.0,
+            ..PortEntry::fixture(3000, 10, "node")
+        };
+        assert!(Filter::new().min_cpu(Some(4.9)).matches(&entry));
+        assert!(!Filter::new().min_cpu(Some(5.0)).matches(&entry));
+        assert!(Filter::new().min_mem(Some(255.9)).matches(&entry));
+        assert!(!Filter::new().min_mem(Some(256.0)).matches(&entry));
+        assert!(Filter::new().min_cpu(None).min_mem(None).matches(&entry));
+    }
+
+    #[test]
+    fn criteria_compose() {
+        let public = |port, pid, name: &str, cpu_percent| PortEntry {
+            local_addr: std::net::Ipv4Addr::UNSPECIFIED.into(),
+            cpu_percent,
+            ..PortEntry::fixture(port, pid, name)
+        };
+        let entries = [
+            // Loopback only
+            PortEntry {
+                cpu_percent: 40.0,
+                ..PortEntry::fixture(3000, 10, "node")
+            },
+            public(3001, 11, "node", 40.0),
+            public(3002, 12, "node", 0.5),
+            public(3003, 13, "node", 40.0),
+            PortEntry {
+                known_service: Some("PostgreSQL"),
+                ..public(5432, 20, "postgres", 40.0)
+            },
+        ];
+        let filter = Filter::new()
+            .text("NODE")
+            .hide_loopback(true)
+            .min_cpu(Some(1.0))
+            .ignore(vec![IgnoreRule::parse("3003:node").unwrap()]);
+        let kept: Vec<u16> = entries
+            .iter()
+            .filter(|e| filter.matches(e))
+            .map(|e| e.port)
+            .collect();
+        assert_eq!(kept, [3001]);
+
+        // Text also matches the service name; the ignore list applies on its own
+        let filter = Filter::new().text("postgresql");
+        assert!(filter.matches(&entries[4]));
+        assert!(!filter.is_ignored(&entries[4]));
+    }
+}
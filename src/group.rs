@@ -0,0 +1,360 @@
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use crate::config::Grouping;
+use crate::models::{memory_display, PortEntry, ServiceCategory};
+
+/// Which entries `kav list` collapses into one summary row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// A browser's helper ports for one service: same PID, same service
+    Browser,
+    /// Worker clusters (gunicorn, PM2, node cluster): one process name
+    /// running as several PIDs under the same parent
+    Workers,
+}
+
+impl Strategy {
+    /// The strategies `[group]` turns on, in the order they're tried
+    pub fn enabled(config: &Grouping) -> Vec<Strategy> {
+        let mut strategies = Vec::new();
+        if config.browsers {
+            strategies.push(Strategy::Browser);
+        }
+        if config.workers {
+            strategies.push(Strategy::Workers);
+        }
+        strategies
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Key<'a> {
+    Browser(u32, &'static str),
+    Workers(&'a str, u32),
+}
+
+/// Entries collapsed under one key; the first one stands for the group
+pub struct Group<'a> {
+    pub strategy: Strategy,
+    pub entries: Vec<&'a PortEntry>,
+}
+
+impl<'a> Group<'a> {
+    pub fn first(&self) -> &'a PortEntry {
+        self.entries[0]
+    }
+
+    /// Distinct PIDs, lowest first
+    pub fn pids(&self) -> Vec<u32> {
+        let pids: BTreeSet<u32> = self.entries.iter().map(|e| e.pid).collect();
+        pids.into_iter().collect()
+    }
+
+    /// Distinct ports, lowest first
+    pub fn ports(&self) -> Vec<u16> {
+        let ports: BTreeSet<u16> = self.entries.iter().map(|e| e.port).collect();
+        ports.into_iter().collect()
+    }
+
+    /// Combined CPU of the group's processes
+    pub fn cpu_percent(&self) -> f32 {
+        per_process(&self.entries).map(|e| e.cpu_percent).sum()
+    }
+
+    /// Combined memory of the group's processes
+    pub fn memory_mb(&self) -> f64 {
+        per_process(&self.entries).map(|e| e.memory_mb).sum()
+    }
+
+    /// How long the oldest process has been running
+    pub fn uptime(&self) -> Duration {
+        self.entries
+            .iter()
+            .map(|e| e.uptime)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Each process once, however many of `entries`' ports it holds
+fn per_process<'a, 'b>(entries: &'b [&'a PortEntry]) -> impl Iterator<Item = &'a PortEntry> + 'b {
+    let mut seen = BTreeSet::new();
+    entries.iter().copied().filter(move |e| seen.insert(e.pid))
+}
+
+/// The parent a worker cluster hangs from: climb through parents of the same
+/// name that are in `by_pid` (a gunicorn master holding the port too), then
+/// take the parent of the topmost one. `None` when the parent isn't known.
+fn cluster_parent(e: &PortEntry, by_pid: &HashMap<u32, &PortEntry>) -> Option<u32> {
+    let mut top = e;
+    let mut walked = vec![e.pid];
+    while let Some(parent) = top
+        .ppid
+        .and_then(|ppid| by_pid.get(&ppid))
+        .filter(|p| p.process_name == e.process_name && !walked.contains(&p.pid))
+    {
+        top = parent;
+        walked.push(top.pid);
+    }
+    top.ppid
+}
+
+/// Split `entries` into rows shown as-is and groups, keeping scan order in
+/// both. Strategies are tried in order and an entry joins the first group
+/// that takes it; a worker group needs at least two PIDs with the same name
+/// and parent, so unrelated processes that share a name stay apart.
+pub fn group<'a>(
+    entries: impl IntoIterator<Item = &'a PortEntry>,
+    strategies: &[Strategy],
+) -> (Vec<&'a PortEntry>, Vec<Group<'a>>) {
+    let entries: Vec<&PortEntry> = entries.into_iter().collect();
+    let mut clusters: HashMap<u32, u32> = HashMap::new();
+    let mut pids_by_cluster: HashMap<(&str, u32), BTreeSet<u32>> = HashMap::new();
+    if strategies.contains(&Strategy::Workers) {
+        let mut by_pid: HashMap<u32, &PortEntry> = HashMap::new();
+        for e in entries.iter().filter(|e| e.pid != 0) {
+            by_pid.entry(e.pid).or_insert(e);
+        }
+        for e in by_pid.values() {
+            if let Some(parent) = cluster_parent(e, &by_pid) {
+                clusters.insert(e.pid, parent);
+                pids_by_cluster
+                    .entry((e.process_name.as_str(), parent))
+                    .or_default()
+                    .insert(e.pid);
+            }
+        }
+    }
+
+    let mut singles = Vec::new();
+    let mut groups: Vec<Group> = Vec::new();
+    let mut index: HashMap<Key, usize> = HashMap::new();
+    for e in entries {
+        let found = strategies.iter().find_map(|&strategy| {
+            let key = match strategy {
+                Strategy::Browser if e.category == ServiceCategory::Browser => {
+                    Key::Browser(e.pid, e.known_service?)
+                }
+                Strategy::Workers => {
+                    let parent = *clusters.get(&e.pid)?;
+                    let pids = pids_by_cluster.get(&(e.process_name.as_str(), parent))?;
+                    if pids.len() < 2 {
+                        return None;
+                    }
+                    Key::Workers(&e.process_name, parent)
+                }
+                _ => return None,
+            };
+            Some((strategy, key))
+        });
+        let Some((strategy, key)) = found else {
+            singles.push(e);
+            continue;
+        };
+        let i = *index.entry(key).or_insert_with(|| {
+            groups.push(Group {
+                strategy,
+                entries: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[i].entries.push(e);
+    }
+    (singles, groups)
+}
+
+/// `list --group-by`: what the table is split into sections by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// One section per service category (dev, database, …)
+    Category,
+    /// One section per owning process
+    Pid,
+}
+
+/// Entries sharing one `--group-by` value, under a subheader
+pub struct Section<'a> {
+    pub title: String,
+    pub entries: Vec<&'a PortEntry>,
+}
+
+impl Section<'_> {
+    /// `3 ports · 4.5% CPU · 512 MB`, counting each process once
+    pub fn subtotal(&self) -> String {
+        let cpu: f32 = per_process(&self.entries).map(|e| e.cpu_percent).sum();
+        let memory_mb: f64 = per_process(&self.entries).map(|e| e.memory_mb).sum();
+        let ports = self.entries.len();
+        format!(
+            "{} port{} · {:.1}% CPU · {}",
+            ports,
+            if ports == 1 { "" } else { "s" },
+            cpu,
+            memory_display(memory_mb)
+        )
+    }
+}
+
+/// Split `entries` into sections, keeping their order inside each one.
+/// Categories come in the usual order (dev first); processes in the order
+/// their first entry appears.
+pub fn sections(entries: &[PortEntry], by: GroupBy) -> Vec<Section<'_>> {
+    match by {
+        GroupBy::Category => ServiceCategory::ALL
+            .iter()
+            .map(|&category| Section {
+                title: category.label().to_string(),
+                entries: entries.iter().filter(|e| e.category == category).collect(),
+            })
+            .filter(|s| !s.entries.is_empty())
+            .collect(),
+        GroupBy::Pid => {
+            let mut sections: Vec<Section> = Vec::new();
+            let mut index: HashMap<u32, usize> = HashMap::new();
+            for e in entries {
+                let i = *index.entry(e.pid).or_insert_with(|| {
+                    let title = if e.pid == 0 {
+                        "no owning process".to_string()
+                    } else {
+                        format!("{} (PID {})", e.process_name, e.pid)
+                    };
+                    sections.push(Section {
+                        title,
+                        entries: Vec::new(),
+                    });
+                    sections.len() - 1
+                });
+                sections[i].entries.push(e);
+            }
+            sections
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worker(port: u16, pid: u32, cpu: f32, mem: f64, uptime_secs: u64) -> PortEntry {
+        PortEntry {
+            cpu_percent: cpu,
+            memory_mb: mem,
+            uptime: Duration::from_secs(uptime_secs),
+            ppid: Some(9),
+            ..PortEntry::fixture(port, pid, "gunicorn")
+        }
+    }
+
+    #[test]
+    fn workers_sum_each_process_once() {
+        let entries = vec![
+            worker(8000, 10, 2.0, 100.0, 60),
+            // The same worker on a second port isn't counted twice
+            worker(8001, 10, 2.0, 100.0, 60),
+            worker(8000, 11, 3.5, 150.0, 600),
+            worker(8000, 12, 0.5, 50.0, 30),
+            PortEntry::fixture(5432, 20, "postgres"),
+        ];
+        let (singles, groups) = group(&entries, &[Strategy::Browser, Strategy::Workers]);
+
+        assert_eq!(singles.len(), 1);
+        assert_eq!(singles[0].port, 5432);
+        let [g] = groups.as_slice() else {
+            panic!("expected one group");
+        };
+        assert_eq!(g.strategy, Strategy::Workers);
+        assert_eq!(g.pids(), [10, 11, 12]);
+        assert_eq!(g.ports(), [8000, 8001]);
+        assert_eq!(g.cpu_percent(), 6.0);
+        assert_eq!(g.memory_mb(), 300.0);
+        assert_eq!(g.uptime(), Duration::from_secs(600));
+    }
+
+    #[test]
+    fn one_pid_is_not_a_cluster() {
+        let entries = vec![
+            worker(8000, 10, 1.0, 10.0, 1),
+            worker(8001, 10, 1.0, 10.0, 1),
+        ];
+        let (singles, groups) = group(&entries, &[Strategy::Workers]);
+        assert_eq!(singles.len(), 2);
+        assert!(groups.is_empty());
+    }
+
+    #[test]
+    fn workers_cluster_under_one_parent() {
+        let child = |port, pid, ppid, name| PortEntry {
+            ppid,
+            ..PortEntry::fixture(port, pid, name)
+        };
+        let entries = vec![
+            // A gunicorn master holding the port with its workers
+            child(8000, 9, Some(2), "gunicorn"),
+            child(8000, 10, Some(9), "gunicorn"),
+            child(8000, 11, Some(9), "gunicorn"),
+            // An unrelated gunicorn from another shell
+            child(8100, 30, Some(3), "gunicorn"),
+            // Two node dev servers with different parents aren't a cluster
+            child(3000, 40, Some(4), "node"),
+            child(5173, 41, Some(5), "node"),
+            // Nor are processes whose parent is unknown
+            child(4000, 50, None, "beam.smp"),
+            child(4001, 51, None, "beam.smp"),
+        ];
+        let (singles, groups) = group(&entries, &[Strategy::Workers]);
+        let clusters: Vec<_> = groups.iter().map(Group::pids).collect();
+        assert_eq!(clusters, [vec![9, 10, 11]]);
+        let ports: Vec<_> = singles.iter().map(|e| e.port).collect();
+        assert_eq!(ports, [8100, 3000, 5173, 4000, 4001]);
+    }
+
+    #[test]
+    fn browser_wins_over_workers_when_tried_first() {
+        let browser = |port, pid| PortEntry {
+            category: ServiceCategory::Browser,
+            known_service: Some("Chrome DevTools"),
+            ppid: Some(1),
+            ..PortEntry::fixture(port, pid, "chrome")
+        };
+        let entries = vec![browser(9222, 30), browser(9223, 30), browser(9229, 31)];
+        let (singles, groups) = group(&entries, &[Strategy::Browser, Strategy::Workers]);
+        assert!(singles.is_empty());
+        let sizes: Vec<_> = groups
+            .iter()
+            .map(|g| (g.strategy, g.entries.len()))
+            .collect();
+        assert_eq!(sizes, [(Strategy::Browser, 2), (Strategy::Browser, 1)]);
+
+        let (_, groups) = group(&entries, &[Strategy::Workers]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].pids(), [30, 31]);
+    }
+
+    #[test]
+    fn sections_keep_order_and_subtotal_per_process() {
+        let db = |port, pid| PortEntry {
+            category: ServiceCategory::Database,
+            ..worker(port, pid, 1.0, 2048.0, 1)
+        };
+        let entries = vec![
+            db(5432, 20),
+            worker(8000, 10, 2.0, 100.0, 1),
+            db(5433, 20),
+            worker(8001, 11, 0.5, 50.0, 1),
+        ];
+
+        let by_category = sections(&entries, GroupBy::Category);
+        let titles: Vec<_> = by_category.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["database", "other"]);
+        assert_eq!(by_category[0].subtotal(), "2 ports · 1.0% CPU · 2.0 GB");
+        assert_eq!(by_category[1].subtotal(), "2 ports · 2.5% CPU · 150 MB");
+
+        let by_pid = sections(&entries, GroupBy::Pid);
+        let ports: Vec<Vec<u16>> = by_pid
+            .iter()
+            .map(|s| s.entries.iter().map(|e| e.port).collect())
+            .collect();
+        assert_eq!(ports, [vec![5432, 5433], vec![8000], vec![8001]]);
+        assert_eq!(by_pid[0].title, "gunicorn (PID 20)");
+    }
+}
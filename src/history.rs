@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, TimeZone, Utc};
+
+use crate::json::{self, Json};
+use crate::models::PortEntry;
+use crate::state;
+
+const FILE_NAME: &str = "first_seen.json";
+
+/// When each `(port, process name)` pair was first and last seen, kept across
+/// runs in the state directory. Unlike process uptime, this survives restarts
+/// of whatever holds the port.
+pub struct FirstSeen {
+    path: Option<PathBuf>,
+    /// `(port, process) -> (first seen, last seen)`, as Unix timestamps
+    seen: HashMap<(u16, String), (i64, i64)>,
+    /// Pairs not seen for this many seconds are forgotten
+    retention_secs: i64,
+    dirty: bool,
+}
+
+impl FirstSeen {
+    /// Load the saved history. A missing or unreadable file starts an empty one;
+    /// it's only a record of sightings, so losing it costs nothing but the dates.
+    pub fn load(forget_after_days: u32) -> Self {
+        let path = state::path(FILE_NAME);
+        let seen = path
+            .as_ref()
+            .and_then(|p| state::load(p, parse).ok().flatten())
+            .unwrap_or_default();
+        Self {
+            path,
+            seen,
+            retention_secs: i64::from(forget_after_days) * 86_400,
+            dirty: false,
+        }
+    }
+
+    /// Record a scan: new pairs start their clock now, known ones are marked
+    /// as seen, and pairs gone longer than the retention period are dropped.
+    pub fn observe(&mut self, entries: &[PortEntry]) {
+        let now = Utc::now().timestamp();
+        for e in entries {
+            match self.seen.get_mut(&(e.port, e.process_name.clone())) {
+                Some((_, last)) => *last = now,
+                None => {
+                    self.seen
+                        .insert((e.port, e.process_name.clone()), (now, now));
+                    self.dirty = true;
+                }
+            }
+        }
+        let before = self.seen.len();
+        let cutoff = now - self.retention_secs;
+        self.seen.retain(|_, (_, last)| *last >= cutoff);
+        self.dirty |= self.seen.len() != before;
+    }
+
+    pub fn first_seen(&self, entry: &PortEntry) -> Option<DateTime<Local>> {
+        let (first, _) = self.seen.get(&(entry.port, entry.process_name.clone()))?;
+        Local.timestamp_opt(*first, 0).single()
+    }
+
+    /// Write the history if a pair was added or dropped since the last save.
+    /// `force` also writes when only last-seen times moved (e.g. on exit).
+    pub fn save(&mut self, force: bool) -> Result<()> {
+        if !self.dirty && !force {
+            return Ok(());
+        }
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut pairs: Vec<_> = self.seen.iter().collect();
+        pairs.sort();
+        let rows: Vec<String> = pairs
+            .iter()
+            .map(|((port, process), (first, last))| {
+                format!(
+                    r#"  {{"port":{},"process":{},"first_seen":{},"last_seen":{}}}"#,
+                    port,
+                    json::quote(process),
+                    first,
+                    last
+                )
+            })
+            .collect();
+        state::save(path, &rows)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+fn parse(text: &str) -> Result<HashMap<(u16, String), (i64, i64)>> {
+    let doc = json::parse(text)?;
+    let items = doc.as_array().context("expected a JSON array")?;
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let port = u16::try_from(item.get("port")?.as_u32()?).ok()?;
+            let process = item.get("process")?.as_str()?.to_string();
+            let first = item.get("first_seen").and_then(Json::as_f64)? as i64;
+            let last = item.get("last_seen").and_then(Json::as_f64)? as i64;
+            Some(((port, process), (first, last)))
+        })
+        .collect())
+}
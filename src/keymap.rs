@@ -0,0 +1,368 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use anyhow::{bail, Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// TUI actions that can be rebound from the `[keys]` config section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Kill,
+    ForceKill,
+    KillTree,
+    Restart,
+    ToggleDetail,
+    ToggleGauges,
+    MessageLog,
+    Export,
+    Peek,
+    Zoom,
+    CycleSort,
+    SortMenu,
+    CycleProtocol,
+    ToggleTree,
+    ToggleCommand,
+    ToggleEstablished,
+    Refresh,
+    Faster,
+    Slower,
+    Filter,
+    Command,
+    PrintSelected,
+    Follow,
+    HideLoopback,
+    ToggleKillable,
+    UnknownOnly,
+    Ignore,
+    Note,
+    Mark,
+    MarkAll,
+    InvertMarks,
+    Up,
+    Down,
+}
+
+impl Action {
+    pub const ALL: [Action; 34] = [
+        Action::Quit,
+        Action::Kill,
+        Action::ForceKill,
+        Action::KillTree,
+        Action::Restart,
+        Action::ToggleDetail,
+        Action::ToggleGauges,
+        Action::MessageLog,
+        Action::Export,
+        Action::Peek,
+        Action::Zoom,
+        Action::CycleSort,
+        Action::SortMenu,
+        Action::CycleProtocol,
+        Action::ToggleTree,
+        Action::ToggleCommand,
+        Action::ToggleEstablished,
+        Action::Refresh,
+        Action::Faster,
+        Action::Slower,
+        Action::Filter,
+        Action::Command,
+        Action::PrintSelected,
+        Action::Follow,
+        Action::HideLoopback,
+        Action::ToggleKillable,
+        Action::UnknownOnly,
+        Action::Ignore,
+        Action::Note,
+        Action::Mark,
+        Action::MarkAll,
+        Action::InvertMarks,
+        Action::Up,
+        Action::Down,
+    ];
+
+    /// Name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Kill => "kill",
+            Action::ForceKill => "force_kill",
+            Action::KillTree => "kill_tree",
+            Action::Restart => "restart",
+            Action::ToggleDetail => "toggle_detail",
+            Action::ToggleGauges => "toggle_gauges",
+            Action::MessageLog => "message_log",
+            Action::Export => "export",
+            Action::Peek => "peek",
+            Action::Zoom => "zoom",
+            Action::CycleSort => "cycle_sort",
+            Action::SortMenu => "sort_menu",
+            Action::CycleProtocol => "cycle_protocol",
+            Action::ToggleTree => "toggle_tree",
+            Action::ToggleCommand => "toggle_command",
+            Action::ToggleEstablished => "toggle_established",
+            Action::Refresh => "refresh",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::Filter => "filter",
+            Action::Command => "command",
+            Action::PrintSelected => "print_selected",
+            Action::Follow => "follow",
+            Action::HideLoopback => "hide_loopback",
+            Action::ToggleKillable => "toggle_killable",
+            Action::UnknownOnly => "unknown_only",
+            Action::Ignore => "ignore",
+            Action::Note => "note",
+            Action::Mark => "mark",
+            Action::MarkAll => "mark_all",
+            Action::InvertMarks => "invert_marks",
+            Action::Up => "up",
+            Action::Down => "down",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    /// Built-in bindings, matching the original hardcoded shortcuts
+    pub fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["ctrl+q", "ctrl+c", "esc"],
+            Action::Kill => &["ctrl+x"],
+            Action::ForceKill => &["ctrl+k"],
+            Action::KillTree => &["T"],
+            Action::Restart => &["R"],
+            Action::ToggleDetail => &["ctrl+d"],
+            Action::ToggleGauges => &["ctrl+g"],
+            Action::MessageLog => &["L"],
+            Action::Export => &["e"],
+            Action::Peek => &["p"],
+            Action::Zoom => &["enter"],
+            Action::CycleSort => &["ctrl+s"],
+            Action::SortMenu => &["S"],
+            Action::CycleProtocol => &["ctrl+t"],
+            Action::ToggleTree => &["ctrl+p"],
+            Action::ToggleCommand => &["ctrl+n"],
+            Action::ToggleEstablished => &["ctrl+e"],
+            Action::Refresh => &["ctrl+r"],
+            Action::Faster => &["+", "="],
+            Action::Slower => &["-"],
+            Action::Filter => &["/"],
+            Action::Command => &[":"],
+            Action::PrintSelected => &["ctrl+y"],
+            Action::Follow => &["f"],
+            Action::HideLoopback => &["ctrl+b"],
+            Action::ToggleKillable => &["ctrl+o"],
+            Action::UnknownOnly => &["u"],
+            Action::Ignore => &["i"],
+            Action::Note => &["n"],
+            Action::Mark => &["space"],
+            Action::MarkAll => &["ctrl+a"],
+            // Most terminals send Ctrl+I as Tab
+            Action::InvertMarks => &["ctrl+i", "tab"],
+            Action::Up => &["up", "k"],
+            Action::Down => &["down", "j"],
+        }
+    }
+}
+
+/// A key plus modifiers, e.g. `ctrl+x`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    code: KeyCode,
+    ctrl: bool,
+    alt: bool,
+}
+
+impl KeyCombo {
+    /// Parse combos like `x`, `X`, `ctrl+x`, `alt+enter`, `esc`, `up`, `f5`
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut rest = s.trim();
+        let mut ctrl = false;
+        let mut alt = false;
+        loop {
+            let lower = rest.to_lowercase();
+            if lower.starts_with("ctrl+") && rest.len() > 5 {
+                ctrl = true;
+                rest = &rest[5..];
+            } else if lower.starts_with("alt+") && rest.len() > 4 {
+                alt = true;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            lower => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                        Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                        _ => bail!("unrecognized key `{}`", s),
+                    },
+                }
+            }
+        };
+
+        Ok(Self { code, ctrl, alt })
+    }
+
+    fn from_event(key: &KeyEvent) -> Self {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let code = match key.code {
+            KeyCode::Char(c) if ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+            other => other,
+        };
+        Self {
+            code,
+            ctrl,
+            alt: key.modifiers.contains(KeyModifiers::ALT),
+        }
+    }
+
+    /// Whether this combo uses a modifier (and so stays active while typing a filter)
+    pub fn is_modified(&self) -> bool {
+        self.ctrl || self.alt
+    }
+
+    /// Terminals with XON/XOFF flow control swallow Ctrl+S and Ctrl+Q
+    fn flow_control_caveat(&self) -> Option<&'static str> {
+        match self.code {
+            KeyCode::Char('s') | KeyCode::Char('q') if self.ctrl && !self.alt => Some(
+                " (note: Ctrl+S/Ctrl+Q are swallowed by terminals with XON/XOFF flow control \
+                 enabled; run `stty -ixon` or pick another key)",
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.alt {
+            write!(f, "M-")?;
+        }
+        if self.ctrl {
+            write!(f, "^")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if self.ctrl => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Backspace => write!(f, "Bksp"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+/// Key bindings consulted by the TUI's key handler
+pub struct KeyMap {
+    bindings: HashMap<KeyCombo, Action>,
+    keys: HashMap<Action, Vec<KeyCombo>>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::from_config(&BTreeMap::new()).expect("default key bindings are valid")
+    }
+}
+
+impl KeyMap {
+    /// Build from the `[keys]` config section. Actions not mentioned keep their
+    /// defaults; binding the same key to two actions is an error.
+    pub fn from_config(overrides: &BTreeMap<String, Vec<String>>) -> Result<Self> {
+        let mut keys: HashMap<Action, Vec<KeyCombo>> = HashMap::new();
+
+        for action in Action::ALL {
+            let combos = action
+                .default_keys()
+                .iter()
+                .map(|k| KeyCombo::parse(k))
+                .collect::<Result<Vec<_>>>()?;
+            keys.insert(action, combos);
+        }
+
+        for (name, combos) in overrides {
+            let action = Action::from_name(name).with_context(|| {
+                let valid: Vec<_> = Action::ALL.iter().map(|a| a.name()).collect();
+                format!(
+                    "unknown action `{}` in [keys] (valid: {})",
+                    name,
+                    valid.join(", ")
+                )
+            })?;
+            if combos.is_empty() {
+                bail!("[keys] {}: at least one key is required", name);
+            }
+            let parsed = combos
+                .iter()
+                .map(|k| KeyCombo::parse(k).with_context(|| format!("[keys] {}", name)))
+                .collect::<Result<Vec<_>>>()?;
+            keys.insert(action, parsed);
+        }
+
+        let mut bindings: HashMap<KeyCombo, Action> = HashMap::new();
+        for action in Action::ALL {
+            for &combo in &keys[&action] {
+                if let Some(other) = bindings.insert(combo, action) {
+                    if other != action {
+                        bail!(
+                            "key `{}` is bound to both `{}` and `{}`{}",
+                            combo,
+                            other.name(),
+                            action.name(),
+                            combo.flow_control_caveat().unwrap_or("")
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(Self { bindings, keys })
+    }
+
+    /// The action bound to a key press, if any
+    pub fn action(&self, key: &KeyEvent) -> Option<(Action, KeyCombo)> {
+        let combo = KeyCombo::from_event(key);
+        self.bindings.get(&combo).map(|&action| (action, combo))
+    }
+
+    /// Short label of an action's primary key, for the status bar
+    pub fn label(&self, action: Action) -> String {
+        self.keys[&action]
+            .first()
+            .map(|combo| combo.to_string())
+            .unwrap_or_default()
+    }
+}
@@ -1,459 +1,1030 @@
+mod alert;
+mod asn;
 mod cli;
+mod config;
+mod diff;
+mod doctor;
+mod filter;
+mod group;
+mod history;
+mod json;
+mod keymap;
 mod models;
+mod notes;
+mod output;
+mod pager;
+mod ports;
+mod remote;
+mod restart;
 mod scanner;
+mod spec;
+mod state;
+mod template;
 mod theme;
+mod tree;
 mod ui;
 mod util;
+mod version;
 
-use std::io::{self, Write};
+use std::collections::HashSet;
+use std::io::{BufRead, IsTerminal, Write};
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use clap::Parser;
-use crossterm::style::{Attribute, Color, ResetColor, SetAttribute, SetForegroundColor};
+use anyhow::{bail, Context, Result};
+use clap::{CommandFactory, Parser};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{self, disable_raw_mode, enable_raw_mode, ClearType},
+};
 
-use cli::{Cli, Command};
-use models::ServiceCategory;
-use scanner::{kill_process, scan_ports};
+use alert::{AlertOn, Alerter};
+use cli::{Cli, Command, ConfigAction, ListFormat, ProtocolArg};
+use config::{Config, IgnoreRule, Protection};
+use filter::Filter;
+use group::Strategy;
+use models::{sort_entries, PortEntry, ServiceCategory};
+use output::{
+    print_alert, print_audit, print_check, print_dev_ports, print_kill_json, print_known_ports,
+    print_outcome, print_privilege_hint, print_remote_hint, print_snapshot_diff, print_table,
+    print_warning, strip_ansi, write_a11y, write_flat_json, write_json, write_json_compact,
+    write_json_meta, write_markdown, write_plain, write_table, TableOptions,
+};
+use restart::Launch;
+use scanner::{current_uid, kill_process, process_parents, scan, scan_ports, ScanOptions};
+use template::Template;
+use theme::{BorderStyle, ThemeOverrides, UdpStyle};
+
+/// How often `list --watch-port` looks at its port
+const WATCH_PORT_INTERVAL: Duration = Duration::from_secs(1);
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    if cli.no_color {
+        crossterm::style::force_color_output(false);
+    }
+    // Commands that need the config refuse to run on a broken one (see also
+    // `kav doctor`); colors just fall back to the defaults, with a warning
+    let mut overrides = match Config::load() {
+        Ok(config) => config.theme,
+        Err(e) => {
+            eprintln!("warning: {:#}; using the default colors", e);
+            ThemeOverrides::default()
+        }
+    };
+    overrides.udp_style = cli.udp_style.or(overrides.udp_style);
+    overrides.border = cli
+        .border
+        .or(cli.ascii.then_some(BorderStyle::Ascii))
+        .or(overrides.border);
+    theme::init_theme(cli.palette, &overrides);
+    if cli.list_known {
+        print_known_ports();
+        return Ok(());
+    }
+    if cli.geoip.is_some() && cli.command.is_some() {
+        // Only the TUI shows remote peers
+        bail!("--geoip only applies to the TUI; run `kav --geoip <PATH>` without a subcommand");
+    }
+    let read_only =
+        cli.read_only || std::env::var_os("KAVAL_READONLY").is_some_and(|v| !v.is_empty());
 
     match cli.command {
         None => {
             // Default: launch TUI
-            ui::run_tui()?;
+            ui::run_tui(&ui::TuiOptions {
+                include_self: cli.include_self,
+                ephemeral_udp: cli.all_udp,
+                include_threads: cli.include_threads,
+                show_inode: cli.show_inode,
+                yolo: cli.yolo,
+                read_only,
+                total_sockets: cli.total_sockets,
+                show_ignored: cli.show_ignored,
+                geoip: cli.geoip.as_deref(),
+                sort_secondary: cli.sort_secondary,
+                ascii: cli.ascii,
+            })?;
         }
 
-        Some(Command::List { json }) => {
-            let entries = scan_ports(true, true)?;
-            if json {
-                print_json(&entries)?;
+        Some(Command::List {
+            json,
+            meta,
+            plain,
+            flat_json,
+            protocol,
+            sort,
+            filter,
+            exposed,
+            min_cpu,
+            min_mem,
+            killable,
+            unknown_only,
+            host,
+            template,
+            format,
+            wide,
+            expand,
+            group_by,
+            max_width,
+            a11y,
+            no_header,
+            every,
+            digest,
+            watch_port,
+            pager,
+            output,
+            alert_on,
+        }) => {
+            let config = Config::load()?;
+            let grouping = if expand {
+                Vec::new()
+            } else {
+                Strategy::enabled(&config.group)
+            };
+            let table = TableOptions {
+                wide,
+                columns: &config.columns,
+                max_width: max_width.map(usize::from),
+                pager,
+                grouping: &grouping,
+                group_by,
+                watched: &config.watch.ports,
+            };
+            // Parse up front so a bad template fails before any scanning
+            let template = template.as_deref().map(Template::parse).transpose()?;
+            let protocol = protocol.unwrap_or(match theme::theme().udp_style {
+                UdpStyle::Hide => ProtocolArg::Tcp,
+                _ => ProtocolArg::Both,
+            });
+            let (tcp, udp) = protocol.flags();
+            let opts = ScanOptions {
+                tcp,
+                udp,
+                include_self: cli.include_self,
+                ephemeral_udp: cli.all_udp,
+                total_sockets: cli.total_sockets,
+                ..ScanOptions::default()
+            };
+            let me = killable.then(current_uid);
+            if me == Some(None) {
+                eprintln!("note: process owners can't be read here; --killable keeps every entry");
+            }
+            let filter = Filter::new()
+                .text(filter.as_deref().unwrap_or_default())
+                .hide_loopback(exposed)
+                .min_cpu(min_cpu)
+                .min_mem(min_mem)
+                .killable(me)
+                .unknown_only(unknown_only)
+                .ignore(ignore_rules(&config, cli.show_ignored));
+            if let Some(port) = watch_port {
+                // A named port is wanted even if it looks like a UDP client socket
+                let opts = ScanOptions {
+                    ephemeral_udp: true,
+                    ..opts
+                };
+                list_watch_port(&opts, host.as_deref(), &filter, port)?;
+                return Ok(());
+            }
+            if let Some(secs) = digest {
+                list_digest(
+                    &opts,
+                    host.as_deref(),
+                    &filter,
+                    Duration::from_secs(secs),
+                    alert_on,
+                )?;
+                return Ok(());
+            }
+            if let Some(secs) = every {
+                list_every(
+                    &opts,
+                    host.as_deref(),
+                    &filter,
+                    Duration::from_secs(secs),
+                    alert_on,
+                    &table,
+                )?;
+                return Ok(());
+            }
+            let (mut result, limited) = match host.as_deref() {
+                Some(host) => {
+                    let remote = remote::scan(host, &opts)?;
+                    (remote.result, remote.limited)
+                }
+                None => (scan(&opts)?, false),
+            };
+            let ignored = result
+                .entries
+                .iter()
+                .filter(|e| filter.is_ignored(e))
+                .count();
+            result.entries.retain(|e| filter.matches(e));
+            sort_entries(&mut result.entries, sort, cli.sort_secondary);
+            config.watch.float_to_top(&mut result.entries);
+            let mut w = Vec::new();
+            if let Some(template) = &template {
+                for e in &result.entries {
+                    writeln!(w, "{}", template.render(e))?;
+                }
+            } else if json && meta {
+                write_json_meta(&mut w, &result)?;
+            } else if json {
+                write_json(&mut w, &result.entries)?;
+            } else if flat_json {
+                write_flat_json(&mut w, &result.entries)?;
+            } else if plain {
+                write_plain(&mut w, &result.entries, !no_header)?;
+            } else if format == Some(ListFormat::JsonCompact) {
+                write_json_compact(&mut w, &result.entries)?;
+            } else if format == Some(ListFormat::Markdown) {
+                write_markdown(&mut w, &result.entries, wide)?;
+            } else if a11y {
+                write_a11y(&mut w, &result.entries, result.unresolved, ignored)?;
+            } else if output.is_some() {
+                write_table(
+                    &mut w,
+                    &result.entries,
+                    result.unresolved,
+                    ignored,
+                    result.total_sockets,
+                    result.hidden_udp,
+                    &table,
+                )?;
             } else {
-                print_table(&entries);
+                print_table(
+                    &result.entries,
+                    result.unresolved,
+                    ignored,
+                    result.total_sockets,
+                    result.hidden_udp,
+                    &table,
+                );
+                if result.underprivileged {
+                    print_privilege_hint();
+                }
+                if let (Some(host), true) = (&host, limited) {
+                    print_remote_hint(host);
+                }
+            }
+            match &output {
+                Some(path) => {
+                    // A file never gets color codes, whichever format it holds
+                    let text = strip_ansi(&String::from_utf8_lossy(&w));
+                    write_output(path, text.as_bytes())?;
+                    println!("Wrote {}", path.display());
+                }
+                None => std::io::stdout().write_all(&w)?,
             }
         }
 
-        Some(Command::Check { port }) => {
-            let entries = scan_ports(true, true)?;
+        Some(Command::Check { port, json, from }) => {
+            // Read the spec before scanning, so a typo fails fast
+            let spec = match &from {
+                Some(path) => {
+                    let text = std::fs::read_to_string(path)
+                        .with_context(|| format!("reading {}", path.display()))?;
+                    Some(spec::parse(&text).with_context(|| format!("in {}", path.display()))?)
+                }
+                None => None,
+            };
+            let entries = scan(&ScanOptions {
+                include_threads: cli.include_threads,
+                ..ScanOptions::for_named_port()
+            })?
+            .entries;
+            let Some(port) = port else {
+                // clap requires one of the two
+                let spec = spec.context("expected a port or --from")?;
+                if !check_spec(&spec, &entries) {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            };
             let matches: Vec<_> = entries.iter().filter(|e| e.port == port).collect();
-            if matches.is_empty() {
+            if json {
+                let matches: Vec<PortEntry> = matches.into_iter().cloned().collect();
+                write_json(&mut std::io::stdout(), &matches)?;
+            } else if matches.is_empty() {
                 println!("Nothing listening on port {}", port);
             } else {
                 for entry in &matches {
-                    println!(
-                        "Port {} ({}) — {} (PID {}){}",
-                        entry.port,
-                        entry.protocol,
-                        entry.process_name,
-                        entry.pid,
-                        entry
-                            .known_service
-                            .map(|s| format!(" [{}]", s))
-                            .unwrap_or_default(),
-                    );
-                    if !entry.process_cmd.is_empty() {
-                        println!("  Command: {}", entry.process_cmd);
-                    }
-                    println!(
-                        "  CPU: {:.1}%  Memory: {}  Uptime: {}",
-                        entry.cpu_percent,
-                        entry.memory_display(),
-                        entry.uptime_display()
-                    );
+                    print_check(entry, cli.include_threads, cli.show_inode);
                 }
             }
         }
 
-        Some(Command::Kill { port, force }) => {
-            let entries = scan_ports(true, true)?;
-            let matches: Vec<_> = entries.iter().filter(|e| e.port == port).collect();
-            if matches.is_empty() {
-                println!("Nothing listening on port {}", port);
+        Some(Command::Kill {
+            target,
+            category,
+            force,
+            tree,
+            json,
+        }) => {
+            if read_only {
+                bail!("read-only mode: `kav kill` is disabled (--read-only or KAVAL_READONLY)");
+            }
+            let protect = Config::load()?.protect;
+            let entries = scan(&ScanOptions::for_named_port())?.entries;
+            let Some(target) = target else {
+                // clap requires one of the two
+                let category = category.context("expected a port or --category")?;
+                if !kill_category(category, &entries, &protect, force)? {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            };
+            let matches: Vec<_> = entries.iter().filter(|e| target.matches(e)).collect();
+            if json {
+                if !kill_json(target.port, &matches, &protect, force) {
+                    std::process::exit(1);
+                }
+            } else if matches.is_empty() {
+                println!("Nothing listening on {}", target);
+            } else if tree {
+                if !kill_tree(&matches, &protect, force)? {
+                    std::process::exit(1);
+                }
             } else {
-                for entry in &matches {
+                // Each bind is its own entry; a process holding several is one kill
+                let mut pids = HashSet::new();
+                for entry in matches.iter().filter(|e| pids.insert(e.pid)) {
+                    // Protected entries always need the typed confirmation, even with --force
+                    if protect.covers(entry) && !confirm_protected(entry)? {
+                        println!("Skipped {} (PID {})", entry.process_name, entry.pid);
+                        continue;
+                    }
                     if !force {
-                        println!(
+                        print_warning(&format!(
                             "Killing {} (PID {}) on port {}...",
                             entry.process_name, entry.pid, entry.port
-                        );
+                        ));
+                    }
+                    if let Err(e) = kill_process(entry.pid, force) {
+                        print_outcome(false, &format!("Kill failed: {:#}", e));
+                        std::process::exit(1);
                     }
-                    kill_process(entry.pid, force)?;
-                    println!(
-                        "{}Killed {} (PID {})",
-                        if force { "Force " } else { "" },
-                        entry.process_name,
-                        entry.pid
+                    print_outcome(
+                        true,
+                        &format!(
+                            "{}Killed {} (PID {})",
+                            if force { "Force " } else { "" },
+                            entry.process_name,
+                            entry.pid
+                        ),
                     );
                 }
             }
         }
+
+        Some(Command::Restart { port, yes }) => {
+            if read_only {
+                bail!("read-only mode: `kav restart` is disabled (--read-only or KAVAL_READONLY)");
+            }
+            let protect = Config::load()?.protect;
+            let entries = scan(&ScanOptions::for_named_port())?.entries;
+            // A TCP and a UDP socket from the same process are still one restart
+            let mut pids = HashSet::new();
+            let matches: Vec<_> = entries
+                .iter()
+                .filter(|e| e.port == port && pids.insert(e.pid))
+                .collect();
+            let entry = match matches.as_slice() {
+                [] => {
+                    println!("Nothing listening on port {}", port);
+                    return Ok(());
+                }
+                [entry] => *entry,
+                _ => bail!(
+                    "port {} is held by {} processes; restart them one at a time",
+                    port,
+                    matches.len()
+                ),
+            };
+
+            let launch = Launch::capture(entry.pid)?;
+            println!(
+                "Restarting {} (PID {}) on port {} re-runs its command verbatim:",
+                entry.process_name, entry.pid, port
+            );
+            println!("  {}", launch.command_line());
+            if let Some(cwd) = &launch.cwd {
+                println!("  in {}", cwd.display());
+            }
+            // Protected entries always need the typed confirmation, even with --yes
+            let confirmed = if protect.covers(entry) {
+                confirm_protected(entry)?
+            } else {
+                yes || confirm("Continue?", "--yes")?
+            };
+            if !confirmed {
+                println!("Skipped {} (PID {})", entry.process_name, entry.pid);
+                return Ok(());
+            }
+            let new_pid = restart::restart(entry, &launch)?;
+            println!(
+                "Restarted {} on port {} as PID {} (output is discarded)",
+                entry.process_name, port, new_pid
+            );
+        }
+
+        Some(Command::Dev) => {
+            let mut ports = util::DEV_PORTS.to_vec();
+            ports.extend(Config::load()?.dev_ports);
+            ports.sort_unstable();
+            ports.dedup();
+            let entries = scan_ports(true, true)?;
+            print_dev_ports(&ports, &entries);
+        }
+
+        Some(Command::Diff { before, after }) => {
+            let read = |path: &std::path::Path| -> Result<Vec<diff::SnapshotEntry>> {
+                let text = std::fs::read_to_string(path)
+                    .with_context(|| format!("reading {}", path.display()))?;
+                diff::parse_snapshot(&text).with_context(|| format!("in {}", path.display()))
+            };
+            let changes = diff::compare_snapshots(&read(&before)?, &read(&after)?);
+            print_snapshot_diff(&changes);
+        }
+
+        Some(Command::Doctor) => {
+            doctor::run()?;
+        }
+
+        Some(Command::Version) => {
+            version::print()?;
+        }
+
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Cli::command(), "kav", &mut std::io::stdout());
+        }
+
+        Some(Command::Config { action }) => {
+            let path = config::config_path().context("no config location (HOME is not set)")?;
+            match action {
+                ConfigAction::Init { force } => {
+                    config::init(force)?;
+                    println!("Wrote {}", path.display());
+                }
+                ConfigAction::Path => {
+                    println!("{}", path.display());
+                    if !path.exists() {
+                        eprintln!("(not present; using defaults)");
+                    }
+                }
+                ConfigAction::Validate => {
+                    if !path.exists() {
+                        println!("No config at {} (using defaults)", path.display());
+                    } else {
+                        let config = Config::load()?;
+                        keymap::KeyMap::from_config(&config.keys)
+                            .with_context(|| format!("in {}", path.display()))?;
+                        println!("{} is valid", path.display());
+                    }
+                }
+            }
+        }
+
+        Some(Command::Audit) => {
+            let entries = scan_ports(true, true)?;
+            print_audit(&entries);
+        }
     }
 
     Ok(())
 }
 
-fn category_color(cat: ServiceCategory) -> Color {
-    match cat {
-        ServiceCategory::DevServer => Color::Rgb {
-            r: 34,
-            g: 197,
-            b: 94,
-        }, // green
-        ServiceCategory::Database => Color::Rgb {
-            r: 234,
-            g: 179,
-            b: 8,
-        }, // yellow
-        ServiceCategory::Cache => Color::Rgb {
-            r: 168,
-            g: 85,
-            b: 247,
-        }, // purple
-        ServiceCategory::Container => Color::Rgb {
-            r: 96,
-            g: 165,
-            b: 250,
-        }, // blue
-        ServiceCategory::Browser => Color::Rgb {
-            r: 251,
-            g: 146,
-            b: 60,
-        }, // orange
-        ServiceCategory::System => Color::Rgb {
-            r: 140,
-            g: 140,
-            b: 145,
-        }, // gray
-        ServiceCategory::Unknown => Color::Rgb {
-            r: 100,
-            g: 100,
-            b: 105,
-        }, // dim gray
-    }
+/// `list --every`: clear and reprint the table on an interval. Raw mode is only
+/// enabled while waiting, so Ctrl-C arrives as a key and the cursor gets restored.
+fn list_every(
+    opts: &ScanOptions,
+    host: Option<&str>,
+    filter: &Filter,
+    interval: Duration,
+    alert_on: Option<AlertOn>,
+    table: &TableOptions,
+) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    execute!(stdout, cursor::Hide)?;
+
+    let mut alerter: Option<Alerter> = None;
+    // The screen is cleared on every redraw, so the latest alert stays under the table
+    let mut last_alert: Option<String> = None;
+    let result = (|| -> Result<()> {
+        loop {
+            let mut result = scan_from(opts, host)?;
+            let ignored = result
+                .entries
+                .iter()
+                .filter(|e| filter.is_ignored(e))
+                .count();
+            result.entries.retain(|e| filter.matches(e));
+            let mut bell = false;
+            match (&mut alerter, alert_on) {
+                (Some(alerter), _) => {
+                    if let Some(alert) = alerter.check(&result.entries) {
+                        bell = alert.bell;
+                        last_alert = Some(format!(
+                            "{}  {}",
+                            chrono::Local::now().format("%H:%M:%S"),
+                            alert.message
+                        ));
+                    }
+                }
+                (None, Some(on)) => alerter = Some(Alerter::new(on, &result.entries)),
+                (None, None) => {}
+            }
+            execute!(
+                stdout,
+                terminal::Clear(ClearType::All),
+                terminal::Clear(ClearType::Purge),
+                cursor::MoveTo(0, 0)
+            )?;
+            print_table(
+                &result.entries,
+                result.unresolved,
+                ignored,
+                result.total_sockets,
+                result.hidden_udp,
+                table,
+            );
+            if result.underprivileged {
+                print_privilege_hint();
+            }
+            println!(
+                "  Every {}s · {} · q or Ctrl-C to exit",
+                interval.as_secs(),
+                chrono::Local::now().format("%H:%M:%S")
+            );
+            if let Some(line) = &last_alert {
+                print_alert(line, bell);
+            }
+
+            if wait_for_quit(interval)? {
+                return Ok(());
+            }
+        }
+    })();
+
+    execute!(stdout, cursor::Show)?;
+    result
 }
 
-fn proto_color(proto: models::Protocol) -> Color {
-    match proto {
-        models::Protocol::Tcp => Color::Rgb {
-            r: 6,
-            g: 182,
-            b: 212,
-        }, // cyan
-        models::Protocol::Udp => Color::Rgb {
-            r: 100,
-            g: 100,
-            b: 105,
-        }, // dim
+/// `list --digest`: re-scan on an interval and print one line per interval
+/// summarizing what appeared and disappeared, by category.
+fn list_digest(
+    opts: &ScanOptions,
+    host: Option<&str>,
+    filter: &Filter,
+    interval: Duration,
+    alert_on: Option<AlertOn>,
+) -> Result<()> {
+    let scan_kept = || -> Result<Vec<PortEntry>> {
+        let mut entries = scan_from(opts, host)?.entries;
+        entries.retain(|e| filter.matches(e));
+        Ok(entries)
+    };
+    let mut previous = scan_kept()?;
+    let mut alerter = alert_on.map(|on| Alerter::new(on, &previous));
+    println!(
+        "Watching {} ports; digest every {}s (q or Ctrl-C to exit)",
+        previous.len(),
+        interval.as_secs()
+    );
+
+    while !wait_for_quit(interval)? {
+        let current = scan_kept()?;
+        println!(
+            "{}  {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            diff::digest_line(&diff::category_changes(&previous, &current))
+        );
+        if let Some(alert) = alerter.as_mut().and_then(|a| a.check(&current)) {
+            print_alert(
+                &format!(
+                    "{}  {}",
+                    chrono::Local::now().format("%H:%M:%S"),
+                    alert.message
+                ),
+                alert.bell,
+            );
+        }
+        previous = current;
     }
+    Ok(())
 }
 
-/// Grouped browser entry for display
-struct BrowserGroup {
-    service: &'static str,
-    category: ServiceCategory,
-    protocol: models::Protocol,
-    pid: u32,
-    process_name: String,
-    cpu_percent: f32,
-    memory_mb: f64,
-    uptime: std::time::Duration,
-    count: usize,
+/// `list --output`: write `contents` to `path`, creating missing parent
+/// directories first
+fn write_output(path: &std::path::Path, contents: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    std::fs::write(path, contents).with_context(|| format!("writing {}", path.display()))
 }
 
-/// Row to render — either a single entry or a grouped browser
-enum DisplayRow<'a> {
-    Single(&'a models::PortEntry),
-    Grouped(BrowserGroup),
+/// `list --watch-port`: poll one port and print a line whenever the set of
+/// processes holding it (among those `filter` keeps) changes.
+fn list_watch_port(
+    opts: &ScanOptions,
+    host: Option<&str>,
+    filter: &Filter,
+    port: u16,
+) -> Result<()> {
+    let holders = || -> Result<Vec<(u32, String)>> {
+        let mut held: Vec<(u32, String)> = scan_from(opts, host)?
+            .entries
+            .into_iter()
+            .filter(|e| e.port == port && filter.matches(e))
+            .map(|e| (e.pid, e.process_name))
+            .collect();
+        // IPv4/IPv6 and TCP/UDP sockets of one process are one holder
+        held.sort();
+        held.dedup();
+        Ok(held)
+    };
+    let describe = |held: &[(u32, String)]| -> String {
+        held.iter()
+            .map(|(pid, name)| format!("{} (PID {})", name, pid))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    println!("Watching port {} (q or Ctrl-C to exit)", port);
+    let mut previous = holders()?;
+    let stamp = || chrono::Local::now().format("%H:%M:%S");
+    if previous.is_empty() {
+        println!("{}  {} is free", stamp(), port);
+    } else {
+        println!("{}  {} held by {}", stamp(), port, describe(&previous));
+    }
+
+    while !wait_for_quit(WATCH_PORT_INTERVAL)? {
+        let current = holders()?;
+        if current == previous {
+            continue;
+        }
+        if current.is_empty() {
+            println!(
+                "{}  {} is free (was {})",
+                stamp(),
+                port,
+                describe(&previous)
+            );
+        } else if previous.is_empty() {
+            println!("{}  {} now held by {}", stamp(), port, describe(&current));
+        } else {
+            println!(
+                "{}  {} now held by {} (was {})",
+                stamp(),
+                port,
+                describe(&current),
+                describe(&previous)
+            );
+        }
+        previous = current;
+    }
+    Ok(())
 }
 
-fn print_table(entries: &[models::PortEntry]) {
-    if entries.is_empty() {
-        println!("No listening ports found.");
-        return;
+/// Scan this machine, or `host` over SSH
+fn scan_from(opts: &ScanOptions, host: Option<&str>) -> Result<scanner::ScanResult> {
+    match host {
+        Some(host) => Ok(remote::scan(host, opts)?.result),
+        None => scan(opts),
     }
+}
 
-    // Build display rows: group browser entries by (pid, service)
-    let mut rows: Vec<DisplayRow> = Vec::new();
-    let mut browser_groups: std::collections::HashMap<
-        (u32, &'static str),
-        Vec<&models::PortEntry>,
-    > = std::collections::HashMap::new();
-
-    for e in entries {
-        if e.category == ServiceCategory::Browser {
-            if let Some(svc) = e.known_service {
-                browser_groups.entry((e.pid, svc)).or_default().push(e);
-                continue;
+/// Wait up to `timeout`; true if the user pressed q, Esc or Ctrl-C meanwhile.
+fn wait_for_quit(timeout: Duration) -> Result<bool> {
+    enable_raw_mode()?;
+    let deadline = Instant::now() + timeout;
+    let quit = (|| -> Result<bool> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || !event::poll(remaining)? {
+                return Ok(false);
+            }
+            if let Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) = event::read()?
+            {
+                match code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(true)
+                    }
+                    _ => {}
+                }
             }
         }
-        rows.push(DisplayRow::Single(e));
+    })();
+    disable_raw_mode()?;
+    quit
+}
+
+/// The config's ignore list, or none with `--show-ignored`
+fn ignore_rules(config: &Config, show_ignored: bool) -> Vec<IgnoreRule> {
+    if show_ignored {
+        return Vec::new();
+    }
+    config.ignore.clone()
+}
+
+/// `kill --json`: kill each owning process once and print the outcome as JSON.
+/// Returns whether every kill succeeded.
+fn kill_json(port: u16, matches: &[&PortEntry], protect: &Protection, force: bool) -> bool {
+    let mut killed = Vec::new();
+    let mut failed = Vec::new();
+    let mut seen = HashSet::new();
+    for entry in matches {
+        // IPv4 and IPv6 sockets of one process are one kill
+        if !seen.insert(entry.pid) {
+            continue;
+        }
+        let outcome = if protect.covers(entry) {
+            Err(anyhow::anyhow!(
+                "port {} is protected; kill it interactively to confirm",
+                port
+            ))
+        } else {
+            kill_process(entry.pid, force)
+        };
+        match outcome {
+            Ok(()) => killed.push((entry.pid, entry.process_name.as_str())),
+            Err(e) => failed.push((entry.pid, format!("{:#}", e))),
+        }
     }
+    print_kill_json(port, &killed, &failed);
+    failed.is_empty()
+}
 
-    // Convert browser groups to display rows, sorted by first entry's port
-    let mut grouped: Vec<DisplayRow> = browser_groups
-        .into_iter()
-        .map(|((pid, svc), group)| {
-            let first = group[0];
-            DisplayRow::Grouped(BrowserGroup {
-                service: svc,
-                category: first.category,
-                protocol: first.protocol,
-                pid,
-                process_name: first.process_name.clone(),
-                cpu_percent: first.cpu_percent,
-                memory_mb: first.memory_mb,
-                uptime: first.uptime,
-                count: group.len(),
-            })
-        })
-        .collect();
-    // Sort grouped rows by process name for consistent ordering
-    grouped.sort_by(|a, b| {
-        let a_name = match a {
-            DisplayRow::Grouped(g) => &g.process_name,
-            _ => unreachable!(),
+/// `check --from`: one line per expectation, then a tally. Returns whether
+/// every port was as expected.
+fn check_spec(spec: &[spec::Expectation], entries: &[PortEntry]) -> bool {
+    let mut met = 0;
+    for expected in spec {
+        let outcome = spec::check(expected, entries);
+        let held_by = |e: &PortEntry| {
+            format!(
+                "{} (PID {}){}",
+                e.process_name,
+                e.pid,
+                e.known_service
+                    .map(|svc| format!(" [{}]", svc))
+                    .unwrap_or_default()
+            )
         };
-        let b_name = match b {
-            DisplayRow::Grouped(g) => &g.process_name,
-            _ => unreachable!(),
+        let line = match &outcome {
+            spec::Outcome::Present(e) => format!("✓ {} — {}", expected.label(), held_by(e)),
+            spec::Outcome::Absent => format!("✗ {} — nothing listening", expected.label()),
+            spec::Outcome::WrongService(e) => {
+                format!("✗ {} — held by {}", expected.label(), held_by(e))
+            }
         };
-        a_name.cmp(b_name)
-    });
-    rows.extend(grouped);
+        if outcome.is_ok() {
+            met += 1;
+        }
+        print_outcome(outcome.is_ok(), &line);
+    }
+    println!("{} of {} as expected", met, spec.len());
+    met == spec.len()
+}
 
-    let out = io::stdout();
-    let mut w = out.lock();
+/// `kill --category`: list every process in `category`, confirm once (unless
+/// `force`), then kill each and print a tally. Returns whether every kill succeeded.
+fn kill_category(
+    category: ServiceCategory,
+    entries: &[PortEntry],
+    protect: &Protection,
+    force: bool,
+) -> Result<bool> {
+    // One target per process, with all the ports it holds
+    let mut targets: Vec<(&PortEntry, Vec<u16>)> = Vec::new();
+    for e in entries
+        .iter()
+        .filter(|e| e.category == category && e.pid != 0)
+    {
+        match targets.iter_mut().find(|(t, _)| t.pid == e.pid) {
+            Some((_, ports)) if !ports.contains(&e.port) => ports.push(e.port),
+            Some(_) => {}
+            None => targets.push((e, vec![e.port])),
+        }
+    }
+    if targets.is_empty() {
+        println!("No {} processes listening", category.label());
+        return Ok(true);
+    }
 
-    let hdr = Color::Rgb {
-        r: 120,
-        g: 120,
-        b: 125,
-    };
-    let dim = Color::Rgb {
-        r: 100,
-        g: 100,
-        b: 105,
-    };
-    let divider = Color::Rgb {
-        r: 60,
-        g: 60,
-        b: 65,
-    };
-    let light = Color::Rgb {
-        r: 160,
-        g: 160,
-        b: 165,
-    };
+    print_warning(&format!(
+        "{} {} {} {}:",
+        if force { "Force killing" } else { "Will kill" },
+        targets.len(),
+        category.label(),
+        if targets.len() == 1 {
+            "process"
+        } else {
+            "processes"
+        }
+    ));
+    for (e, ports) in &targets {
+        let ports: Vec<String> = ports.iter().map(|p| p.to_string()).collect();
+        println!(
+            "  {} (PID {}) on port {}{}",
+            e.process_name,
+            e.pid,
+            ports.join(", "),
+            if protect.covers(e) {
+                "  [protected]"
+            } else {
+                ""
+            }
+        );
+    }
+    if !force && !confirm("Kill them all?", "--force")? {
+        println!("Nothing killed");
+        return Ok(true);
+    }
 
-    // Header
-    let _ = writeln!(
-        w,
-        "{}{}  {:<6} {:<5} {:<22} {:<20} {:<7} {:<7} {:<9} UPTIME{}{}",
-        SetForegroundColor(hdr),
-        SetAttribute(Attribute::Bold),
-        "PORT",
-        "PROTO",
-        "PROCESS",
-        "SERVICE",
-        "PID",
-        "CPU",
-        "MEM",
-        SetAttribute(Attribute::Reset),
-        ResetColor,
-    );
-    let _ = writeln!(
-        w,
-        "{}{}{}",
-        SetForegroundColor(divider),
-        "─".repeat(100),
-        ResetColor,
+    let mut killed = 0;
+    let mut failed = 0;
+    for (e, _) in &targets {
+        // Protected entries always need the typed confirmation, even with --force
+        if protect.covers(e) && !confirm_protected(e)? {
+            println!("Skipped {} (PID {})", e.process_name, e.pid);
+            continue;
+        }
+        match kill_process(e.pid, force) {
+            Ok(()) => {
+                killed += 1;
+                print_outcome(true, &format!("Killed {} (PID {})", e.process_name, e.pid));
+            }
+            Err(err) => {
+                failed += 1;
+                print_outcome(
+                    false,
+                    &format!("Failed {} (PID {}): {:#}", e.process_name, e.pid, err),
+                );
+            }
+        }
+    }
+    print_outcome(
+        failed == 0,
+        &format!(
+            "Killed {} of {} {} processes{}",
+            killed,
+            targets.len(),
+            category.label(),
+            if failed > 0 {
+                format!(" ({} failed)", failed)
+            } else {
+                String::new()
+            }
+        ),
     );
+    Ok(failed == 0)
+}
 
-    for row in &rows {
-        match row {
-            DisplayRow::Single(e) => {
-                let cat_col = category_color(e.category);
-                let port_col = if e.known_service.is_some() {
-                    Color::White
-                } else {
-                    light
+/// `kill --tree`: list each owner with every process below it, confirm once
+/// (unless `force`), then kill them children first and report each PID.
+/// Returns whether every kill succeeded and no tree was refused.
+fn kill_tree(matches: &[&PortEntry], protect: &Protection, force: bool) -> Result<bool> {
+    let parents = process_parents();
+    let mut owners = HashSet::new();
+    let mut pids = HashSet::new();
+    let mut refused = false;
+    // (pid, name, the port owner it belongs under); each owner comes last in its group
+    let mut targets: Vec<(u32, String, &PortEntry)> = Vec::new();
+    for entry in matches
+        .iter()
+        .filter(|e| e.pid != 0 && owners.insert(e.pid))
+    {
+        let subtree = tree::subtree(entry.pid, &parents, std::process::id());
+        if subtree.is_empty() {
+            refused = true;
+            print_outcome(
+                false,
+                &format!(
+                    "Not killing the tree of {} (PID {}): it is init or runs kav itself",
+                    entry.process_name, entry.pid
+                ),
+            );
+        }
+        for pid in subtree {
+            if pids.insert(pid) {
+                let name = match parents.get(&pid) {
+                    Some(node) => node.name.clone(),
+                    None => entry.process_name.clone(),
                 };
+                targets.push((pid, name, *entry));
+            }
+        }
+    }
+    if targets.is_empty() {
+        if !refused {
+            println!("No process to kill on port {}", matches[0].port);
+        }
+        return Ok(!refused);
+    }
 
-                let _ = write!(
-                    w,
-                    "{}{}  {:<6}{}",
-                    SetForegroundColor(port_col),
-                    SetAttribute(Attribute::Bold),
-                    e.port,
-                    SetAttribute(Attribute::Reset)
-                );
-                let _ = write!(
-                    w,
-                    "{} {:<5}",
-                    SetForegroundColor(proto_color(e.protocol)),
-                    e.protocol
-                );
-                let _ = write!(
-                    w,
-                    "{} {:<22}",
-                    SetForegroundColor(cat_col),
-                    truncate(&e.process_name, 22)
-                );
-
-                let service = e.known_service.unwrap_or("");
-                if service.is_empty() {
-                    let _ = write!(w, "{} {:<20}", SetForegroundColor(divider), "·");
-                } else {
-                    let _ = write!(
-                        w,
-                        "{}{} {:<20}{}",
-                        SetForegroundColor(cat_col),
-                        SetAttribute(Attribute::Bold),
-                        service,
-                        SetAttribute(Attribute::Reset)
-                    );
-                }
+    print_warning(&format!(
+        "{} {} {}, children first:",
+        if force { "Force killing" } else { "Will kill" },
+        targets.len(),
+        if targets.len() == 1 {
+            "process"
+        } else {
+            "processes"
+        }
+    ));
+    for (pid, name, owner) in &targets {
+        let role = if *pid == owner.pid {
+            format!("  owns port {}", owner.port)
+        } else {
+            String::new()
+        };
+        println!("  {} (PID {}){}", name, pid, role);
+    }
+    if !force && !confirm("Kill them all?", "--force")? {
+        println!("Nothing killed");
+        return Ok(true);
+    }
+    // Protected owners always need the typed confirmation, even with --force
+    let mut skipped = HashSet::new();
+    for owner in matches.iter().filter(|e| owners.contains(&e.pid)) {
+        if protect.covers(owner) && !skipped.contains(&owner.pid) && !confirm_protected(owner)? {
+            skipped.insert(owner.pid);
+        }
+    }
 
-                let _ = write!(w, "{} {:<7}", SetForegroundColor(dim), e.pid);
-                let cpu_str = format!("{:.1}%", e.cpu_percent);
-                let cpu_col = if e.cpu_percent > 50.0 {
-                    Color::Rgb {
-                        r: 239,
-                        g: 68,
-                        b: 68,
-                    }
-                } else if e.cpu_percent > 10.0 {
-                    Color::Rgb {
-                        r: 234,
-                        g: 179,
-                        b: 8,
-                    }
-                } else {
-                    dim
-                };
-                let _ = write!(w, "{} {:<7}", SetForegroundColor(cpu_col), cpu_str);
-                let _ = write!(w, "{} {:<9}", SetForegroundColor(light), e.memory_display());
-                let _ = write!(w, "{} {}", SetForegroundColor(dim), e.uptime_display());
-                let _ = writeln!(w, "{}", ResetColor);
+    let mut killed = 0;
+    let mut failed = 0;
+    for (pid, name, owner) in &targets {
+        if skipped.contains(&owner.pid) {
+            println!("Skipped {} (PID {})", name, pid);
+            continue;
+        }
+        match kill_process(*pid, force) {
+            Ok(()) => {
+                killed += 1;
+                print_outcome(true, &format!("Killed {} (PID {})", name, pid));
             }
-            DisplayRow::Grouped(g) => {
-                let cat_col = category_color(g.category);
-                let svc_label = format!("{} ×{}", g.service, g.count);
-
-                let _ = write!(
-                    w,
-                    "{}{}  {:<6}{}",
-                    SetForegroundColor(dim),
-                    SetAttribute(Attribute::Bold),
-                    "···",
-                    SetAttribute(Attribute::Reset)
-                );
-                let _ = write!(
-                    w,
-                    "{} {:<5}",
-                    SetForegroundColor(proto_color(g.protocol)),
-                    g.protocol
-                );
-                let _ = write!(
-                    w,
-                    "{} {:<22}",
-                    SetForegroundColor(cat_col),
-                    truncate(&g.process_name, 22)
-                );
-                let _ = write!(
-                    w,
-                    "{}{} {:<20}{}",
-                    SetForegroundColor(cat_col),
-                    SetAttribute(Attribute::Bold),
-                    svc_label,
-                    SetAttribute(Attribute::Reset)
-                );
-                let _ = write!(w, "{} {:<7}", SetForegroundColor(dim), g.pid);
-                let _ = write!(
-                    w,
-                    "{} {:<7}",
-                    SetForegroundColor(dim),
-                    format!("{:.1}%", g.cpu_percent)
-                );
-                let _ = write!(
-                    w,
-                    "{} {:<9}",
-                    SetForegroundColor(light),
-                    if g.memory_mb >= 1024.0 {
-                        format!("{:.1} GB", g.memory_mb / 1024.0)
-                    } else {
-                        format!("{:.0} MB", g.memory_mb)
-                    }
-                );
-                let _ = write!(w, "{} {}", SetForegroundColor(dim), {
-                    let secs = g.uptime.as_secs();
-                    if secs < 60 {
-                        format!("{}s", secs)
-                    } else if secs < 3600 {
-                        format!("{}m", secs / 60)
-                    } else if secs < 86400 {
-                        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
-                    } else {
-                        format!("{}d {}h", secs / 86400, (secs % 86400) / 3600)
-                    }
-                });
-                let _ = writeln!(w, "{}", ResetColor);
+            Err(err) => {
+                failed += 1;
+                print_outcome(false, &format!("Failed {} (PID {}): {:#}", name, pid, err));
             }
         }
     }
-
-    // Summary
-    let total = entries.len();
-    let tcp = entries
-        .iter()
-        .filter(|e| e.protocol == models::Protocol::Tcp)
-        .count();
-    let udp = total - tcp;
-    let services = entries.iter().filter(|e| e.known_service.is_some()).count();
-    let _ = write!(
-        w,
-        "\n{}{}{} ports{} ({} TCP, {} UDP)",
-        SetForegroundColor(hdr),
-        SetAttribute(Attribute::Bold),
-        total,
-        SetAttribute(Attribute::Reset),
-        tcp,
-        udp,
+    print_outcome(
+        failed == 0,
+        &format!(
+            "Killed {} of {} processes{}",
+            killed,
+            targets.len(),
+            if failed > 0 {
+                format!(" ({} failed)", failed)
+            } else {
+                String::new()
+            }
+        ),
     );
-    if services > 0 {
-        let _ = write!(w, " · {} known services", services);
-    }
-    let _ = writeln!(w, "{}", ResetColor);
+    Ok(failed == 0 && !refused)
 }
 
-fn print_json(entries: &[models::PortEntry]) -> Result<()> {
-    // Manual JSON to avoid serde dependency
-    println!("[");
-    for (i, e) in entries.iter().enumerate() {
-        let comma = if i < entries.len() - 1 { "," } else { "" };
-        println!(
-            r#"  {{"port":{},"protocol":"{}","process":"{}","service":{},"pid":{},"cpu":{:.1},"memory_mb":{:.1},"uptime_secs":{}}}{}"#,
-            e.port,
-            e.protocol,
-            e.process_name.replace('"', "\\\""),
-            e.known_service
-                .map(|s| format!("\"{}\"", s))
-                .unwrap_or_else(|| "null".to_string()),
-            e.pid,
-            e.cpu_percent,
-            e.memory_mb,
-            e.uptime.as_secs(),
-            comma,
+/// y/N prompt; without a terminal to ask on, tells the user to pass `skip_flag`.
+fn confirm(question: &str, skip_flag: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "refusing to continue without confirmation; pass {}",
+            skip_flag
         );
     }
-    println!("]");
-    Ok(())
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
 }
 
-fn truncate(s: &str, max: usize) -> String {
-    if s.len() <= max {
-        s.to_string()
-    } else {
-        format!("{}…", &s[..max - 1])
+/// Ask the user to type the port number before killing a protected entry.
+fn confirm_protected(entry: &PortEntry) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "Port {} is protected; refusing to kill without an interactive confirmation",
+            entry.port
+        );
     }
+    print!(
+        "Port {} ({} — {}, PID {}) is protected. Type the port number to confirm: ",
+        entry.port,
+        entry.known_service.unwrap_or("protected"),
+        entry.process_name,
+        entry.pid
+    );
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim() == entry.port.to_string())
 }
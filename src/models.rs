@@ -1,8 +1,17 @@
+use std::cmp::Ordering;
 use std::fmt;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::util::{container_proxy_addr, is_container_proxy};
+
+/// Dev servers at or below this CPU usage are candidates for the idle hint
+pub const IDLE_CPU_PERCENT: f32 = 0.5;
+
+/// Minimum uptime before a quiet dev server is considered forgotten
+pub const IDLE_MIN_UPTIME: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Protocol {
     Tcp,
     Udp,
@@ -11,88 +20,386 @@ pub enum Protocol {
 impl fmt::Display for Protocol {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Protocol::Tcp => write!(f, "TCP"),
-            Protocol::Udp => write!(f, "UDP"),
+            Protocol::Tcp => f.pad("TCP"),
+            Protocol::Udp => f.pad("UDP"),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// TCP connection state (UDP sockets have none)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SocketState {
+    Listen,
+    Established,
+    SynSent,
+    SynReceived,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    CloseWait,
+    LastAck,
+    Closing,
+    Closed,
+    Unknown,
+}
+
+impl SocketState {
+    /// Short label for the STATE column
+    pub fn label(self) -> &'static str {
+        match self {
+            SocketState::Listen => "LISTEN",
+            SocketState::Established => "ESTAB",
+            SocketState::SynSent => "SYN_SENT",
+            SocketState::SynReceived => "SYN_RECV",
+            SocketState::FinWait1 => "FIN_WAIT1",
+            SocketState::FinWait2 => "FIN_WAIT2",
+            SocketState::TimeWait => "TIME_WAIT",
+            SocketState::CloseWait => "CLOSE_WAIT",
+            SocketState::LastAck => "LAST_ACK",
+            SocketState::Closing => "CLOSING",
+            SocketState::Closed => "CLOSED",
+            SocketState::Unknown => "?",
+        }
+    }
+
+    /// States that pile up when sockets leak or aren't closed properly
+    pub fn is_lingering(self) -> bool {
+        matches!(self, SocketState::TimeWait | SocketState::CloseWait)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum ServiceCategory {
+    #[value(name = "dev")]
     DevServer,
     Database,
     Cache,
     Container,
     Browser,
     System,
+    #[value(name = "other")]
     Unknown,
 }
 
+impl ServiceCategory {
+    pub const ALL: [ServiceCategory; 7] = [
+        ServiceCategory::DevServer,
+        ServiceCategory::Database,
+        ServiceCategory::Cache,
+        ServiceCategory::Container,
+        ServiceCategory::Browser,
+        ServiceCategory::System,
+        ServiceCategory::Unknown,
+    ];
+
+    /// Short lowercase name for summaries
+    pub fn label(self) -> &'static str {
+        match self {
+            ServiceCategory::DevServer => "dev",
+            ServiceCategory::Database => "database",
+            ServiceCategory::Cache => "cache",
+            ServiceCategory::Container => "container",
+            ServiceCategory::Browser => "browser",
+            ServiceCategory::System => "system",
+            ServiceCategory::Unknown => "other",
+        }
+    }
+}
+
+/// Accept queue of a listening TCP socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BacklogInfo {
+    /// Connections completed but not yet accepted by the process
+    pub queued: u32,
+    /// Maximum the queue may hold (the `listen()` backlog)
+    pub backlog: u32,
+}
+
+impl BacklogInfo {
+    /// New connections are being dropped or refused
+    pub fn is_full(&self) -> bool {
+        self.backlog > 0 && self.queued >= self.backlog
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PortEntry {
     pub protocol: Protocol,
     pub local_addr: IpAddr,
     pub port: u16,
+    /// TCP state; `None` for UDP
+    pub state: Option<SocketState>,
+    /// Peer address for connected (non-listening) TCP sockets
+    pub remote: Option<SocketAddr>,
     pub pid: u32,
+    pub ppid: Option<u32>,
     pub process_name: String,
     pub process_cmd: String,
     pub cpu_percent: f32,
     pub memory_mb: f64,
+    /// Share of total system RAM; `None` when the total is unknown
+    pub memory_percent: Option<f32>,
+    /// Threads in the owning process; `None` unless requested, or where the
+    /// platform doesn't report them
+    pub thread_count: Option<usize>,
+    /// Accept queue for listening TCP sockets, where the platform reports it
+    pub backlog: Option<BacklogInfo>,
+    /// Socket inode (Linux), matching the `socket:[N]` links in `/proc/<pid>/fd`
+    pub inode: Option<u32>,
+    /// Owning user's ID (Unix); `None` where it can't be read
+    pub uid: Option<u32>,
     pub uptime: Duration,
     pub known_service: Option<&'static str>,
     pub category: ServiceCategory,
+    /// Owning process couldn't be looked up, so process details are missing
+    pub partial: bool,
+}
+
+/// Minimal process info used to walk parent chains for the tree view
+#[derive(Debug, Clone)]
+pub struct ProcessNode {
+    pub ppid: Option<u32>,
+    pub name: String,
+}
+
+#[cfg(test)]
+impl PortEntry {
+    /// A TCP socket listening on localhost, with no metrics or service
+    pub fn fixture(port: u16, pid: u32, name: &str) -> Self {
+        Self {
+            protocol: Protocol::Tcp,
+            local_addr: std::net::Ipv4Addr::LOCALHOST.into(),
+            port,
+            state: Some(SocketState::Listen),
+            remote: None,
+            pid,
+            ppid: None,
+            process_name: name.to_string(),
+            process_cmd: String::new(),
+            cpu_percent: 0.0,
+            memory_mb: 0.0,
+            memory_percent: None,
+            thread_count: None,
+            backlog: None,
+            inode: None,
+            uid: None,
+            uptime: Duration::ZERO,
+            known_service: None,
+            category: ServiceCategory::Unknown,
+            partial: false,
+        }
+    }
 }
 
 impl PortEntry {
-    /// Display address as compact string
+    /// Display address as compact string: `*` for any address, `127…` for
+    /// v4 loopback, and IPv6 in brackets (`[::1]:8080`), with link-local
+    /// addresses cut to `[fe80…]`
     pub fn addr_display(&self) -> String {
         match self.local_addr {
             IpAddr::V4(addr) if addr.is_unspecified() => format!("*:{}", self.port),
             IpAddr::V6(addr) if addr.is_unspecified() => format!("*:{}", self.port),
             IpAddr::V4(addr) if addr.is_loopback() => format!("127…:{}", self.port),
-            _ => format!("{}:{}", self.local_addr, self.port),
+            // fe80::/10; the interface-specific rest rarely matters here
+            IpAddr::V6(addr) if addr.segments()[0] & 0xffc0 == 0xfe80 => {
+                format!("[fe80…]:{}", self.port)
+            }
+            // Display already compresses the longest run of zeros (`::`)
+            IpAddr::V6(addr) => format!("[{}]:{}", addr, self.port),
+            IpAddr::V4(addr) => format!("{}:{}", addr, self.port),
+        }
+    }
+
+    /// Bound to 127.0.0.1 / ::1, so not reachable from the network
+    pub fn is_loopback(&self) -> bool {
+        self.local_addr.is_loopback()
+    }
+
+    /// Reachable from the network: bound to a wildcard or non-loopback address
+    pub fn is_public(&self) -> bool {
+        !self.is_loopback()
+    }
+
+    /// Dev server sitting at ~0% CPU for a long time — likely forgotten
+    pub fn is_idle(&self) -> bool {
+        self.category == ServiceCategory::DevServer
+            && self.cpu_percent <= IDLE_CPU_PERCENT
+            && self.uptime >= IDLE_MIN_UPTIME
+    }
+
+    /// Container plumbing (docker-proxy and the like) holding the port for a
+    /// container rather than serving it
+    pub fn is_container_proxy(&self) -> bool {
+        is_container_proxy(&self.process_name)
+    }
+
+    /// Where a container port forwarder relays to, when its command line says
+    pub fn proxy_target(&self) -> Option<SocketAddr> {
+        self.is_container_proxy()
+            .then(|| container_proxy_addr(&self.process_cmd))
+            .flatten()
+    }
+
+    /// SERVICE column text: for a container port forwarder, the container
+    /// address it relays to when its command line says, else the known service
+    pub fn service_label(&self) -> Option<String> {
+        match self.proxy_target() {
+            Some(target) => Some(format!("→ {}", target)),
+            None => self.known_service.map(str::to_string),
         }
     }
 
-    /// Format memory as human-readable string
+    /// STATE column text (`—` for UDP)
+    pub fn state_display(&self) -> &'static str {
+        self.state.map(|s| s.label()).unwrap_or("—")
+    }
+
+    /// Format memory as human-readable string (see [`memory_display`])
     pub fn memory_display(&self) -> String {
-        if self.memory_mb >= 1024.0 {
-            format!("{:.1} GB", self.memory_mb / 1024.0)
-        } else {
-            format!("{:.0} MB", self.memory_mb)
+        memory_display(self.memory_mb)
+    }
+
+    /// Memory as a share of total RAM, e.g. `1.5%` (`—` when unknown)
+    pub fn memory_percent_display(&self) -> String {
+        match self.memory_percent {
+            Some(pct) => format!("{:.1}%", pct),
+            None => "—".to_string(),
         }
     }
 
+    /// Accept queue as `queued / backlog`, or `n/a` where it isn't available
+    pub fn backlog_display(&self) -> String {
+        match self.backlog {
+            Some(b) => format!("{} / {}", b.queued, b.backlog),
+            None => "n/a".to_string(),
+        }
+    }
+
+    /// Thread count, or `n/a` where it isn't available
+    pub fn thread_display(&self) -> String {
+        match self.thread_count {
+            Some(n) => n.to_string(),
+            None => "n/a".to_string(),
+        }
+    }
+
+    /// Neither the process nor the port told us what this is
+    pub fn is_unidentified(&self) -> bool {
+        self.known_service.is_none() && self.category == ServiceCategory::Unknown
+    }
+
+    /// Whether a user with ID `me` may signal the owner: root can signal
+    /// anyone, others only their own processes. Unknown ownership (or an
+    /// unknown `me`) counts as killable; PID 0 has nothing to signal.
+    pub fn is_killable_by(&self, me: Option<u32>) -> bool {
+        self.pid != 0
+            && match (me, self.uid) {
+                (Some(me), Some(owner)) => me == 0 || me == owner,
+                _ => true,
+            }
+    }
+
     /// Format uptime as human-readable string
     pub fn uptime_display(&self) -> String {
-        let secs = self.uptime.as_secs();
-        if secs < 60 {
-            format!("{}s", secs)
-        } else if secs < 3600 {
-            format!("{}m", secs / 60)
-        } else if secs < 86400 {
-            format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
-        } else {
-            format!("{}d {}h", secs / 86400, (secs % 86400) / 3600)
-        }
+        duration_display(self.uptime)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Memory in the unit that suits its size: `400 KB` under a megabyte (so
+/// small daemons don't read as zero), `512 MB`, or `1.5 GB`. Nothing at all
+/// (no process to measure) stays `0 MB`. The unit is picked by the rounded
+/// number, so 1023.6 MB never prints as `1024 MB`.
+pub fn memory_display(mb: f64) -> String {
+    if mb.round() >= 1024.0 {
+        format!("{:.1} GB", mb / 1024.0)
+    } else if (mb * 1024.0).round() >= 1024.0 || mb <= 0.0 {
+        format!("{:.0} MB", mb)
+    } else {
+        format!("{:.0} KB", (mb * 1024.0).max(1.0))
+    }
+}
+
+/// Compact duration like `45s`, `12m`, `3h 5m`, `2d 4h`, `3w 2d`, `4mo 1w` or
+/// `2y 3mo`: at most two units, the second always whole. Months count as
+/// 30 days and years as 365.
+pub fn duration_display(d: Duration) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+    const MONTH: u64 = 30 * DAY;
+    const YEAR: u64 = 365 * DAY;
+
+    let secs = d.as_secs();
+    if secs < MINUTE {
+        format!("{}s", secs)
+    } else if secs < HOUR {
+        format!("{}m", secs / MINUTE)
+    } else if secs < DAY {
+        format!("{}h {}m", secs / HOUR, secs % HOUR / MINUTE)
+    } else if secs < WEEK {
+        format!("{}d {}h", secs / DAY, secs % DAY / HOUR)
+    } else if secs < MONTH {
+        format!("{}w {}d", secs / WEEK, secs % WEEK / DAY)
+    } else if secs < YEAR {
+        format!("{}mo {}w", secs / MONTH, secs % MONTH / WEEK)
+    } else {
+        format!("{}y {}mo", secs / YEAR, secs % YEAR / MONTH)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum SortField {
     Port,
+    #[value(name = "name")]
     ProcessName,
     Cpu,
+    #[value(name = "mem")]
     Memory,
+    /// Share of total RAM; entries without one sort last
+    #[value(name = "mem-pct")]
+    MemoryPercent,
+    /// Process run time, not how long the port has been open
+    Uptime,
 }
 
 impl SortField {
+    pub const ALL: [SortField; 6] = [
+        SortField::Port,
+        SortField::ProcessName,
+        SortField::Cpu,
+        SortField::Memory,
+        SortField::MemoryPercent,
+        SortField::Uptime,
+    ];
+
     pub fn next(self) -> Self {
         match self {
             SortField::Port => SortField::ProcessName,
             SortField::ProcessName => SortField::Cpu,
             SortField::Cpu => SortField::Memory,
-            SortField::Memory => SortField::Port,
+            SortField::Memory => SortField::MemoryPercent,
+            SortField::MemoryPercent => SortField::Uptime,
+            SortField::Uptime => SortField::Port,
+        }
+    }
+
+    /// Port and name ascending; CPU and memory descending (biggest first);
+    /// uptime descending, so the oldest process comes first
+    pub fn compare(self, a: &PortEntry, b: &PortEntry) -> Ordering {
+        match self {
+            SortField::Port => a.port.cmp(&b.port),
+            SortField::ProcessName => a
+                .process_name
+                .to_lowercase()
+                .cmp(&b.process_name.to_lowercase()),
+            SortField::Cpu => b.cpu_percent.total_cmp(&a.cpu_percent),
+            SortField::Memory => b.memory_mb.total_cmp(&a.memory_mb),
+            SortField::MemoryPercent => match (a.memory_percent, b.memory_percent) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+            SortField::Uptime => b.uptime.cmp(&a.uptime),
         }
     }
 
@@ -102,6 +409,275 @@ impl SortField {
             SortField::ProcessName => "Name",
             SortField::Cpu => "CPU",
             SortField::Memory => "Mem",
+            SortField::MemoryPercent => "Mem%",
+            SortField::Uptime => "Uptime",
         }
     }
 }
+
+/// Groups of bindings that share a port and protocol but sit on different
+/// addresses held by different processes — e.g. one server on
+/// `127.0.0.1:3000` and another on `[::]:3000`, so which one answers depends
+/// on how the client resolves the host. One process binding both IPv4 and
+/// IPv6 is the normal dual-stack setup, and worker pools share every socket,
+/// so two addresses only conflict when no PID holds both. Docker publishes
+/// IPv4 and IPv6 through a separate `docker-proxy` each, so two container
+/// forwarders count as one holder. Connected sockets are left out. Groups
+/// come back sorted by port.
+pub fn port_conflicts(entries: &[PortEntry]) -> Vec<Vec<&PortEntry>> {
+    let mut groups: Vec<Vec<&PortEntry>> = Vec::new();
+    for e in entries.iter().filter(|e| e.remote.is_none()) {
+        match groups
+            .iter_mut()
+            .find(|g| g[0].port == e.port && g[0].protocol == e.protocol)
+        {
+            Some(group) => group.push(e),
+            None => groups.push(vec![e]),
+        }
+    }
+    groups.retain(|g| {
+        let holders = |addr: IpAddr| -> Vec<u32> {
+            g.iter()
+                .filter(|e| e.local_addr == addr)
+                .map(|e| e.pid)
+                .collect()
+        };
+        g.iter().enumerate().any(|(i, a)| {
+            g[i + 1..].iter().any(|b| {
+                a.local_addr != b.local_addr
+                    && !(a.is_container_proxy() && b.is_container_proxy())
+                    && {
+                        let other = holders(b.local_addr);
+                        !holders(a.local_addr).iter().any(|pid| other.contains(pid))
+                    }
+            })
+        })
+    });
+    groups.sort_by_key(|g| (g[0].port, g[0].protocol == Protocol::Udp));
+    groups
+}
+
+/// The listener a container port forwarder relays to: a socket of the same
+/// protocol on the target port, bound to the target address or a wildcard,
+/// that isn't another forwarder. Best-effort: a container's own sockets
+/// usually live in another network namespace and don't show up at all.
+pub fn proxy_backend<'a>(proxy: &PortEntry, entries: &'a [PortEntry]) -> Option<&'a PortEntry> {
+    let target = proxy.proxy_target()?;
+    let candidates: Vec<&PortEntry> = entries
+        .iter()
+        .filter(|e| {
+            e.port == target.port()
+                && e.protocol == proxy.protocol
+                && e.remote.is_none()
+                && !e.is_container_proxy()
+        })
+        .collect();
+    candidates
+        .iter()
+        .find(|e| e.local_addr == target.ip())
+        .or_else(|| candidates.iter().find(|e| e.local_addr.is_unspecified()))
+        .copied()
+}
+
+/// The container port forwarders whose traffic ends up at `backend`
+pub fn proxy_frontends<'a>(backend: &PortEntry, entries: &'a [PortEntry]) -> Vec<&'a PortEntry> {
+    entries
+        .iter()
+        .filter(|e| {
+            proxy_backend(e, entries)
+                .is_some_and(|b| b.pid == backend.pid && b.port == backend.port)
+        })
+        .collect()
+}
+
+/// Sort by `primary`, breaking ties with `secondary`, then by port and PID so
+/// the order is fully deterministic
+pub fn sort_entries(entries: &mut [PortEntry], primary: SortField, secondary: SortField) {
+    entries.sort_by(|a, b| {
+        primary
+            .compare(a, b)
+            .then_with(|| secondary.compare(a, b))
+            .then_with(|| a.port.cmp(&b.port))
+            .then_with(|| a.pid.cmp(&b.pid))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv6Addr;
+
+    use super::*;
+
+    fn addr(local_addr: &str) -> String {
+        PortEntry {
+            local_addr: local_addr.parse().unwrap(),
+            ..PortEntry::fixture(8080, 1, "server")
+        }
+        .addr_display()
+    }
+
+    #[test]
+    fn addr_display_compresses_ipv6() {
+        assert_eq!(addr("::"), "*:8080");
+        assert_eq!(addr("0.0.0.0"), "*:8080");
+        assert_eq!(addr("::1"), "[::1]:8080");
+        assert_eq!(addr("127.0.0.1"), "127…:8080");
+        assert_eq!(addr("fe80::1c2b:3dff:fe4e:5f60"), "[fe80…]:8080");
+        assert_eq!(addr("192.168.1.20"), "192.168.1.20:8080");
+
+        let global = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x42);
+        assert_eq!(addr(&global.to_string()), "[2001:db8::42]:8080");
+        assert_eq!(
+            addr("2001:0db8:85a3:0000:0000:8a2e:0370:7334"),
+            "[2001:db8:85a3::8a2e:370:7334]:8080"
+        );
+    }
+
+    #[test]
+    fn duration_coarsens_at_each_boundary() {
+        let d = |secs| duration_display(Duration::from_secs(secs));
+        const DAY: u64 = 86400;
+        assert_eq!(d(59), "59s");
+        assert_eq!(d(3599), "59m");
+        assert_eq!(d(3600), "1h 0m");
+        assert_eq!(d(DAY - 1), "23h 59m");
+        assert_eq!(d(DAY), "1d 0h");
+        assert_eq!(d(7 * DAY - 1), "6d 23h");
+        assert_eq!(d(7 * DAY), "1w 0d");
+        assert_eq!(d(30 * DAY - 1), "4w 1d");
+        assert_eq!(d(30 * DAY), "1mo 0w");
+        assert_eq!(d(90 * DAY + 4 * 3600), "3mo 0w");
+        assert_eq!(d(365 * DAY - 1), "12mo 0w");
+        assert_eq!(d(365 * DAY), "1y 0mo");
+        assert_eq!(d(3 * 365 * DAY + 95 * DAY), "3y 3mo");
+        // Nothing overflows, however long
+        assert_eq!(d(u64::MAX), "584942417355y 0mo");
+    }
+
+    #[test]
+    fn memory_picks_a_unit_by_size() {
+        assert_eq!(memory_display(0.0), "0 MB");
+        assert_eq!(memory_display(400.0 / 1024.0), "400 KB");
+        assert_eq!(memory_display(0.0001), "1 KB");
+        assert_eq!(memory_display(1.0), "1 MB");
+        assert_eq!(memory_display(512.4), "512 MB");
+        assert_eq!(memory_display(1023.0), "1023 MB");
+        // Rounding up to the next unit switches to it
+        assert_eq!(memory_display(1023.4), "1023 MB");
+        assert_eq!(memory_display(1023.6), "1.0 GB");
+        assert_eq!(memory_display(0.9994), "1023 KB");
+        assert_eq!(memory_display(0.9996), "1 MB");
+        assert_eq!(memory_display(1024.0), "1.0 GB");
+        assert_eq!(memory_display(1536.0), "1.5 GB");
+    }
+
+    #[test]
+    fn docker_proxy_links_to_its_backend() {
+        let proxy = |port, pid, target: &str| {
+            let (ip, container_port) = target.split_once(':').unwrap();
+            PortEntry {
+                local_addr: std::net::Ipv4Addr::UNSPECIFIED.into(),
+                process_cmd: format!(
+                    "/usr/bin/docker-proxy -proto tcp -host-ip 0.0.0.0 -host-port {} \
+                     -container-ip {} -container-port {}",
+                    port, ip, container_port
+                ),
+                ..PortEntry::fixture(port, pid, "docker-proxy")
+            }
+        };
+        let postgres = PortEntry {
+            local_addr: "172.17.0.2".parse().unwrap(),
+            ..PortEntry::fixture(5432, 40, "postgres")
+        };
+        let entries = vec![
+            proxy(5432, 10, "172.17.0.2:5432"),
+            proxy(15432, 11, "172.17.0.2:5432"),
+            proxy(6379, 12, "172.17.0.3:6379"),
+            postgres,
+        ];
+
+        let backend = proxy_backend(&entries[0], &entries).map(|e| e.pid);
+        assert_eq!(backend, Some(40));
+        assert_eq!(
+            entries[0].service_label().as_deref(),
+            Some("→ 172.17.0.2:5432")
+        );
+        // Redis runs out of sight, so there's nothing to link to
+        assert!(proxy_backend(&entries[2], &entries).is_none());
+        // Nor is the backend itself a forwarder
+        assert!(proxy_backend(&entries[3], &entries).is_none());
+
+        let fronts: Vec<u16> = proxy_frontends(&entries[3], &entries)
+            .iter()
+            .map(|e| e.port)
+            .collect();
+        assert_eq!(fronts, [5432, 15432]);
+    }
+
+    #[test]
+    fn idle_needs_a_quiet_long_running_dev_server() {
+        let server = |category, cpu_percent, uptime| PortEntry {
+            category,
+            cpu_percent,
+            uptime,
+            ..PortEntry::fixture(3000, 1, "node")
+        };
+        let dev = ServiceCategory::DevServer;
+        let long = IDLE_MIN_UPTIME * 8;
+        assert!(server(dev, 0.0, IDLE_MIN_UPTIME).is_idle());
+        assert!(server(dev, IDLE_CPU_PERCENT, long).is_idle());
+        // Busy, too young, or not a dev server
+        assert!(!server(dev, IDLE_CPU_PERCENT + 0.1, long).is_idle());
+        assert!(!server(dev, 0.0, IDLE_MIN_UPTIME - Duration::from_secs(1)).is_idle());
+        assert!(!server(ServiceCategory::Database, 0.0, long).is_idle());
+    }
+
+    #[test]
+    fn memory_percent_sorts_biggest_first_and_unknown_last() {
+        let with = |pid, memory_percent| PortEntry {
+            memory_percent,
+            ..PortEntry::fixture(3000, pid, "node")
+        };
+        let mut entries = [with(1, None), with(2, Some(1.5)), with(3, Some(12.0))];
+        entries.sort_by(|a, b| SortField::MemoryPercent.compare(a, b));
+        let pids: Vec<u32> = entries.iter().map(|e| e.pid).collect();
+        assert_eq!(pids, [3, 2, 1]);
+    }
+
+    #[test]
+    fn equal_cpu_ties_break_deterministically() {
+        let with = |port, pid, name: &str| PortEntry {
+            cpu_percent: 2.0,
+            ..PortEntry::fixture(port, pid, name)
+        };
+        let entries = [
+            with(8080, 30, "alpha"),
+            with(3000, 11, "beta"),
+            with(3000, 10, "beta"),
+            with(5432, 20, "alpha"),
+            PortEntry {
+                cpu_percent: 9.0,
+                ..PortEntry::fixture(9000, 40, "zeta")
+            },
+        ];
+        let order = |secondary| {
+            // Every starting order ends up the same
+            let mut reversed = entries.to_vec();
+            reversed.reverse();
+            let mut sorted = entries.to_vec();
+            sort_entries(&mut sorted, SortField::Cpu, secondary);
+            sort_entries(&mut reversed, SortField::Cpu, secondary);
+            let key = |es: &[PortEntry]| es.iter().map(|e| (e.port, e.pid)).collect::<Vec<_>>();
+            assert_eq!(key(&sorted), key(&reversed));
+            key(&sorted)
+        };
+        assert_eq!(
+            order(SortField::Port),
+            [(9000, 40), (3000, 10), (3000, 11), (5432, 20), (8080, 30)]
+        );
+        assert_eq!(
+            order(SortField::ProcessName),
+            [(9000, 40), (5432, 20), (8080, 30), (3000, 10), (3000, 11)]
+        );
+    }
+}
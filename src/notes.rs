@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use crate::json;
+use crate::models::PortEntry;
+use crate::state;
+
+const FILE_NAME: &str = "notes.json";
+
+/// Free-text notes jotted on TUI rows, keyed by `(port, process name)` so
+/// they stay with the service across refreshes, restarts and PID changes.
+/// Kept in the state directory next to the first-seen history.
+pub struct Notes {
+    path: Option<PathBuf>,
+    notes: HashMap<(u16, String), String>,
+    /// Why the saved notes couldn't be read. While set, nothing is saved, so
+    /// the file is never replaced by the few notes added since.
+    load_error: Option<String>,
+}
+
+impl Notes {
+    /// Load the saved notes; a missing file starts with none
+    pub fn load() -> Self {
+        Self::load_from(state::path(FILE_NAME))
+    }
+
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let loaded = match &path {
+            Some(p) => state::load(p, parse),
+            None => Ok(None),
+        };
+        let (notes, load_error) = match loaded {
+            Ok(notes) => (notes.unwrap_or_default(), None),
+            Err(e) => (HashMap::new(), Some(format!("{:#}", e))),
+        };
+        Self {
+            path,
+            notes,
+            load_error,
+        }
+    }
+
+    /// Why the saved notes couldn't be loaded, if they couldn't
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    pub fn get(&self, entry: &PortEntry) -> Option<&str> {
+        self.notes
+            .get(&(entry.port, entry.process_name.clone()))
+            .map(String::as_str)
+    }
+
+    /// Set the note on `entry`'s port + process and save; blank text removes it
+    pub fn set(&mut self, entry: &PortEntry, text: &str) -> Result<()> {
+        let key = (entry.port, entry.process_name.clone());
+        let text = text.trim();
+        if text.is_empty() {
+            self.notes.remove(&key);
+        } else {
+            self.notes.insert(key, text.to_string());
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(e) = &self.load_error {
+            bail!(
+                "the saved notes couldn't be read, so they were left alone ({})",
+                e
+            );
+        }
+
+        let mut notes: Vec<_> = self.notes.iter().collect();
+        notes.sort();
+        let rows: Vec<String> = notes
+            .iter()
+            .map(|((port, process), note)| {
+                format!(
+                    r#"  {{"port":{},"process":{},"note":{}}}"#,
+                    port,
+                    json::quote(process),
+                    json::quote(note)
+                )
+            })
+            .collect();
+        state::save(path, &rows)
+    }
+}
+
+fn parse(text: &str) -> Result<HashMap<(u16, String), String>> {
+    let doc = json::parse(text)?;
+    let items = doc.as_array().context("expected a JSON array")?;
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let port = u16::try_from(item.get("port")?.as_u32()?).ok()?;
+            let process = item.get("process")?.as_str()?.to_string();
+            let note = item.get("note")?.as_str()?.to_string();
+            Some(((port, process), note))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreadable_notes_are_reported_and_never_overwritten() {
+        let dir = std::env::temp_dir().join(format!("kav-notes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(FILE_NAME);
+        let entry = PortEntry::fixture(3000, 42, "node");
+
+        // Missing: no notes, no error, and the first note creates the file
+        let mut notes = Notes::load_from(Some(path.clone()));
+        assert_eq!(notes.load_error(), None);
+        notes.set(&entry, "  the blog  ").unwrap();
+        let notes = Notes::load_from(Some(path.clone()));
+        assert_eq!(notes.get(&entry), Some("the blog"));
+
+        // Corrupt: the error names the file, and saving leaves it as it was
+        std::fs::write(&path, "[{\"port\":3000,").unwrap();
+        let mut notes = Notes::load_from(Some(path.clone()));
+        assert!(notes.load_error().unwrap().contains("notes.json"));
+        assert!(notes.set(&entry, "lost?").is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[{\"port\":3000,");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
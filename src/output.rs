@@ -0,0 +1,1467 @@
+use std::io::{self, IsTerminal, Write};
+use std::net::IpAddr;
+
+use anyhow::Result;
+use crossterm::style::{Attribute, Color, ResetColor, SetAttribute, SetForegroundColor};
+
+use crate::config::Columns;
+use crate::diff::{self, SnapshotDiff};
+use crate::group::{self, Group, GroupBy, Section, Strategy};
+use crate::json;
+use crate::models::{self, duration_display, memory_display, ServiceCategory};
+use crate::pager;
+use crate::ports;
+use crate::scanner::ScanResult;
+use crate::theme::{theme, Palette};
+
+fn category_color(cat: ServiceCategory) -> Color {
+    let t = theme();
+    if t.palette != Palette::Default && cat != ServiceCategory::Unknown {
+        if let ratatui::style::Color::Rgb(r, g, b) = t.category_color(cat) {
+            return Color::Rgb { r, g, b };
+        }
+    }
+    match cat {
+        ServiceCategory::DevServer => Color::Rgb {
+            r: 34,
+            g: 197,
+            b: 94,
+        }, // green
+        ServiceCategory::Database => Color::Rgb {
+            r: 234,
+            g: 179,
+            b: 8,
+        }, // yellow
+        ServiceCategory::Cache => Color::Rgb {
+            r: 168,
+            g: 85,
+            b: 247,
+        }, // purple
+        ServiceCategory::Container => Color::Rgb {
+            r: 96,
+            g: 165,
+            b: 250,
+        }, // blue
+        ServiceCategory::Browser => Color::Rgb {
+            r: 251,
+            g: 146,
+            b: 60,
+        }, // orange
+        ServiceCategory::System => Color::Rgb {
+            r: 140,
+            g: 140,
+            b: 145,
+        }, // gray
+        ServiceCategory::Unknown => Color::Rgb {
+            r: 100,
+            g: 100,
+            b: 105,
+        }, // dim gray
+    }
+}
+
+/// CPU usage color, as in the table: red above 50%, yellow above 10%, else `normal`
+fn cpu_color(percent: f32, normal: Color) -> Color {
+    if percent > 50.0 {
+        Color::Rgb {
+            r: 239,
+            g: 68,
+            b: 68,
+        }
+    } else if percent > 10.0 {
+        Color::Rgb {
+            r: 234,
+            g: 179,
+            b: 8,
+        }
+    } else {
+        normal
+    }
+}
+
+fn proto_color(proto: models::Protocol) -> Color {
+    match theme().protocol_color(proto) {
+        ratatui::style::Color::Rgb(r, g, b) => Color::Rgb { r, g, b },
+        _ => Color::Reset,
+    }
+}
+
+/// Watched ports' color: the theme's accent
+fn watch_color() -> Color {
+    match theme().primary {
+        ratatui::style::Color::Rgb(r, g, b) => Color::Rgb { r, g, b },
+        _ => Color::Green,
+    }
+}
+
+/// Row to render — either a single entry or a group summary
+enum DisplayRow<'a> {
+    Single(&'a models::PortEntry),
+    Grouped(Group<'a>),
+}
+
+impl<'a> DisplayRow<'a> {
+    /// The entry that stands for the row when ordering the table
+    fn representative(&self) -> &'a models::PortEntry {
+        match self {
+            DisplayRow::Single(e) => e,
+            DisplayRow::Grouped(g) => g.first(),
+        }
+    }
+}
+
+/// Browser services and worker clusters collapse into one row each. Every
+/// row sits where its representative entry falls in `entries`, which come
+/// already sorted, so grouped rows follow the chosen sort like the rest.
+fn display_rows<'a>(
+    entries: impl IntoIterator<Item = &'a models::PortEntry>,
+    grouping: &[Strategy],
+) -> Vec<DisplayRow<'a>> {
+    let entries: Vec<&models::PortEntry> = entries.into_iter().collect();
+    let (singles, groups) = group::group(entries.iter().copied(), grouping);
+    let mut rows: Vec<DisplayRow> = singles.into_iter().map(DisplayRow::Single).collect();
+    rows.extend(groups.into_iter().map(DisplayRow::Grouped));
+    rows.sort_by_cached_key(|row| {
+        let first = row.representative();
+        entries.iter().position(|e| std::ptr::eq(*e, first))
+    });
+    rows
+}
+
+/// Natural PROCESS and SERVICE widths, used when the terminal width is unknown
+const PROCESS_WIDTH: usize = 22;
+const SERVICE_WIDTH: usize = 20;
+/// Narrowest PROCESS / SERVICE / COMMAND columns before giving up on fitting
+const MIN_PROCESS_WIDTH: usize = 8;
+const MIN_SERVICE_WIDTH: usize = 6;
+const MIN_COMMAND_WIDTH: usize = 10;
+/// How far PROCESS grows on wide terminals
+const MAX_PROCESS_WIDTH: usize = 32;
+/// UPTIME plus the " idle" marker, e.g. `12d 23h idle`
+const UPTIME_WIDTH: usize = 12;
+/// Indent, PORT, PROTO, PID, CPU, MEM and UPTIME with their gaps, plus the
+/// gaps before PROCESS and SERVICE
+const FIXED_WIDTH: usize = 2 + 6 + (1 + 5) + (1 + 7) + (1 + 7) + (1 + 9) + (1 + UPTIME_WIDTH) + 2;
+
+/// Narrowest width `--max-width` accepts: the fixed columns plus the
+/// narrowest PROCESS and SERVICE
+pub const MIN_TABLE_WIDTH: usize = FIXED_WIDTH + MIN_PROCESS_WIDTH + MIN_SERVICE_WIDTH;
+
+/// How `print_table` lays out its columns
+pub struct TableOptions<'a> {
+    /// Add a COMMAND column with each process's full command line
+    pub wide: bool,
+    /// `[columns]` widths from the config
+    pub columns: &'a Columns,
+    /// `--max-width`: never draw wider than this, even when piped
+    pub max_width: Option<usize>,
+    /// `--pager`: page the table in the terminal when it's taller than the screen
+    pub pager: bool,
+    /// Entries collapsed into summary rows (`[group]`; none with `--expand`)
+    pub grouping: &'a [Strategy],
+    /// `--group-by`: split the table into sections with subtotals
+    pub group_by: Option<GroupBy>,
+    /// `[watch] ports`: starred and drawn in the accent color
+    pub watched: &'a [u16],
+}
+
+/// Column widths for one `print_table` run
+struct TableLayout {
+    process: usize,
+    service: usize,
+    /// COMMAND width with `--wide`, or `None` when it's left out
+    command: Option<usize>,
+    /// Length of the divider under the header
+    width: usize,
+}
+
+/// Fit the columns to `term_width`, shrinking PROCESS and SERVICE on narrow
+/// terminals; with `wide`, COMMAND takes whatever is left. Unknown width
+/// (piped output) keeps the fixed layout and untruncated commands. Widths
+/// set in `[columns]` replace the defaults and cap PROCESS / COMMAND.
+fn table_layout(term_width: Option<usize>, wide: bool, columns: &Columns) -> TableLayout {
+    let process_width = columns.process.unwrap_or(PROCESS_WIDTH);
+    let command_cap = columns.command.unwrap_or(usize::MAX);
+    let Some(total) = term_width else {
+        return TableLayout {
+            process: process_width,
+            service: SERVICE_WIDTH,
+            command: wide.then_some(command_cap),
+            width: 100,
+        };
+    };
+
+    let room = total.saturating_sub(FIXED_WIDTH);
+    let natural = process_width + SERVICE_WIDTH;
+    if room < natural {
+        let process = (room * process_width / natural).max(MIN_PROCESS_WIDTH);
+        let service = room.saturating_sub(process).max(MIN_SERVICE_WIDTH);
+        return TableLayout {
+            process,
+            service,
+            command: None,
+            width: total,
+        };
+    }
+
+    if wide {
+        // Exactly the natural width leaves nothing for COMMAND
+        let command = room.saturating_sub(natural + 1).min(command_cap);
+        TableLayout {
+            process: process_width,
+            service: SERVICE_WIDTH,
+            command: (command >= MIN_COMMAND_WIDTH).then_some(command),
+            width: total,
+        }
+    } else {
+        TableLayout {
+            process: (room - SERVICE_WIDTH).min(columns.process.unwrap_or(MAX_PROCESS_WIDTH)),
+            service: SERVICE_WIDTH,
+            command: None,
+            width: total,
+        }
+    }
+}
+
+/// `ignored` is how many entries the ignore list hid, `total_sockets` the
+/// system-wide socket count and `hidden_udp` how many ephemeral UDP sockets
+/// the scan left out, all for the summary line.
+pub fn print_table(
+    entries: &[models::PortEntry],
+    unresolved: usize,
+    ignored: usize,
+    total_sockets: Option<usize>,
+    hidden_udp: usize,
+    opts: &TableOptions,
+) {
+    let out = io::stdout();
+    let detected = out
+        .is_terminal()
+        .then(|| crossterm::terminal::size().ok())
+        .flatten()
+        .map(|(cols, _)| cols as usize);
+    // --max-width caps a real terminal and stands in for one when piped
+    let term_width = match (detected, opts.max_width) {
+        (Some(cols), Some(max)) => Some(cols.min(max)),
+        (cols, max) => cols.or(max),
+    };
+    // Rendered in full first, so the pager can show it a screen at a time
+    let mut w = Vec::new();
+    let _ = write_table(
+        &mut w,
+        entries,
+        unresolved,
+        ignored,
+        total_sockets,
+        hidden_udp,
+        &TableOptions {
+            max_width: term_width,
+            ..*opts
+        },
+    );
+
+    let text = String::from_utf8_lossy(&w);
+    if opts.pager && out.is_terminal() && pager::page(&text).is_ok() {
+        return;
+    }
+    let _ = out.lock().write_all(text.as_bytes());
+}
+
+/// The table behind [`print_table`], laid out for `opts.max_width` alone
+/// (`None` is the fixed layout used when piped): `list --output` writes it
+/// to a file, where there's no terminal to measure.
+pub fn write_table(
+    w: &mut impl Write,
+    entries: &[models::PortEntry],
+    unresolved: usize,
+    ignored: usize,
+    total_sockets: Option<usize>,
+    hidden_udp: usize,
+    opts: &TableOptions,
+) -> io::Result<()> {
+    if entries.is_empty() {
+        if ignored > 0 {
+            writeln!(
+                w,
+                "No listening ports found ({} ignored; --show-ignored to list them).",
+                ignored
+            )?;
+        } else {
+            writeln!(w, "No listening ports found.")?;
+        }
+        return Ok(());
+    }
+
+    // One section per --group-by value, or a single untitled one
+    let sections: Vec<(Option<Section>, Vec<DisplayRow>)> = match opts.group_by {
+        Some(by) => group::sections(entries, by)
+            .into_iter()
+            .map(|section| {
+                let rows = display_rows(section.entries.iter().copied(), opts.grouping);
+                (Some(section), rows)
+            })
+            .collect(),
+        None => vec![(None, display_rows(entries, opts.grouping))],
+    };
+
+    let layout = table_layout(opts.max_width, opts.wide, opts.columns);
+    let (pw, sw) = (layout.process, layout.service);
+
+    let hdr = Color::Rgb {
+        r: 120,
+        g: 120,
+        b: 125,
+    };
+    let dim = Color::Rgb {
+        r: 100,
+        g: 100,
+        b: 105,
+    };
+    let divider = Color::Rgb {
+        r: 60,
+        g: 60,
+        b: 65,
+    };
+    let light = Color::Rgb {
+        r: 160,
+        g: 160,
+        b: 165,
+    };
+    let warn = Color::Rgb {
+        r: 234,
+        g: 179,
+        b: 8,
+    };
+
+    // Header
+    let _ = writeln!(
+        w,
+        "{}{}  {:<6} {:<5} {:<pw$} {:<sw$} {:<7} {:<7} {:<9} {}{}{}",
+        SetForegroundColor(hdr),
+        SetAttribute(Attribute::Bold),
+        "PORT",
+        "PROTO",
+        "PROCESS",
+        "SERVICE",
+        "PID",
+        "CPU",
+        "MEM",
+        if layout.command.is_some() {
+            format!("{:<UPTIME_WIDTH$} COMMAND", "UPTIME")
+        } else {
+            "UPTIME".to_string()
+        },
+        SetAttribute(Attribute::Reset),
+        ResetColor,
+    );
+    let _ = writeln!(
+        w,
+        "{}{}{}",
+        SetForegroundColor(divider),
+        "─".repeat(layout.width),
+        ResetColor,
+    );
+
+    for (i, (section, rows)) in sections.iter().enumerate() {
+        if let Some(section) = section {
+            let _ = writeln!(
+                w,
+                "{}{}{}{}{}  {}{}{}",
+                if i == 0 { "" } else { "\n" },
+                SetForegroundColor(hdr),
+                SetAttribute(Attribute::Bold),
+                section.title,
+                SetAttribute(Attribute::Reset),
+                SetForegroundColor(dim),
+                section.subtotal(),
+                ResetColor,
+            );
+        }
+        for row in rows {
+            match row {
+                DisplayRow::Single(e) => {
+                    let dimmed = theme().is_dimmed(e.protocol) || e.is_container_proxy();
+                    let paint = |c: Color| SetForegroundColor(if dimmed { dim } else { c });
+                    let cat_col = category_color(e.category);
+                    let port_col = if e.known_service.is_some() {
+                        Color::White
+                    } else {
+                        light
+                    };
+
+                    // Watched ports stand out even on dimmed rows
+                    let (port_col, port) = if opts.watched.contains(&e.port) {
+                        (SetForegroundColor(watch_color()), format!("{}★", e.port))
+                    } else {
+                        (paint(port_col), e.port.to_string())
+                    };
+                    let _ = write!(
+                        w,
+                        "{}{}  {:<6}{}",
+                        port_col,
+                        SetAttribute(Attribute::Bold),
+                        port,
+                        SetAttribute(Attribute::Reset)
+                    );
+                    let _ = write!(w, "{} {:<5}", paint(proto_color(e.protocol)), e.protocol);
+                    let _ = write!(
+                        w,
+                        "{} {:<pw$}",
+                        paint(cat_col),
+                        truncate(&e.process_name, pw)
+                    );
+
+                    let service = e
+                        .service_label()
+                        .map(|s| format!("{}{}", theme().category_prefix(e.category), s))
+                        .unwrap_or_default();
+                    if service.is_empty() {
+                        let _ = write!(w, "{} {:<sw$}", paint(divider), "·");
+                    } else {
+                        let _ = write!(
+                            w,
+                            "{}{} {:<sw$}{}",
+                            paint(cat_col),
+                            SetAttribute(Attribute::Bold),
+                            truncate(&service, sw),
+                            SetAttribute(Attribute::Reset)
+                        );
+                    }
+
+                    let _ = write!(w, "{} {:<7}", paint(dim), e.pid);
+                    let cpu_str = format!("{:.1}%", e.cpu_percent);
+                    let cpu_col = cpu_color(e.cpu_percent, dim);
+                    let _ = write!(w, "{} {:<7}", paint(cpu_col), cpu_str);
+                    let _ = write!(w, "{} {:<9}", paint(light), e.memory_display());
+                    let uptime = e.uptime_display();
+                    let _ = write!(w, "{} {}", paint(dim), uptime);
+                    if e.is_idle() {
+                        let _ = write!(w, "{} idle", paint(warn));
+                    }
+                    if let Some(cw) = layout.command {
+                        let used = uptime.chars().count() + if e.is_idle() { 5 } else { 0 };
+                        let _ = write!(
+                            w,
+                            "{}{} {}",
+                            " ".repeat(UPTIME_WIDTH.saturating_sub(used)),
+                            paint(dim),
+                            truncate(&e.process_cmd, cw)
+                        );
+                    }
+                    let _ = writeln!(w, "{}", ResetColor);
+                }
+                DisplayRow::Grouped(g) => {
+                    let first = g.first();
+                    let dimmed = theme().is_dimmed(first.protocol);
+                    let paint = |c: Color| SetForegroundColor(if dimmed { dim } else { c });
+                    let cat_col = category_color(first.category);
+                    let pids = g.pids();
+                    // Browsers count their ports, worker clusters their processes
+                    let (service, count) = match g.strategy {
+                        Strategy::Browser => (
+                            first.known_service.unwrap_or_default().to_string(),
+                            g.entries.len(),
+                        ),
+                        Strategy::Workers => (
+                            first
+                                .service_label()
+                                .unwrap_or_else(|| "workers".to_string()),
+                            pids.len(),
+                        ),
+                    };
+                    let svc_label = format!(
+                        "{}{} ×{}",
+                        theme().category_prefix(first.category),
+                        service,
+                        count
+                    );
+                    // Workers sharing one port show it; anything else is `···`
+                    let (port_col, port) = match g.ports().as_slice() {
+                        [port] if g.strategy == Strategy::Workers => {
+                            if opts.watched.contains(port) {
+                                (SetForegroundColor(watch_color()), format!("{}★", port))
+                            } else {
+                                (paint(dim), port.to_string())
+                            }
+                        }
+                        _ => (paint(dim), "···".to_string()),
+                    };
+                    let pid = match pids.as_slice() {
+                        [pid] => pid.to_string(),
+                        _ => format!("{}+", pids[0]),
+                    };
+
+                    let _ = write!(
+                        w,
+                        "{}{}  {:<6}{}",
+                        port_col,
+                        SetAttribute(Attribute::Bold),
+                        port,
+                        SetAttribute(Attribute::Reset)
+                    );
+                    let _ = write!(
+                        w,
+                        "{} {:<5}",
+                        paint(proto_color(first.protocol)),
+                        first.protocol
+                    );
+                    let _ = write!(
+                        w,
+                        "{} {:<pw$}",
+                        paint(cat_col),
+                        truncate(&first.process_name, pw)
+                    );
+                    let _ = write!(
+                        w,
+                        "{}{} {:<sw$}{}",
+                        paint(cat_col),
+                        SetAttribute(Attribute::Bold),
+                        truncate(&svc_label, sw),
+                        SetAttribute(Attribute::Reset)
+                    );
+                    let _ = write!(w, "{} {:<7}", paint(dim), pid);
+                    let cpu = g.cpu_percent();
+                    let _ = write!(w, "{} {:<7}", paint(dim), format!("{:.1}%", cpu));
+                    let _ = write!(w, "{} {:<9}", paint(light), memory_display(g.memory_mb()));
+                    let _ = write!(w, "{} {}", paint(dim), duration_display(g.uptime()));
+                    let _ = writeln!(w, "{}", ResetColor);
+                }
+            }
+        }
+    }
+
+    // Summary
+    let total = entries.len();
+    let tcp = entries
+        .iter()
+        .filter(|e| e.protocol == models::Protocol::Tcp)
+        .count();
+    let udp = total - tcp;
+    let services = entries.iter().filter(|e| e.known_service.is_some()).count();
+    let _ = write!(
+        w,
+        "\n{}{}{} ports{} ({} TCP, {} UDP)",
+        SetForegroundColor(hdr),
+        SetAttribute(Attribute::Bold),
+        total,
+        SetAttribute(Attribute::Reset),
+        tcp,
+        udp,
+    );
+    if services > 0 {
+        let _ = write!(w, " · {} known services", services);
+    }
+    if ignored > 0 {
+        let _ = write!(w, " · {} ignored", ignored);
+    }
+    if let Some(n) = total_sockets {
+        let _ = write!(w, " · {} total sockets", n);
+    }
+    if hidden_udp > 0 {
+        let _ = write!(w, " · {} UDP client sockets hidden (--all-udp)", hidden_udp);
+    }
+    if unresolved > 0 {
+        let _ = write!(
+            w,
+            "{} · {} not fully resolved",
+            SetForegroundColor(warn),
+            unresolved
+        );
+    }
+    writeln!(w, "{}", ResetColor)
+}
+
+/// Note after a `--host` table when the remote has no kav to report metrics
+pub fn print_remote_hint(host: &str) {
+    let _ = writeln!(
+        io::stdout(),
+        "{}kav isn't installed on {}; sockets came from `ss`, so CPU, memory and \
+         uptime are unavailable.{}",
+        SetForegroundColor(Color::Rgb {
+            r: 234,
+            g: 179,
+            b: 8
+        }),
+        host,
+        ResetColor
+    );
+}
+
+/// `kav check` output for one entry: the service in its category color and
+/// the CPU figure in the table's CPU colors
+pub fn print_check(entry: &models::PortEntry, include_threads: bool, show_inode: bool) {
+    let mut w = io::stdout().lock();
+    let _ = write!(
+        w,
+        "{}Port {}{} ({}) — {} (PID {})",
+        SetAttribute(Attribute::Bold),
+        entry.port,
+        SetAttribute(Attribute::Reset),
+        entry.protocol,
+        entry.process_name,
+        entry.pid
+    );
+    if let Some(svc) = entry.known_service {
+        let _ = write!(
+            w,
+            " {}[{}{}]{}",
+            SetForegroundColor(category_color(entry.category)),
+            theme().category_prefix(entry.category),
+            svc,
+            ResetColor
+        );
+    }
+    let _ = writeln!(w);
+    if !entry.process_cmd.is_empty() {
+        let _ = writeln!(w, "  Command: {}", entry.process_cmd);
+    }
+    let _ = writeln!(
+        w,
+        "  CPU: {}{:.1}%{}  Memory: {}  Uptime: {}",
+        SetForegroundColor(cpu_color(entry.cpu_percent, Color::Reset)),
+        entry.cpu_percent,
+        ResetColor,
+        entry.memory_display(),
+        entry.uptime_display()
+    );
+    if include_threads {
+        let _ = writeln!(w, "  Threads: {}", entry.thread_display());
+    }
+    if let Some(inode) = entry.inode.filter(|_| show_inode) {
+        let _ = writeln!(w, "  Inode: {} (socket:[{}])", inode, inode);
+    }
+}
+
+/// A line about to be acted on, e.g. the list of processes a kill will take
+pub fn print_warning(line: &str) {
+    let _ = writeln!(
+        io::stdout(),
+        "{}{}{}",
+        SetForegroundColor(Color::Rgb {
+            r: 234,
+            g: 179,
+            b: 8
+        }),
+        line,
+        ResetColor
+    );
+}
+
+/// The result of a kill: green when it went through, red when it didn't
+pub fn print_outcome(ok: bool, line: &str) {
+    let color = if ok {
+        Color::Rgb {
+            r: 34,
+            g: 197,
+            b: 94,
+        }
+    } else {
+        Color::Rgb {
+            r: 239,
+            g: 68,
+            b: 68,
+        }
+    };
+    let _ = writeln!(
+        io::stdout(),
+        "{}{}{}",
+        SetForegroundColor(color),
+        line,
+        ResetColor
+    );
+}
+
+/// One-line hint shown after the table when the scan looked under-privileged
+pub fn print_privilege_hint() {
+    let _ = writeln!(
+        io::stdout(),
+        "{}Some sockets belong to processes this user can't see; \
+         run with sudo for complete results.{}",
+        SetForegroundColor(Color::Rgb {
+            r: 234,
+            g: 179,
+            b: 8
+        }),
+        ResetColor
+    );
+}
+
+/// `kav dev`: `3000: node [Next.js]` or `3000: free`, one line per port
+/// `kav --list-known`: the well-known port table, by port, colored by category
+pub fn print_known_ports() {
+    let mut w = io::stdout().lock();
+    let _ = writeln!(
+        w,
+        "{}{}{:>5}  {:<18} CATEGORY{}",
+        SetAttribute(Attribute::Bold),
+        SetForegroundColor(Color::Rgb {
+            r: 120,
+            g: 120,
+            b: 125,
+        }),
+        "PORT",
+        "SERVICE",
+        ResetColor,
+    );
+    for (port, label, category) in ports::sorted() {
+        let _ = writeln!(
+            w,
+            "{:>5}  {}{:<18}{} {}",
+            port,
+            SetForegroundColor(category_color(category)),
+            label,
+            ResetColor,
+            category.label()
+        );
+    }
+    let _ = write!(w, "{}", SetAttribute(Attribute::Reset));
+}
+
+pub fn print_dev_ports(ports: &[u16], entries: &[models::PortEntry]) {
+    let free = Color::Rgb {
+        r: 100,
+        g: 100,
+        b: 105,
+    };
+    let out = io::stdout();
+    let mut w = out.lock();
+
+    for &port in ports {
+        let mut owners: Vec<String> = Vec::new();
+        for e in entries.iter().filter(|e| e.port == port) {
+            let owner = match e.known_service {
+                Some(svc) => format!("{} [{}]", e.process_name, svc),
+                None => e.process_name.clone(),
+            };
+            if !owners.contains(&owner) {
+                owners.push(owner);
+            }
+        }
+
+        if owners.is_empty() {
+            let _ = writeln!(
+                w,
+                "{:>5}: {}free{}",
+                port,
+                SetForegroundColor(free),
+                ResetColor
+            );
+        } else {
+            let color = entries
+                .iter()
+                .find(|e| e.port == port)
+                .map(|e| category_color(e.category))
+                .unwrap_or(free);
+            let _ = writeln!(
+                w,
+                "{:>5}: {}{}{}",
+                port,
+                SetForegroundColor(color),
+                owners.join(", "),
+                ResetColor
+            );
+        }
+    }
+}
+
+/// `kav diff`: `+` added, `-` removed, `~` same port with a new PID
+pub fn print_snapshot_diff(diff: &SnapshotDiff) {
+    let green = Color::Rgb {
+        r: 34,
+        g: 197,
+        b: 94,
+    };
+    let red = Color::Rgb {
+        r: 239,
+        g: 68,
+        b: 68,
+    };
+    let yellow = Color::Rgb {
+        r: 234,
+        g: 179,
+        b: 8,
+    };
+    let service = |s: &Option<String>| s.as_ref().map(|s| format!(" [{}]", s)).unwrap_or_default();
+
+    if diff.is_empty() {
+        println!("No changes.");
+        return;
+    }
+
+    let out = io::stdout();
+    let mut w = out.lock();
+    for e in &diff.added {
+        let _ = writeln!(
+            w,
+            "{}+ {:<6} {:<4} {} (PID {}){}{}",
+            SetForegroundColor(green),
+            e.port,
+            e.protocol,
+            e.process,
+            e.pid,
+            service(&e.service),
+            ResetColor
+        );
+    }
+    for e in &diff.removed {
+        let _ = writeln!(
+            w,
+            "{}- {:<6} {:<4} {} (PID {}){}{}",
+            SetForegroundColor(red),
+            e.port,
+            e.protocol,
+            e.process,
+            e.pid,
+            service(&e.service),
+            ResetColor
+        );
+    }
+    for (old, new) in &diff.pid_changed {
+        let _ = writeln!(
+            w,
+            "{}~ {:<6} {:<4} {} (PID {}) → {} (PID {}){}",
+            SetForegroundColor(yellow),
+            new.port,
+            new.protocol,
+            old.process,
+            old.pid,
+            new.process,
+            new.pid,
+            ResetColor
+        );
+    }
+    let _ = writeln!(
+        w,
+        "\n{} added, {} removed, {} changed PID",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.pid_changed.len()
+    );
+}
+
+pub fn print_audit(entries: &[models::PortEntry]) {
+    let idle: Vec<_> = entries.iter().filter(|e| e.is_idle()).collect();
+    let conflicts = models::port_conflicts(entries);
+
+    if idle.is_empty() && conflicts.is_empty() {
+        println!("No issues found.");
+        return;
+    }
+
+    if !conflicts.is_empty() {
+        println!(
+            "Port conflicts ({}) — different processes on the same port, on different addresses:",
+            conflicts.len()
+        );
+        for group in &conflicts {
+            let holders: Vec<String> = group
+                .iter()
+                .map(|e| format!("{} (PID {}) on {}", e.process_name, e.pid, e.local_addr))
+                .collect();
+            println!(
+                "  {:<6} {:<4} {}",
+                group[0].port,
+                group[0].protocol,
+                holders.join(", ")
+            );
+        }
+        if !idle.is_empty() {
+            println!();
+        }
+    }
+    if idle.is_empty() {
+        return;
+    }
+
+    println!(
+        "Idle dev servers ({}) — low CPU for over {}h, consider freeing the port:",
+        idle.len(),
+        models::IDLE_MIN_UPTIME.as_secs() / 3600
+    );
+    for e in &idle {
+        println!(
+            "  {:<6} {} (PID {}) — up {}, {:.1}% CPU",
+            e.port,
+            e.process_name,
+            e.pid,
+            e.uptime_display(),
+            e.cpu_percent
+        );
+    }
+}
+
+pub fn write_json(w: &mut impl Write, entries: &[models::PortEntry]) -> Result<()> {
+    let rows = json_rows(entries);
+    if rows.is_empty() {
+        writeln!(w, "[]")?;
+    } else {
+        writeln!(w, "[\n  {}\n]", rows.join(",\n  "))?;
+    }
+    Ok(())
+}
+
+/// `list --format json-compact`: the [`write_json`] array on one line
+pub fn write_json_compact(w: &mut impl Write, entries: &[models::PortEntry]) -> Result<()> {
+    writeln!(w, "[{}]", json_rows(entries).join(","))?;
+    Ok(())
+}
+
+/// `list --flat-json`: like [`write_json`], but through [`FlatEntry`] so every
+/// key holds a plain value and none is ever `null`
+pub fn write_flat_json(w: &mut impl Write, entries: &[models::PortEntry]) -> Result<()> {
+    let rows: Vec<String> = entries
+        .iter()
+        .map(|e| FlatEntry::from(e).to_json())
+        .collect();
+    if rows.is_empty() {
+        writeln!(w, "[]")?;
+    } else {
+        writeln!(w, "[\n  {}\n]", rows.join(",\n  "))?;
+    }
+    Ok(())
+}
+
+/// An entry with its optional fields filled in, for tools that infer a schema
+/// from the data: missing text becomes "" and missing numbers 0
+struct FlatEntry<'a> {
+    port: u16,
+    protocol: models::Protocol,
+    address: IpAddr,
+    state: &'static str,
+    process: &'a str,
+    service: &'static str,
+    pid: u32,
+    ppid: u32,
+    cpu: f32,
+    memory_mb: f64,
+    memory_percent: f32,
+    uptime_secs: u64,
+    partial: bool,
+}
+
+impl<'a> From<&'a models::PortEntry> for FlatEntry<'a> {
+    fn from(e: &'a models::PortEntry) -> Self {
+        Self {
+            port: e.port,
+            protocol: e.protocol,
+            address: e.local_addr,
+            state: e.state.map(|s| s.label()).unwrap_or_default(),
+            process: &e.process_name,
+            service: e.known_service.unwrap_or_default(),
+            pid: e.pid,
+            ppid: e.ppid.unwrap_or_default(),
+            cpu: e.cpu_percent,
+            memory_mb: e.memory_mb,
+            memory_percent: e.memory_percent.unwrap_or_default(),
+            uptime_secs: e.uptime.as_secs(),
+            partial: e.partial,
+        }
+    }
+}
+
+impl FlatEntry<'_> {
+    fn to_json(&self) -> String {
+        format!(
+            r#"{{"port":{},"protocol":"{}","address":"{}","state":"{}","process":{},"service":{},"pid":{},"ppid":{},"cpu":{:.1},"memory_mb":{:.1},"memory_percent":{:.2},"uptime_secs":{},"partial":{}}}"#,
+            self.port,
+            self.protocol,
+            self.address,
+            self.state,
+            json::quote(self.process),
+            json::quote(self.service),
+            self.pid,
+            self.ppid,
+            self.cpu,
+            self.memory_mb,
+            self.memory_percent,
+            self.uptime_secs,
+            self.partial,
+        )
+    }
+}
+
+/// `list --json --meta`: the entries wrapped in an object that says when,
+/// where and what was scanned, in the versioned snapshot shape `kav diff` reads
+pub fn write_json_meta(w: &mut impl Write, result: &ScanResult) -> Result<()> {
+    let rows = json_rows(&result.entries);
+    writeln!(w, "{{")?;
+    writeln!(w, r#"  "version":{},"#, diff::SNAPSHOT_VERSION)?;
+    writeln!(
+        w,
+        r#"  "scanned_at":{},"#,
+        json::quote(
+            &result
+                .scanned_at
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+        )
+    )?;
+    writeln!(
+        w,
+        r#"  "host":{},"#,
+        result
+            .host
+            .as_deref()
+            .map(json::quote)
+            .unwrap_or_else(|| "null".to_string())
+    )?;
+    writeln!(w, r#"  "tcp":{},"#, result.tcp)?;
+    writeln!(w, r#"  "udp":{},"#, result.udp)?;
+    writeln!(w, r#"  "truncated":{},"#, result.truncated())?;
+    if rows.is_empty() {
+        writeln!(w, r#"  "entries":[]"#)?;
+    } else {
+        writeln!(w, r#"  "entries":["#)?;
+        writeln!(w, "    {}", rows.join(",\n    "))?;
+        writeln!(w, "  ]")?;
+    }
+    writeln!(w, "}}")?;
+    Ok(())
+}
+
+/// One JSON object per entry, without separators or indentation
+fn json_rows(entries: &[models::PortEntry]) -> Vec<String> {
+    // Manual JSON to avoid serde dependency
+    entries
+        .iter()
+        .map(|e| {
+            format!(
+            r#"{{"port":{},"protocol":"{}","address":"{}","state":{},"process":{},"service":{},"pid":{},"ppid":{},"cpu":{:.1},"memory_mb":{:.1},"memory_percent":{},"uptime_secs":{},"partial":{}}}"#,
+            e.port,
+            e.protocol,
+            e.local_addr,
+            e.state
+                .map(|s| format!("\"{}\"", s.label()))
+                .unwrap_or_else(|| "null".to_string()),
+            json::quote(&e.process_name),
+            e.known_service
+                .map(json::quote)
+                .unwrap_or_else(|| "null".to_string()),
+            e.pid,
+            e.ppid
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| "null".to_string()),
+            e.cpu_percent,
+            e.memory_mb,
+            e.memory_percent
+                .map(|p| format!("{:.2}", p))
+                .unwrap_or_else(|| "null".to_string()),
+            e.uptime.as_secs(),
+            e.partial,
+        )
+        })
+        .collect()
+}
+
+/// Highlighted watch-mode alert line, optionally ringing the terminal bell
+pub fn print_alert(line: &str, bell: bool) {
+    let out = io::stdout();
+    let mut w = out.lock();
+    let _ = writeln!(
+        w,
+        "{}{}{}⚠ {}{}",
+        if bell { "\x07" } else { "" },
+        SetForegroundColor(Color::Rgb {
+            r: 239,
+            g: 68,
+            b: 68,
+        }),
+        SetAttribute(Attribute::Bold),
+        line,
+        SetAttribute(Attribute::Reset),
+    );
+    let _ = write!(w, "{}", ResetColor);
+}
+
+/// `kav kill --json` result: `{"port":…,"killed":[{pid,name}],"failed":[{pid,error}]}`
+pub fn print_kill_json(port: u16, killed: &[(u32, &str)], failed: &[(u32, String)]) {
+    let killed: Vec<String> = killed
+        .iter()
+        .map(|(pid, name)| format!(r#"{{"pid":{},"name":{}}}"#, pid, json::quote(name)))
+        .collect();
+    let failed: Vec<String> = failed
+        .iter()
+        .map(|(pid, error)| format!(r#"{{"pid":{},"error":{}}}"#, pid, json::quote(error)))
+        .collect();
+    println!(
+        r#"{{"port":{},"killed":[{}],"failed":[{}]}}"#,
+        port,
+        killed.join(","),
+        failed.join(",")
+    );
+}
+
+/// Tab-separated, colorless output for `sort`/`awk`/`cut`: one entry per line,
+/// raw numbers (CPU %, memory MB, uptime seconds) and `-` for empty fields.
+pub fn write_plain(
+    w: &mut impl Write,
+    entries: &[models::PortEntry],
+    header: bool,
+) -> io::Result<()> {
+    if header {
+        writeln!(
+            w,
+            "PORT\tPROTO\tPROCESS\tSERVICE\tPID\tCPU\tMEM_MB\tUPTIME_S"
+        )?;
+    }
+    for e in entries {
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}\t{}\t{:.1}\t{:.1}\t{}",
+            e.port,
+            e.protocol,
+            plain_field(&e.process_name),
+            plain_field(e.known_service.unwrap_or("")),
+            e.pid,
+            e.cpu_percent,
+            e.memory_mb,
+            e.uptime.as_secs(),
+        )?;
+    }
+    Ok(())
+}
+
+/// CSV with a header row: the `--plain` fields plus the address, quoted where
+/// a field needs it (RFC 4180)
+pub fn write_csv(w: &mut impl Write, entries: &[models::PortEntry]) -> io::Result<()> {
+    writeln!(
+        w,
+        "port,protocol,address,process,service,pid,cpu,memory_mb,uptime_secs"
+    )?;
+    for e in entries {
+        writeln!(
+            w,
+            "{},{},{},{},{},{},{:.1},{:.1},{}",
+            e.port,
+            e.protocol,
+            e.local_addr,
+            csv_field(&e.process_name),
+            csv_field(e.known_service.unwrap_or("")),
+            e.pid,
+            e.cpu_percent,
+            e.memory_mb,
+            e.uptime.as_secs(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Quote a CSV field holding a comma, quote or line break, doubling its quotes
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// `--format markdown`: a GitHub-flavored pipe table, with a COMMAND column
+/// when `wide`
+pub fn write_markdown(
+    w: &mut impl Write,
+    entries: &[models::PortEntry],
+    wide: bool,
+) -> io::Result<()> {
+    let mut header = vec![
+        "PORT", "PROTO", "ADDRESS", "PROCESS", "SERVICE", "PID", "CPU", "MEM", "UPTIME",
+    ];
+    if wide {
+        header.push("COMMAND");
+    }
+    writeln!(w, "| {} |", header.join(" | "))?;
+    writeln!(w, "|{}", "---|".repeat(header.len()))?;
+    for e in entries {
+        let mut cells = vec![
+            e.port.to_string(),
+            e.protocol.to_string(),
+            e.local_addr.to_string(),
+            markdown_cell(&e.process_name),
+            markdown_cell(&e.service_label().unwrap_or_default()),
+            e.pid.to_string(),
+            format!("{:.1}%", e.cpu_percent),
+            e.memory_display(),
+            e.uptime_display(),
+        ];
+        if wide {
+            cells.push(markdown_cell(&e.process_cmd));
+        }
+        writeln!(w, "| {} |", cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+/// Keep a table cell on one line, with its pipes escaped
+fn markdown_cell(s: &str) -> String {
+    s.replace(['\n', '\r'], " ").replace('|', "\\|")
+}
+
+/// `--a11y`: one plain sentence per entry for screen readers — no color, no
+/// box drawing, no columns to line up
+pub fn write_a11y(
+    w: &mut impl Write,
+    entries: &[models::PortEntry],
+    unresolved: usize,
+    ignored: usize,
+) -> io::Result<()> {
+    for e in entries {
+        let mut parts = vec![format!("Port {}", e.port), e.protocol.to_string()];
+        if let Some(svc) = e.known_service {
+            parts.push(svc.to_string());
+        }
+        parts.push(format!("process {}", plain_field(&e.process_name)));
+        if e.pid != 0 {
+            parts.push(format!("PID {}", e.pid));
+        }
+        parts.push(format!(
+            "using {:.1}% CPU and {:.1} MB of memory",
+            e.cpu_percent, e.memory_mb
+        ));
+        if !e.uptime.is_zero() {
+            parts.push(format!("running for {}", spoken_duration(e.uptime)));
+        }
+        parts.push(if e.is_public() {
+            "reachable from the network".to_string()
+        } else {
+            "local only".to_string()
+        });
+        writeln!(w, "{}.", parts.join(", "))?;
+    }
+
+    match entries.len() {
+        0 => writeln!(w, "No listening ports found.")?,
+        1 => writeln!(w, "1 port in total.")?,
+        n => writeln!(w, "{} ports in total.", n)?,
+    }
+    if ignored > 0 {
+        writeln!(w, "{} more hidden by the ignore list.", ignored)?;
+    }
+    if unresolved > 0 {
+        writeln!(
+            w,
+            "{} {} could not be matched to a process; run with sudo for complete results.",
+            unresolved,
+            if unresolved == 1 { "socket" } else { "sockets" }
+        )?;
+    }
+    Ok(())
+}
+
+/// Largest whole unit, spelled out: "3 hours", "1 day"
+fn spoken_duration(d: std::time::Duration) -> String {
+    let secs = d.as_secs();
+    let (n, unit) = match secs {
+        0..=59 => (secs, "second"),
+        60..=3599 => (secs / 60, "minute"),
+        3600..=86399 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" })
+}
+
+/// `text` without its color and style escape sequences
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI: `ESC [`, parameters, then one final byte in @..~
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Keep a field on one line and inside its column
+fn plain_field(s: &str) -> String {
+    if s.is_empty() {
+        "-".to_string()
+    } else {
+        s.replace(['\t', '\n', '\r'], " ")
+    }
+}
+
+/// Cut `s` to at most `max` characters, marking the cut with `…`
+pub fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let kept: String = s.chars().take(max.saturating_sub(1)).collect();
+        format!("{}…", kept)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markdown(entries: &[models::PortEntry], wide: bool) -> Vec<String> {
+        let mut w = Vec::new();
+        write_markdown(&mut w, entries, wide).unwrap();
+        String::from_utf8(w)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn compact_json_is_one_line() {
+        let mut w = Vec::new();
+        write_json_compact(&mut w, &[]).unwrap();
+        assert_eq!(w, b"[]\n");
+
+        let entries = [
+            models::PortEntry::fixture(3000, 42, "node"),
+            models::PortEntry::fixture(5432, 7, "postgres"),
+        ];
+        let mut w = Vec::new();
+        write_json_compact(&mut w, &entries).unwrap();
+        let text = String::from_utf8(w).unwrap();
+        let line = text.strip_suffix('\n').unwrap();
+        assert!(!line.contains(char::is_whitespace));
+        let doc = json::parse(line).unwrap();
+        assert_eq!(doc.as_array().map(|a| a.len()), Some(2));
+    }
+
+    #[test]
+    fn flat_json_has_no_nulls() {
+        let mut udp = models::PortEntry::fixture(5353, 9, "mdnsd");
+        udp.protocol = models::Protocol::Udp;
+        udp.state = None;
+        let entries = [
+            models::PortEntry::fixture(3000, 42, "node"),
+            udp,
+            models::PortEntry {
+                known_service: Some("PostgreSQL"),
+                ppid: Some(1),
+                memory_percent: Some(1.5),
+                ..models::PortEntry::fixture(5432, 7, "postgres")
+            },
+        ];
+        let mut w = Vec::new();
+        write_flat_json(&mut w, &entries).unwrap();
+        let text = String::from_utf8(w).unwrap();
+        assert!(!text.contains("null"));
+
+        let doc = json::parse(&text).unwrap();
+        let rows = doc.as_array().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].get("service").and_then(|v| v.as_str()), Some(""));
+        assert_eq!(rows[1].get("state").and_then(|v| v.as_str()), Some(""));
+        assert_eq!(rows[1].get("ppid").and_then(|v| v.as_u32()), Some(0));
+        assert_eq!(
+            rows[2].get("service").and_then(|v| v.as_str()),
+            Some("PostgreSQL")
+        );
+    }
+
+    #[test]
+    fn csv_quotes_awkward_fields() {
+        let entries = [
+            models::PortEntry::fixture(3000, 42, "node"),
+            models::PortEntry::fixture(8080, 7, "my \"app\", v2"),
+        ];
+        let mut w = Vec::new();
+        write_csv(&mut w, &entries).unwrap();
+        let text = String::from_utf8(w).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "3000,TCP,127.0.0.1,node,,42,0.0,0.0,0");
+        assert!(lines[2].starts_with(r#"8080,TCP,127.0.0.1,"my ""app"", v2",,7,"#));
+    }
+
+    #[test]
+    fn browser_group_sorts_by_its_representative_port() {
+        let browser = |port| models::PortEntry {
+            category: ServiceCategory::Browser,
+            known_service: Some("Chrome DevTools"),
+            ..models::PortEntry::fixture(port, 30, "chrome")
+        };
+        let mut entries = vec![
+            models::PortEntry::fixture(9999, 10, "api"),
+            browser(9223),
+            models::PortEntry::fixture(3000, 11, "node"),
+            browser(9222),
+            models::PortEntry::fixture(5432, 12, "postgres"),
+        ];
+        models::sort_entries(
+            &mut entries,
+            models::SortField::Port,
+            models::SortField::Port,
+        );
+
+        let rows = display_rows(&entries, &[Strategy::Browser]);
+        let order: Vec<(u16, bool)> = rows
+            .iter()
+            .map(|row| {
+                let grouped = matches!(row, DisplayRow::Grouped(_));
+                (row.representative().port, grouped)
+            })
+            .collect();
+        assert_eq!(
+            order,
+            [(3000, false), (5432, false), (9222, true), (9999, false)]
+        );
+    }
+
+    /// Cells in a table row, not counting escaped pipes
+    fn columns(row: &str) -> usize {
+        row.replace("\\|", "").matches('|').count() - 1
+    }
+
+    #[test]
+    fn markdown_has_separator_and_matching_columns() {
+        let mut entry = models::PortEntry::fixture(3000, 42, "node|dev");
+        entry.process_cmd = "node -e 'a || b'".to_string();
+        let lines = markdown(&[entry], true);
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "|---|---|---|---|---|---|---|---|---|---|");
+        assert!(lines.iter().all(|row| columns(row) == 10));
+        assert!(lines[2].contains("node\\|dev"));
+        assert!(lines[2].contains("a \\|\\| b"));
+        assert!(!lines[2].contains('\x1b'));
+    }
+
+    #[test]
+    fn markdown_without_wide_leaves_out_command() {
+        let lines = markdown(&[models::PortEntry::fixture(5432, 7, "postgres")], false);
+        assert_eq!(lines[0].matches('|').count() - 1, 9);
+        assert!(!lines[0].contains("COMMAND"));
+    }
+
+    #[test]
+    fn table_layout_at_the_edges() {
+        let columns = Columns::default();
+        let natural = FIXED_WIDTH + PROCESS_WIDTH + SERVICE_WIDTH;
+        for wide in [false, true] {
+            let layout = table_layout(Some(natural), wide, &columns);
+            assert_eq!(
+                (layout.process, layout.service, layout.command),
+                (PROCESS_WIDTH, SERVICE_WIDTH, None)
+            );
+            assert_eq!(layout.width, natural);
+
+            let layout = table_layout(Some(MIN_TABLE_WIDTH), wide, &columns);
+            assert_eq!(
+                (layout.process, layout.service, layout.command),
+                (MIN_PROCESS_WIDTH, MIN_SERVICE_WIDTH, None)
+            );
+            assert_eq!(layout.width, MIN_TABLE_WIDTH);
+        }
+
+        // COMMAND only appears once it gets a usable width
+        let room_for_command = natural + 1 + MIN_COMMAND_WIDTH;
+        let layout = table_layout(Some(room_for_command - 1), true, &columns);
+        assert_eq!(layout.command, None);
+        let layout = table_layout(Some(room_for_command), true, &columns);
+        assert_eq!(layout.command, Some(MIN_COMMAND_WIDTH));
+    }
+}
@@ -0,0 +1,101 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{Attribute, ResetColor, SetAttribute},
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+
+/// Show already-rendered (colored) output a screen at a time, like `less -R`.
+/// Output that fits on one screen is printed as is.
+///
+/// Keys: ↑/k and ↓/j/Enter scroll a line, Space/PageDown/f and PageUp/b a
+/// screen, g/Home and G/End jump to either end, q/Esc/Ctrl-C quit.
+pub fn page(text: &str) -> Result<()> {
+    let lines: Vec<&str> = text.lines().collect();
+    let (_, rows) = terminal::size()?;
+    if lines.len() < rows as usize {
+        print!("{}", text);
+        return Ok(());
+    }
+
+    let mut stdout = io::stdout();
+    enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+    let result = run(&lines, &mut stdout);
+    execute!(stdout, LeaveAlternateScreen, cursor::Show)?;
+    disable_raw_mode()?;
+    result
+}
+
+fn run(lines: &[&str], stdout: &mut io::Stdout) -> Result<()> {
+    let mut top = 0usize;
+    loop {
+        // The last row holds the status line
+        let view = terminal::size()?.1.saturating_sub(1).max(1) as usize;
+        let max_top = lines.len().saturating_sub(view);
+        top = top.min(max_top);
+        draw(stdout, lines, top, view)?;
+
+        let Event::Key(KeyEvent {
+            code, modifiers, ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Enter => top += 1,
+            KeyCode::Up | KeyCode::Char('k') => top = top.saturating_sub(1),
+            KeyCode::PageDown | KeyCode::Char(' ') | KeyCode::Char('f') => top += view,
+            KeyCode::PageUp | KeyCode::Char('b') => top = top.saturating_sub(view),
+            KeyCode::Home | KeyCode::Char('g') => top = 0,
+            KeyCode::End | KeyCode::Char('G') => top = max_top,
+            _ => {}
+        }
+    }
+}
+
+fn draw(stdout: &mut io::Stdout, lines: &[&str], top: usize, view: usize) -> Result<()> {
+    for (row, line) in lines.iter().skip(top).take(view).enumerate() {
+        queue!(
+            stdout,
+            cursor::MoveTo(0, row as u16),
+            Clear(ClearType::CurrentLine)
+        )?;
+        write!(stdout, "{}", line)?;
+        queue!(stdout, ResetColor, SetAttribute(Attribute::Reset))?;
+    }
+    let shown = lines.len().saturating_sub(top).min(view);
+    for row in shown..view {
+        queue!(
+            stdout,
+            cursor::MoveTo(0, row as u16),
+            Clear(ClearType::CurrentLine)
+        )?;
+    }
+
+    queue!(
+        stdout,
+        cursor::MoveTo(0, view as u16),
+        Clear(ClearType::CurrentLine),
+        SetAttribute(Attribute::Reverse)
+    )?;
+    write!(
+        stdout,
+        " lines {}-{} of {}   ↑↓ line  space/b page  g/G ends  q quit ",
+        top + 1,
+        top + shown,
+        lines.len()
+    )?;
+    queue!(stdout, SetAttribute(Attribute::Reset))?;
+    stdout.flush()?;
+    Ok(())
+}
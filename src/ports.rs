@@ -0,0 +1,88 @@
+use crate::models::ServiceCategory;
+
+/// Well-known ports and what usually listens on them, the last guess when
+/// neither the process name nor its command line says more. Each port
+/// appears once; `kav --list-known` prints the table sorted by port.
+pub const KNOWN_PORTS: &[(u16, &str, ServiceCategory)] = &[
+    // Dev servers
+    (3000, "Next.js / Rails", ServiceCategory::DevServer),
+    (3001, "React Dev", ServiceCategory::DevServer),
+    (4000, "Phoenix", ServiceCategory::DevServer),
+    (4200, "Angular", ServiceCategory::DevServer),
+    (5173, "Vite", ServiceCategory::DevServer),
+    (5174, "Vite", ServiceCategory::DevServer),
+    (8000, "Django / FastAPI", ServiceCategory::DevServer),
+    (8080, "HTTP Alt", ServiceCategory::DevServer),
+    (8443, "HTTPS Alt", ServiceCategory::DevServer),
+    (8888, "Jupyter", ServiceCategory::DevServer),
+    (9000, "PHP-FPM", ServiceCategory::DevServer),
+    (19006, "Expo", ServiceCategory::DevServer),
+    // Databases
+    (3306, "MySQL", ServiceCategory::Database),
+    (5432, "PostgreSQL", ServiceCategory::Database),
+    (5433, "PostgreSQL Alt", ServiceCategory::Database),
+    (27017, "MongoDB", ServiceCategory::Database),
+    (26257, "CockroachDB", ServiceCategory::Database),
+    // Cache / brokers
+    (6379, "Redis", ServiceCategory::Cache),
+    (11211, "Memcached", ServiceCategory::Cache),
+    (9092, "Kafka", ServiceCategory::Cache),
+    (5672, "RabbitMQ", ServiceCategory::Cache),
+    (15672, "RabbitMQ UI", ServiceCategory::Cache),
+    // Container / orchestration
+    (2375, "Docker", ServiceCategory::Container),
+    (2376, "Docker", ServiceCategory::Container),
+    // Dev tools
+    (11434, "Ollama", ServiceCategory::DevServer),
+    // System
+    (22, "SSH", ServiceCategory::System),
+    (80, "HTTP", ServiceCategory::System),
+    (443, "HTTPS", ServiceCategory::System),
+    (53, "DNS", ServiceCategory::System),
+    (9090, "Prometheus", ServiceCategory::System),
+    (2019, "Caddy Admin", ServiceCategory::System),
+];
+
+/// The label and category [`KNOWN_PORTS`] gives `port`
+pub fn lookup(port: u16) -> Option<(&'static str, ServiceCategory)> {
+    KNOWN_PORTS
+        .iter()
+        .find(|(p, _, _)| *p == port)
+        .map(|&(_, label, category)| (label, category))
+}
+
+/// [`KNOWN_PORTS`] in port order
+pub fn sorted() -> Vec<(u16, &'static str, ServiceCategory)> {
+    let mut ports = KNOWN_PORTS.to_vec();
+    ports.sort_by_key(|&(port, _, _)| port);
+    ports
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn each_port_is_listed_once() {
+        let mut seen = HashSet::new();
+        for &(port, label, _) in KNOWN_PORTS {
+            assert!(seen.insert(port), "port {} is listed twice", port);
+            assert!(!label.is_empty(), "port {} has no label", port);
+        }
+    }
+
+    #[test]
+    fn looks_up_labels_and_categories() {
+        assert_eq!(
+            lookup(5432),
+            Some(("PostgreSQL", ServiceCategory::Database))
+        );
+        assert_eq!(lookup(5174), Some(("Vite", ServiceCategory::DevServer)));
+        assert_eq!(lookup(1), None);
+        let ports: Vec<u16> = sorted().iter().map(|&(port, _, _)| port).collect();
+        assert!(ports.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(ports.len(), KNOWN_PORTS.len());
+    }
+}
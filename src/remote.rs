@@ -0,0 +1,361 @@
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr};
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+
+use crate::json::{self, Json};
+use crate::models::{BacklogInfo, PortEntry, Protocol, SocketState};
+use crate::scanner::{is_ephemeral_udp, ScanOptions, ScanResult, UNKNOWN_PROCESS};
+use crate::util::identify_service;
+
+/// First line the remote prints when it falls back to `ss`
+const SS_MARKER: &str = "KAVAL-SS";
+
+/// A scan run on another machine
+pub struct RemoteScan {
+    pub result: ScanResult,
+    /// The remote has no kav, so CPU, memory and uptime are missing
+    pub limited: bool,
+}
+
+/// Scan `host` over SSH. Runs `kav list --json` there when it's installed,
+/// otherwise falls back to `ss` (Linux), which only knows sockets and owners.
+/// Uses the user's SSH config and keys; never prompts.
+pub fn scan(host: &str, opts: &ScanOptions) -> Result<RemoteScan> {
+    if host.starts_with('-') || host.is_empty() {
+        bail!("invalid host `{}`", host);
+    }
+
+    let protocol = match (opts.tcp, opts.udp) {
+        (true, false) => "tcp",
+        (false, true) => "udp",
+        _ => "both",
+    };
+    // `ss` drops its Netid column when asked for one protocol, so it always
+    // lists both and `parse_ss` keeps the ones asked for
+    let remote_cmd = format!(
+        "if command -v kav >/dev/null 2>&1; then kav list --json --show-ignored --protocol {}{}; \
+         else echo {}; ss -Htulnp; fi",
+        protocol,
+        if opts.ephemeral_udp {
+            " --ephemeral-udp"
+        } else {
+            ""
+        },
+        SS_MARKER
+    );
+
+    let output = Command::new("ssh")
+        .args(["-o", "BatchMode=yes", host, &remote_cmd])
+        .output()
+        .context("running ssh (is it installed?)")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("ssh {} failed: {}", host, stderr.trim());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut scan = match stdout.strip_prefix(SS_MARKER) {
+        Some(rest) => RemoteScan {
+            result: parse_ss(rest, opts),
+            limited: true,
+        },
+        None => RemoteScan {
+            result: parse_json(&stdout, opts)
+                .with_context(|| format!("reading kav output from {}", host))?,
+            limited: false,
+        },
+    };
+    scan.result.host = Some(host.to_string());
+    Ok(scan)
+}
+
+/// Entries from a remote `kav list --json`, with or without `--meta`
+fn parse_json(text: &str, opts: &ScanOptions) -> Result<ScanResult> {
+    let doc = json::parse(text).context("not valid JSON")?;
+    let items = doc
+        .as_array()
+        .or_else(|| doc.get("entries").and_then(Json::as_array))
+        .context("expected a JSON array")?;
+    let entries = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| entry_from_json(item).with_context(|| format!("entry {}", i + 1)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(ScanResult {
+        entries,
+        unresolved: 0,
+        underprivileged: false,
+        total_sockets: None,
+        hidden_udp: 0,
+        scanned_at: Local::now(),
+        host: None,
+        tcp: opts.tcp,
+        udp: opts.udp,
+    })
+}
+
+fn entry_from_json(item: &Json) -> Result<PortEntry> {
+    let field = |key: &str| item.get(key).with_context(|| format!("missing `{}`", key));
+    let port = field("port")?
+        .as_u32()
+        .and_then(|p| u16::try_from(p).ok())
+        .context("bad `port`")?;
+    let protocol = match field("protocol")?.as_str() {
+        Some("TCP") => Protocol::Tcp,
+        Some("UDP") => Protocol::Udp,
+        _ => bail!("bad `protocol`"),
+    };
+    let process_name = field("process")?
+        .as_str()
+        .context("bad `process`")?
+        .to_string();
+    let pid = field("pid")?.as_u32().context("bad `pid`")?;
+    let state = item.get("state").and_then(Json::as_str).map(|s| {
+        if s == SocketState::Listen.label() {
+            SocketState::Listen
+        } else {
+            SocketState::Unknown
+        }
+    });
+    // Added in later versions; older remotes report wildcard binds
+    let local_addr = item
+        .get("address")
+        .and_then(Json::as_str)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    let number = |key: &str| item.get(key).and_then(Json::as_f64);
+
+    let (known_service, category) = identify_service(port, &process_name, "");
+    Ok(PortEntry {
+        protocol,
+        local_addr,
+        port,
+        state,
+        remote: None,
+        pid,
+        ppid: item.get("ppid").and_then(Json::as_u32),
+        process_name,
+        process_cmd: String::new(),
+        cpu_percent: number("cpu").unwrap_or(0.0) as f32,
+        memory_mb: number("memory_mb").unwrap_or(0.0),
+        memory_percent: number("memory_percent").map(|p| p as f32),
+        thread_count: None,
+        backlog: None,
+        inode: None,
+        uid: None,
+        uptime: Duration::from_secs(number("uptime_secs").unwrap_or(0.0) as u64),
+        known_service,
+        category,
+        partial: matches!(item.get("partial"), Some(Json::Bool(true))),
+    })
+}
+
+/// Entries from `ss -Htulnp` lines such as
+/// `tcp LISTEN 0 4096 [::]:22 [::]:* users:(("sshd",pid=812,fd=4))`, keeping
+/// the protocols `opts` asks for. Lines without the Netid column (`ss -t` or
+/// `-u` alone) go by their state. Sockets without a visible owner are counted
+/// as unresolved, like a local scan.
+fn parse_ss(text: &str, opts: &ScanOptions) -> ScanResult {
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    let mut unresolved = 0;
+    let mut hidden_udp = 0;
+
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        // From here on, `fields` starts at the State column
+        let (protocol, fields) = match fields.first() {
+            Some(&"tcp") => (Protocol::Tcp, &fields[1..]),
+            Some(&"udp") => (Protocol::Udp, &fields[1..]),
+            Some(&"LISTEN") => (Protocol::Tcp, &fields[..]),
+            Some(&"UNCONN") => (Protocol::Udp, &fields[..]),
+            _ => continue,
+        };
+        let wanted = match protocol {
+            Protocol::Tcp => opts.tcp,
+            Protocol::Udp => opts.udp,
+        };
+        if !wanted || fields.len() < 4 {
+            continue;
+        }
+        let Some((local_addr, port)) = parse_ss_addr(fields[3]) else {
+            continue;
+        };
+        if protocol == Protocol::Udp && !opts.ephemeral_udp && is_ephemeral_udp(port) {
+            hidden_udp += 1;
+            continue;
+        }
+        let (state, backlog) = match protocol {
+            // For listening sockets ss reports the accept queue as Recv-Q / Send-Q
+            Protocol::Tcp => (
+                Some(SocketState::Listen),
+                fields[1]
+                    .parse()
+                    .ok()
+                    .zip(fields[2].parse().ok())
+                    .map(|(queued, backlog)| BacklogInfo { queued, backlog }),
+            ),
+            Protocol::Udp => (None, None),
+        };
+
+        let owners = fields.get(5..).map(|rest| parse_ss_users(&rest.join(" ")));
+        let owners = owners.unwrap_or_default();
+        if owners.is_empty() {
+            unresolved += 1;
+            continue;
+        }
+        for (process_name, pid) in owners {
+            if !seen.insert((protocol, local_addr, port, pid)) {
+                continue;
+            }
+            let (known_service, category) = identify_service(port, &process_name, "");
+            entries.push(PortEntry {
+                protocol,
+                local_addr,
+                port,
+                state,
+                remote: None,
+                pid,
+                ppid: None,
+                process_name: if process_name.is_empty() {
+                    UNKNOWN_PROCESS.to_string()
+                } else {
+                    process_name
+                },
+                process_cmd: String::new(),
+                cpu_percent: 0.0,
+                memory_mb: 0.0,
+                memory_percent: None,
+                thread_count: None,
+                backlog,
+                inode: None,
+                uid: None,
+                uptime: Duration::ZERO,
+                known_service,
+                category,
+                partial: false,
+            });
+        }
+    }
+
+    entries.sort_by_key(|e| e.port);
+    ScanResult {
+        entries,
+        unresolved,
+        underprivileged: unresolved > 0,
+        total_sockets: None,
+        hidden_udp,
+        scanned_at: Local::now(),
+        host: None,
+        tcp: opts.tcp,
+        udp: opts.udp,
+    }
+}
+
+/// `0.0.0.0:22`, `[::]:22`, `*:8080`, `127.0.0.53%lo:53`
+fn parse_ss_addr(s: &str) -> Option<(IpAddr, u16)> {
+    let (host, port) = s.rsplit_once(':')?;
+    let port = port.parse().ok()?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let host = host.split('%').next()?;
+    let ip = if host == "*" {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    } else {
+        host.parse().ok()?
+    };
+    Some((ip, port))
+}
+
+/// `users:(("nginx",pid=2,fd=6),("nginx",pid=3,fd=6))` → name and PID pairs
+fn parse_ss_users(s: &str) -> Vec<(String, u32)> {
+    s.split("((")
+        .skip(1)
+        .flat_map(|chunk| chunk.split("),("))
+        .filter_map(|owner| {
+            let name = owner.split('"').nth(1)?.to_string();
+            let pid = owner
+                .split(',')
+                .find_map(|kv| kv.strip_prefix("pid="))?
+                .parse()
+                .ok()?;
+            Some((name, pid))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SS_UDP: &str = "\
+udp UNCONN 0 0 127.0.0.53%lo:53 0.0.0.0:* users:((\"systemd-resolve\",pid=640,fd=13))
+udp UNCONN 0 0 0.0.0.0:50000 0.0.0.0:* users:((\"firefox\",pid=2100,fd=80))
+";
+
+    #[test]
+    fn ephemeral_udp_is_hidden_and_counted() {
+        let result = parse_ss(SS_UDP, &ScanOptions::default());
+        let ports: Vec<u16> = result.entries.iter().map(|e| e.port).collect();
+        assert_eq!(ports, [53]);
+        assert_eq!(result.hidden_udp, 1);
+
+        // `--all-udp`, and what `kav check`/`kav kill` scan with: a port named
+        // explicitly is kept even in the ephemeral range
+        let all = parse_ss(
+            SS_UDP,
+            &ScanOptions {
+                ephemeral_udp: true,
+                ..ScanOptions::default()
+            },
+        );
+        let ports: Vec<u16> = all.entries.iter().map(|e| e.port).collect();
+        assert_eq!(ports, [53, 50000]);
+        assert_eq!(all.hidden_udp, 0);
+    }
+
+    #[test]
+    fn protocols_are_filtered_with_or_without_netid() {
+        let both = format!(
+            "tcp LISTEN 0 4096 0.0.0.0:22 0.0.0.0:* users:((\"sshd\",pid=812,fd=3))\n{}",
+            SS_UDP
+        );
+        let tcp_only = ScanOptions {
+            udp: false,
+            ..ScanOptions::default()
+        };
+        let ports = |result: ScanResult| -> Vec<(Protocol, u16)> {
+            result
+                .entries
+                .iter()
+                .map(|e| (e.protocol, e.port))
+                .collect()
+        };
+        assert_eq!(
+            ports(parse_ss(&both, &ScanOptions::default())),
+            [(Protocol::Tcp, 22), (Protocol::Udp, 53)]
+        );
+        assert_eq!(ports(parse_ss(&both, &tcp_only)), [(Protocol::Tcp, 22)]);
+
+        // What `ss -Htlnp` prints: no Netid column
+        let ss_tcp = "\
+LISTEN 0 128 0.0.0.0:2024 0.0.0.0:* users:((\"node\",pid=900,fd=21))
+LISTEN 3 511 [::1]:5432 [::]:* users:((\"postgres\",pid=700,fd=6))
+";
+        let result = parse_ss(ss_tcp, &tcp_only);
+        assert_eq!(result.unresolved, 0);
+        assert_eq!(
+            ports(result),
+            [(Protocol::Tcp, 2024), (Protocol::Tcp, 5432)]
+        );
+        let postgres = parse_ss(ss_tcp, &ScanOptions::default()).entries[1].clone();
+        assert_eq!(postgres.pid, 700);
+        assert_eq!(
+            postgres.backlog.map(|b| (b.queued, b.backlog)),
+            Some((3, 511))
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+use crate::models::{PortEntry, Protocol};
+use crate::scanner::{kill_process, scan, ScanOptions};
+
+/// How long to wait for the old process to let go of its port
+pub const PORT_FREE_TIMEOUT: Duration = Duration::from_secs(5);
+const PORT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How a process was started, as far as the OS will tell us
+#[derive(Debug, Clone)]
+pub struct Launch {
+    pub argv: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    /// `KEY=VALUE` pairs; empty when unreadable, in which case ours is inherited
+    pub env: Vec<(String, String)>,
+}
+
+impl Launch {
+    /// Read the command line, working directory and environment of `pid`
+    pub fn capture(pid: u32) -> Result<Self> {
+        if pid == 0 {
+            bail!("socket has no owning process to restart");
+        }
+        let pid_obj = sysinfo::Pid::from_u32(pid);
+        let mut sys = System::new();
+        sys.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid_obj]),
+            true,
+            ProcessRefreshKind::new()
+                .with_cmd(UpdateKind::Always)
+                .with_cwd(UpdateKind::Always)
+                .with_environ(UpdateKind::Always),
+        );
+        let proc = sys
+            .process(pid_obj)
+            .with_context(|| format!("process with PID {} not found", pid))?;
+
+        let argv: Vec<String> = proc
+            .cmd()
+            .iter()
+            .map(|s| s.to_string_lossy().to_string())
+            .collect();
+        if argv.is_empty() {
+            bail!(
+                "can't read the command line of PID {}; try running with sudo",
+                pid
+            );
+        }
+        let env = proc
+            .environ()
+            .iter()
+            .filter_map(|kv| {
+                let kv = kv.to_string_lossy();
+                let (k, v) = kv.split_once('=')?;
+                Some((k.to_string(), v.to_string()))
+            })
+            .collect();
+
+        Ok(Self {
+            argv,
+            cwd: proc.cwd().map(|p| p.to_path_buf()),
+            env,
+        })
+    }
+
+    /// The command as it will be run, for showing before a restart
+    pub fn command_line(&self) -> String {
+        self.argv.join(" ")
+    }
+
+    /// Start the command in the background, detached from our terminal, and
+    /// return its PID. Its output is discarded.
+    fn spawn(&self) -> Result<u32> {
+        let mut cmd = Command::new(&self.argv[0]);
+        cmd.args(&self.argv[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+        if !self.env.is_empty() {
+            cmd.env_clear().envs(self.env.iter().map(|(k, v)| (k, v)));
+        }
+        // Its own process group, so Ctrl-C in our terminal doesn't reach it
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("failed to start `{}`", self.command_line()))?;
+        let pid = child.id();
+        // Reap it when it eventually exits so it doesn't linger as a zombie
+        std::thread::spawn(move || child.wait());
+        Ok(pid)
+    }
+}
+
+/// Stop `entry`'s process (SIGTERM), wait for its port to free up, then run
+/// `launch` again. Returns the new PID.
+pub fn restart(entry: &PortEntry, launch: &Launch) -> Result<u32> {
+    kill_process(entry.pid, false)?;
+    if !wait_for_port_free(entry.port, entry.protocol, PORT_FREE_TIMEOUT)? {
+        bail!(
+            "port {} is still in use after {}s; not restarting",
+            entry.port,
+            PORT_FREE_TIMEOUT.as_secs()
+        );
+    }
+    launch.spawn()
+}
+
+/// Poll until nothing holds `port` for `protocol`, or `timeout` passes
+fn wait_for_port_free(port: u16, protocol: Protocol, timeout: Duration) -> Result<bool> {
+    let opts = ScanOptions {
+        tcp: protocol == Protocol::Tcp,
+        udp: protocol == Protocol::Udp,
+        ..ScanOptions::for_named_port()
+    };
+    let start = Instant::now();
+    loop {
+        if !scan(&opts)?.entries.iter().any(|e| e.port == port) {
+            return Ok(true);
+        }
+        if start.elapsed() >= timeout {
+            return Ok(false);
+        }
+        std::thread::sleep(PORT_POLL_INTERVAL);
+    }
+}
@@ -1,90 +1,466 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::time::Duration;
 
 use anyhow::Result;
-use netstat2::{get_sockets_info, AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, TcpState};
-use sysinfo::System;
+use chrono::{DateTime, Local};
+use netstat2::{
+    get_sockets_info, AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, SocketInfo, TcpState,
+};
+use sysinfo::{ProcessRefreshKind, System, UpdateKind, Users};
 
-use crate::models::{PortEntry, Protocol};
+use crate::models::{BacklogInfo, PortEntry, ProcessNode, Protocol, SocketState};
 use crate::util::identify_service;
 
+/// Label used for entries whose owning process could not be looked up
+pub const UNKNOWN_PROCESS: &str = "<unknown>";
+
+/// Label for connections that outlived their process (e.g. TIME_WAIT)
+pub const NO_OWNER: &str = "(none)";
+
+/// Extra attempts at reading the socket tables after a failure, which is
+/// usually transient (a permissions race, or the kernel being busy)
+const SOCKET_READ_RETRIES: u32 = 2;
+
+/// Wait before the first retry; doubled for each one after
+const SOCKET_READ_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Start of the ephemeral port range. Linux hands out 32768–60999 and the
+/// IANA range (macOS, Windows) is 49152–65535, so this covers both.
+pub const EPHEMERAL_PORT_START: u16 = 32768;
+
+/// Entries from a scan, plus how much of it couldn't be resolved
+pub struct ScanResult {
+    pub entries: Vec<PortEntry>,
+    /// Listening sockets with no visible owner PID, plus owners that vanished mid-scan.
+    /// Usually a sign of missing privileges.
+    pub unresolved: usize,
+    /// The socket list suggests we can't see other users' processes
+    pub underprivileged: bool,
+    /// Every open TCP and UDP socket in any state, when asked for
+    pub total_sockets: Option<usize>,
+    /// UDP sockets left out as ephemeral client sockets (see `is_ephemeral_udp`)
+    pub hidden_udp: usize,
+    /// When the sockets were read
+    pub scanned_at: DateTime<Local>,
+    /// Remote machine scanned with `--host`; `None` for this one
+    pub host: Option<String>,
+    /// Protocols the scan was asked for
+    pub tcp: bool,
+    pub udp: bool,
+}
+
+impl ScanResult {
+    /// Some sockets couldn't be tied to a process, so `entries` is likely
+    /// missing owners or whole entries
+    pub fn truncated(&self) -> bool {
+        self.unresolved > 0 || self.underprivileged
+    }
+}
+
+/// What a scan should include
+#[derive(Debug, Clone, Copy)]
+pub struct ScanOptions {
+    pub tcp: bool,
+    pub udp: bool,
+    /// Also include connected (non-listening) TCP sockets
+    pub established: bool,
+    /// Keep sockets owned by kaval itself (excluded by default as noise)
+    pub include_self: bool,
+    /// Keep UDP sockets that look like ephemeral client sockets
+    pub ephemeral_udp: bool,
+    /// Count each owner's threads (Linux only)
+    pub include_threads: bool,
+    /// Also count every open socket system-wide, not just the listening ones
+    pub total_sockets: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            tcp: true,
+            udp: true,
+            established: false,
+            include_self: false,
+            ephemeral_udp: false,
+            include_threads: false,
+            total_sockets: false,
+        }
+    }
+}
+
+impl ScanOptions {
+    /// For commands handed a port (`kav check`, `kav kill`, `kav restart`): an
+    /// explicitly named port is wanted even if it looks like a UDP client socket
+    pub fn for_named_port() -> Self {
+        Self {
+            ephemeral_udp: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Accept queues of listening TCP sockets by local address. On Linux,
+/// `/proc/net/tcp{,6}` reports them in the tx_queue (backlog) and rx_queue
+/// (queued) columns of LISTEN rows.
+#[cfg(target_os = "linux")]
+fn listen_queues() -> HashMap<(IpAddr, u16), BacklogInfo> {
+    let mut queues = HashMap::new();
+    for file in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        let Ok(text) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        for line in text.lines().skip(1) {
+            if let Some((addr, info)) = parse_proc_net_listen(line) {
+                queues.insert(addr, info);
+            }
+        }
+    }
+    queues
+}
+
+#[cfg(not(target_os = "linux"))]
+fn listen_queues() -> HashMap<(IpAddr, u16), BacklogInfo> {
+    HashMap::new()
+}
+
+/// One `/proc/net/tcp{,6}` row, if it is a LISTEN socket (state `0A`)
+#[cfg(target_os = "linux")]
+fn parse_proc_net_listen(line: &str) -> Option<((IpAddr, u16), BacklogInfo)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.get(3)? != &"0A" {
+        return None;
+    }
+    let (addr, port) = fields.get(1)?.split_once(':')?;
+    let (tx, rx) = fields.get(4)?.split_once(':')?;
+
+    // Addresses are printed as native-endian 32-bit words
+    let mut bytes = Vec::with_capacity(16);
+    for chunk in addr.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let ip = match bytes.len() {
+        4 => IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?),
+        16 => IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?),
+        _ => return None,
+    };
+
+    Some((
+        (ip, u16::from_str_radix(port, 16).ok()?),
+        BacklogInfo {
+            queued: u32::from_str_radix(rx, 16).ok()?,
+            backlog: u32::from_str_radix(tx, 16).ok()?,
+        },
+    ))
+}
+
 /// Scan the system for all listening ports and map them to process info.
 pub fn scan_ports(show_tcp: bool, show_udp: bool) -> Result<Vec<PortEntry>> {
+    let opts = ScanOptions {
+        tcp: show_tcp,
+        udp: show_udp,
+        ..ScanOptions::default()
+    };
+    Ok(scan(&opts)?.entries)
+}
+
+/// Where a scan reads sockets and process details from: the live system,
+/// or a fixed set of both in tests
+pub trait ScanSource {
+    /// Every socket of the given families and protocols, in any state
+    fn sockets(&self, af: AddressFamilyFlags, proto: ProtocolFlags) -> Result<Vec<SocketInfo>>;
+
+    /// Accept queues of listening TCP sockets by local address
+    fn listen_queues(&self) -> HashMap<(IpAddr, u16), BacklogInfo>;
+
+    /// Load the process table; called once, after the sockets are read
+    fn refresh_processes(&mut self);
+
+    /// Details of `pid`, or `None` when it isn't (or is no longer) running
+    fn process(&self, pid: u32) -> Option<ProcessInfo>;
+
+    /// Total system RAM in bytes, zero when unknown
+    fn total_memory(&self) -> u64;
+
+    /// Overall CPU (since the previous scan) and memory in use, where known
+    fn usage(&self) -> Option<SystemUsage> {
+        None
+    }
+}
+
+/// What a [`ScanSource`] knows about one process
+#[derive(Debug, Clone, Default)]
+pub struct ProcessInfo {
+    pub name: String,
+    pub cmd: String,
+    pub ppid: Option<u32>,
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub run_time: Duration,
+    /// Including the main thread; `None` where unsupported
+    pub threads: Option<usize>,
+    /// Owning user's ID (Unix)
+    pub uid: Option<u32>,
+}
+
+/// The machine kaval runs on: netstat2 for sockets, sysinfo for processes.
+/// Kept between scans (as the TUI does), CPU figures cover the time since
+/// the previous scan.
+#[derive(Default)]
+pub struct LiveSource {
+    sys: System,
+}
+
+/// Whole-system load at the last scan
+#[derive(Debug, Clone, Copy)]
+pub struct SystemUsage {
+    pub cpu_percent: f32,
+    pub used_memory: u64,
+    pub total_memory: u64,
+}
+
+impl ScanSource for LiveSource {
+    fn sockets(&self, af: AddressFamilyFlags, proto: ProtocolFlags) -> Result<Vec<SocketInfo>> {
+        read_sockets(af, proto)
+    }
+
+    fn listen_queues(&self) -> HashMap<(IpAddr, u16), BacklogInfo> {
+        listen_queues()
+    }
+
+    fn refresh_processes(&mut self) {
+        // The default refresh skips command lines, which the detail pane and --wide show
+        self.sys.refresh_processes_specifics(
+            sysinfo::ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::new()
+                .with_cpu()
+                .with_memory()
+                .with_cmd(sysinfo::UpdateKind::OnlyIfNotSet)
+                .with_user(sysinfo::UpdateKind::OnlyIfNotSet),
+        );
+        self.sys.refresh_memory();
+        self.sys.refresh_cpu_usage();
+    }
+
+    fn process(&self, pid: u32) -> Option<ProcessInfo> {
+        let proc = self.sys.process(sysinfo::Pid::from_u32(pid))?;
+        Some(ProcessInfo {
+            name: proc.name().to_string_lossy().to_string(),
+            cmd: proc
+                .cmd()
+                .iter()
+                .map(|s| s.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            ppid: proc.parent().map(|p| p.as_u32()),
+            cpu_percent: proc.cpu_usage(),
+            memory_bytes: proc.memory(),
+            run_time: Duration::from_secs(proc.run_time()),
+            // sysinfo's task list leaves out the main thread
+            threads: proc.tasks().map(|tasks| tasks.len() + 1),
+            uid: uid_number(proc.user_id()),
+        })
+    }
+
+    fn total_memory(&self) -> u64 {
+        self.sys.total_memory()
+    }
+
+    fn usage(&self) -> Option<SystemUsage> {
+        Some(SystemUsage {
+            cpu_percent: self.sys.global_cpu_usage(),
+            used_memory: self.sys.used_memory(),
+            total_memory: self.sys.total_memory(),
+        })
+    }
+}
+
+/// Like [`scan_ports`], but configurable and also reports sockets that
+/// couldn't be fully resolved.
+pub fn scan(opts: &ScanOptions) -> Result<ScanResult> {
+    scan_with(&mut LiveSource::default(), opts)
+}
+
+/// [`scan`] over any [`ScanSource`]
+pub fn scan_with(
+    source: &mut (impl ScanSource + ?Sized),
+    opts: &ScanOptions,
+) -> Result<ScanResult> {
     let mut proto_flags = ProtocolFlags::empty();
-    if show_tcp {
+    if opts.tcp {
         proto_flags |= ProtocolFlags::TCP;
     }
-    if show_udp {
+    if opts.udp {
         proto_flags |= ProtocolFlags::UDP;
     }
 
     let af_flags = AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6;
 
-    let sockets = get_sockets_info(af_flags, proto_flags)?;
+    let sockets = source.sockets(af_flags, proto_flags)?;
+    let underprivileged = likely_underprivileged(&sockets);
+    // The listening scan already holds every state, so only a partial one needs a second call
+    let total_sockets = if !opts.total_sockets {
+        None
+    } else if opts.tcp && opts.udp {
+        Some(sockets.len())
+    } else {
+        Some(
+            source
+                .sockets(af_flags, ProtocolFlags::TCP | ProtocolFlags::UDP)?
+                .len(),
+        )
+    };
+    let queues = if opts.tcp {
+        source.listen_queues()
+    } else {
+        HashMap::new()
+    };
 
-    // Build a sysinfo System for process lookups
-    let mut sys = System::new();
-    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+    source.refresh_processes();
+    // Zero when the platform doesn't report it; percentages are left out then
+    let total_memory = source.total_memory();
 
     // Collect PIDs we care about, then look them up
     let mut entries: Vec<PortEntry> = Vec::new();
-    let mut seen: HashMap<(u16, u32), bool> = HashMap::new();
+    let mut seen: HashSet<(Protocol, IpAddr, u16, u32, Option<SocketAddr>)> = HashSet::new();
+    let mut unresolved = 0;
+    let mut hidden_udp = 0;
+    // Only our exact PID is skipped; children and helpers we spawn stay visible
+    let own_pid = std::process::id();
 
     for socket in &sockets {
-        let (protocol, local_addr, port, is_listening) = match &socket.protocol_socket_info {
+        let (protocol, local_addr, port, state, remote) = match &socket.protocol_socket_info {
             ProtocolSocketInfo::Tcp(tcp) => {
-                if tcp.state != TcpState::Listen {
+                let state = socket_state(tcp.state);
+                let remote = if state == SocketState::Listen {
+                    None
+                } else if opts.established {
+                    Some(SocketAddr::new(tcp.remote_addr, tcp.remote_port))
+                } else {
+                    continue;
+                };
+                (
+                    Protocol::Tcp,
+                    tcp.local_addr,
+                    tcp.local_port,
+                    Some(state),
+                    remote,
+                )
+            }
+            ProtocolSocketInfo::Udp(udp) => {
+                if !opts.ephemeral_udp && is_ephemeral_udp(udp.local_port) {
+                    hidden_udp += 1;
                     continue;
                 }
-                (Protocol::Tcp, tcp.local_addr, tcp.local_port, true)
+                (Protocol::Udp, udp.local_addr, udp.local_port, None, None)
             }
-            ProtocolSocketInfo::Udp(udp) => (Protocol::Udp, udp.local_addr, udp.local_port, true),
         };
 
-        if !is_listening {
+        // Sockets owned by other users may come back without any PIDs, while
+        // closing connections (TIME_WAIT) genuinely have no owner; keep those as PID 0
+        let pids: &[u32] = if !socket.associated_pids.is_empty() {
+            &socket.associated_pids
+        } else if remote.is_some() {
+            &[0]
+        } else {
+            unresolved += 1;
             continue;
-        }
+        };
 
         // Get associated PIDs
-        for &pid in &socket.associated_pids {
-            // Deduplicate by (port, pid)
-            if seen.contains_key(&(port, pid)) {
+        for &pid in pids {
+            if pid == own_pid && !opts.include_self {
+                continue;
+            }
+
+            // One entry per process and bind: a socket shared by several fds or
+            // threads is listed once, while each address and each connection's
+            // peer stays distinct. Callers wanting one row per process dedup by PID.
+            if !seen.insert((protocol, local_addr, port, pid, remote)) {
                 continue;
             }
-            seen.insert((port, pid), true);
-
-            let pid_obj = sysinfo::Pid::from_u32(pid);
-            let (process_name, process_cmd, cpu_percent, memory_mb, uptime) =
-                if let Some(proc) = sys.process(pid_obj) {
-                    let name = proc.name().to_string_lossy().to_string();
-                    let cmd = proc
-                        .cmd()
-                        .iter()
-                        .map(|s| s.to_string_lossy().to_string())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let cpu = proc.cpu_usage();
-                    let mem = proc.memory() as f64 / (1024.0 * 1024.0);
-                    let up = Duration::from_secs(proc.run_time());
-                    (name, cmd, cpu, mem, up)
+
+            let process = source.process(pid);
+            let (process_name, process_cmd, ppid, cpu_percent, memory_mb, uptime, partial) =
+                if let Some(proc) = &process {
+                    let mem = proc.memory_bytes as f64 / (1024.0 * 1024.0);
+                    (
+                        proc.name.clone(),
+                        proc.cmd.clone(),
+                        proc.ppid,
+                        proc.cpu_percent,
+                        mem,
+                        proc.run_time,
+                        false,
+                    )
+                } else if pid == 0 {
+                    (
+                        String::from(NO_OWNER),
+                        String::new(),
+                        None,
+                        0.0,
+                        0.0,
+                        Duration::ZERO,
+                        false,
+                    )
                 } else {
-                    (String::from("?"), String::new(), 0.0, 0.0, Duration::ZERO)
+                    // Process exited between socket enumeration and lookup
+                    unresolved += 1;
+                    (
+                        String::from(UNKNOWN_PROCESS),
+                        String::new(),
+                        None,
+                        0.0,
+                        0.0,
+                        Duration::ZERO,
+                        true,
+                    )
                 };
 
-            let (known_service, category) = identify_service(port, &process_name);
+            let uid = process.as_ref().and_then(|p| p.uid);
+            let thread_count = if opts.include_threads {
+                process.and_then(|p| p.threads)
+            } else {
+                None
+            };
+
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            let inode = Some(socket.inode);
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            let inode = None;
+
+            let backlog = (state == Some(SocketState::Listen))
+                .then(|| queues.get(&(local_addr, port)).copied())
+                .flatten();
+
+            let (known_service, category) = identify_service(port, &process_name, &process_cmd);
+            let memory_percent = (total_memory > 0 && !partial && pid != 0)
+                .then(|| (memory_mb * 1024.0 * 1024.0 / total_memory as f64 * 100.0) as f32);
 
             entries.push(PortEntry {
                 protocol,
                 local_addr,
                 port,
+                state,
+                remote,
                 pid,
+                ppid,
                 process_name,
                 process_cmd,
                 cpu_percent,
                 memory_mb,
+                memory_percent,
+                thread_count,
+                backlog,
+                inode,
+                uid,
                 uptime,
                 known_service,
                 category,
+                partial,
             });
         }
     }
@@ -92,11 +468,150 @@ pub fn scan_ports(show_tcp: bool, show_udp: bool) -> Result<Vec<PortEntry>> {
     // Default sort by port number
     entries.sort_by_key(|e| e.port);
 
-    Ok(entries)
+    Ok(ScanResult {
+        entries,
+        unresolved,
+        underprivileged,
+        total_sockets,
+        hidden_udp,
+        scanned_at: Local::now(),
+        host: None,
+        tcp: opts.tcp,
+        udp: opts.udp,
+    })
+}
+
+/// `get_sockets_info`, retried with a short backoff before giving up
+fn read_sockets(af: AddressFamilyFlags, proto: ProtocolFlags) -> Result<Vec<SocketInfo>> {
+    let mut backoff = SOCKET_READ_BACKOFF;
+    for _ in 0..SOCKET_READ_RETRIES {
+        if let Ok(sockets) = get_sockets_info(af, proto) {
+            return Ok(sockets);
+        }
+        std::thread::sleep(backoff);
+        backoff *= 2;
+    }
+    Ok(get_sockets_info(af, proto)?)
+}
+
+/// Without root, listening sockets owned by other users come back with no
+/// PIDs at all. Any such socket means results are likely incomplete.
+pub fn likely_underprivileged(sockets: &[SocketInfo]) -> bool {
+    sockets.iter().any(|socket| {
+        socket.associated_pids.is_empty()
+            && match &socket.protocol_socket_info {
+                ProtocolSocketInfo::Tcp(tcp) => tcp.state == TcpState::Listen,
+                ProtocolSocketInfo::Udp(_) => true,
+            }
+    })
+}
+
+/// UDP has no LISTEN state, so every bound socket shows up, including the
+/// throwaway sockets clients get for DNS lookups, QUIC and the like. Those
+/// are auto-assigned from the ephemeral range, while servers bind a stable,
+/// lower port. So: a UDP socket counts as a client socket when its port is
+/// in the ephemeral range and isn't a known service port. The local address
+/// doesn't help here; clients usually bind the wildcard address too, and
+/// the socket tables don't report a peer for UDP to tell them apart by.
+/// Hidden unless `--all-udp` is given; the summary says how many.
+pub fn is_ephemeral_udp(port: u16) -> bool {
+    port >= EPHEMERAL_PORT_START && identify_service(port, "", "").0.is_none()
+}
+
+fn socket_state(state: TcpState) -> SocketState {
+    match state {
+        TcpState::Listen => SocketState::Listen,
+        TcpState::Established => SocketState::Established,
+        TcpState::SynSent => SocketState::SynSent,
+        TcpState::SynReceived => SocketState::SynReceived,
+        TcpState::FinWait1 => SocketState::FinWait1,
+        TcpState::FinWait2 => SocketState::FinWait2,
+        TcpState::TimeWait => SocketState::TimeWait,
+        TcpState::CloseWait => SocketState::CloseWait,
+        TcpState::LastAck => SocketState::LastAck,
+        TcpState::Closing => SocketState::Closing,
+        TcpState::Closed => SocketState::Closed,
+        _ => SocketState::Unknown,
+    }
+}
+
+/// Snapshot the parent and name of every running process (for the tree view).
+pub fn process_parents() -> HashMap<u32, ProcessNode> {
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::new(),
+    );
+
+    sys.processes()
+        .iter()
+        // Linux lists threads as processes too; they'd show up as children
+        .filter(|(_, proc)| proc.thread_kind().is_none())
+        .map(|(pid, proc)| {
+            (
+                pid.as_u32(),
+                ProcessNode {
+                    ppid: proc.parent().map(|p| p.as_u32()),
+                    name: proc.name().to_string_lossy().to_string(),
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn uid_number(uid: Option<&sysinfo::Uid>) -> Option<u32> {
+    uid.map(|uid| **uid)
+}
+
+// Windows owners are SIDs, not numbers
+#[cfg(not(unix))]
+fn uid_number(_uid: Option<&sysinfo::Uid>) -> Option<u32> {
+    None
+}
+
+/// Effective user ID kaval runs as (Unix); `None` where ownership can't be
+/// compared, so `--killable` keeps every entry
+pub fn current_uid() -> Option<u32> {
+    let mut sys = System::new();
+    let pid = sysinfo::get_current_pid().ok()?;
+    sys.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::new().with_user(UpdateKind::Always),
+    );
+    uid_number(sys.process(pid)?.effective_user_id())
+}
+
+/// Owning user name and working directory of `pid`, where visible
+pub fn process_user_and_cwd(pid: u32) -> (Option<String>, Option<PathBuf>) {
+    let pid_obj = sysinfo::Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&[pid_obj]),
+        true,
+        ProcessRefreshKind::new()
+            .with_user(UpdateKind::Always)
+            .with_cwd(UpdateKind::Always),
+    );
+    let Some(proc) = sys.process(pid_obj) else {
+        return (None, None);
+    };
+    let users = Users::new_with_refreshed_list();
+    let user = proc
+        .user_id()
+        .and_then(|uid| users.get_user_by_id(uid))
+        .map(|u| u.name().to_string());
+    (user, proc.cwd().map(|p| p.to_path_buf()))
 }
 
 /// Kill a process by PID (cross-platform: macOS, Linux, Windows)
 pub fn kill_process(pid: u32, force: bool) -> Result<()> {
+    if pid == 0 {
+        anyhow::bail!("Socket has no owning process to kill");
+    }
+
     let mut sys = System::new();
     sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
 
@@ -117,3 +632,380 @@ pub fn kill_process(pid: u32, force: bool) -> Result<()> {
         anyhow::bail!("Failed to kill PID {}. Try running with sudo.", pid,)
     }
 }
+
+/// A [`ScanSource`] for tests, shared by the scanner and TUI tests
+#[cfg(test)]
+pub mod fake {
+    use std::net::Ipv4Addr;
+
+    use netstat2::{TcpSocketInfo, UdpSocketInfo};
+
+    use super::*;
+
+    /// Fixed sockets and processes instead of the live system
+    #[derive(Default)]
+    pub struct FakeSource {
+        pub sockets: Vec<SocketInfo>,
+        pub processes: HashMap<u32, ProcessInfo>,
+        pub total_memory: u64,
+    }
+
+    impl FakeSource {
+        pub fn tcp(mut self, port: u16, state: TcpState, pids: &[u32]) -> Self {
+            self.sockets.push(socket(
+                ProtocolSocketInfo::Tcp(TcpSocketInfo {
+                    local_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    local_port: port,
+                    remote_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+                    remote_port: 51000,
+                    state,
+                }),
+                pids,
+            ));
+            self
+        }
+
+        pub fn listen_at(mut self, addr: IpAddr, port: u16, pids: &[u32]) -> Self {
+            self.sockets.push(socket(
+                ProtocolSocketInfo::Tcp(TcpSocketInfo {
+                    local_addr: addr,
+                    local_port: port,
+                    remote_addr: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                    remote_port: 0,
+                    state: TcpState::Listen,
+                }),
+                pids,
+            ));
+            self
+        }
+
+        pub fn udp(mut self, port: u16, pids: &[u32]) -> Self {
+            self.sockets.push(socket(
+                ProtocolSocketInfo::Udp(UdpSocketInfo {
+                    local_addr: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                    local_port: port,
+                }),
+                pids,
+            ));
+            self
+        }
+
+        pub fn process(mut self, pid: u32, name: &str, cmd: &str) -> Self {
+            self.processes.insert(
+                pid,
+                ProcessInfo {
+                    name: name.to_string(),
+                    cmd: cmd.to_string(),
+                    memory_bytes: 64 * 1024 * 1024,
+                    threads: Some(4),
+                    ..ProcessInfo::default()
+                },
+            );
+            self
+        }
+    }
+
+    fn socket(info: ProtocolSocketInfo, pids: &[u32]) -> SocketInfo {
+        SocketInfo {
+            protocol_socket_info: info,
+            associated_pids: pids.to_vec(),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            inode: 0,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            uid: 0,
+        }
+    }
+
+    impl ScanSource for FakeSource {
+        fn sockets(&self, _: AddressFamilyFlags, proto: ProtocolFlags) -> Result<Vec<SocketInfo>> {
+            Ok(self
+                .sockets
+                .iter()
+                .filter(|s| match s.protocol_socket_info {
+                    ProtocolSocketInfo::Tcp(_) => proto.contains(ProtocolFlags::TCP),
+                    ProtocolSocketInfo::Udp(_) => proto.contains(ProtocolFlags::UDP),
+                })
+                .cloned()
+                .collect())
+        }
+
+        fn listen_queues(&self) -> HashMap<(IpAddr, u16), BacklogInfo> {
+            HashMap::new()
+        }
+
+        fn refresh_processes(&mut self) {}
+
+        fn process(&self, pid: u32) -> Option<ProcessInfo> {
+            self.processes.get(&pid).cloned()
+        }
+
+        fn total_memory(&self) -> u64 {
+            self.total_memory
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::fake::FakeSource;
+    use super::*;
+    use crate::cli::KillTarget;
+    use crate::filter::Filter;
+    use crate::models::{port_conflicts, ServiceCategory};
+
+    fn scan_fake(mut source: FakeSource, opts: ScanOptions) -> ScanResult {
+        scan_with(&mut source, &opts).unwrap()
+    }
+
+    #[test]
+    fn maps_listening_socket_to_process_and_service() {
+        let source = FakeSource {
+            total_memory: 1024 * 1024 * 1024,
+            ..FakeSource::default()
+        }
+        .tcp(5432, TcpState::Listen, &[100])
+        .process(100, "postgres", "postgres -D /var/lib/postgres");
+        let result = scan_fake(source, ScanOptions::default());
+
+        assert_eq!(result.entries.len(), 1);
+        let entry = &result.entries[0];
+        assert_eq!(entry.port, 5432);
+        assert_eq!(entry.pid, 100);
+        assert_eq!(entry.process_name, "postgres");
+        assert_eq!(entry.state, Some(SocketState::Listen));
+        assert_eq!(entry.category, ServiceCategory::Database);
+        assert_eq!(entry.memory_mb, 64.0);
+        assert_eq!(entry.memory_percent, Some(6.25));
+        assert_eq!(entry.thread_count, None);
+        assert!(!entry.partial);
+        assert!(!result.truncated());
+    }
+
+    #[test]
+    fn duplicate_pid_on_one_port_is_one_entry() {
+        // The same process on the same bind twice, as with a socket shared by
+        // several fds
+        let source = FakeSource::default()
+            .tcp(3000, TcpState::Listen, &[100])
+            .tcp(3000, TcpState::Listen, &[100, 100])
+            .tcp(3001, TcpState::Listen, &[100])
+            .process(100, "node", "node server.js");
+        let result = scan_fake(source, ScanOptions::default());
+
+        let ports: Vec<_> = result.entries.iter().map(|e| (e.port, e.pid)).collect();
+        assert_eq!(ports, [(3000, 100), (3001, 100)]);
+    }
+
+    #[test]
+    fn each_bind_of_a_process_is_its_own_entry() {
+        let source = FakeSource::default()
+            .listen_at(IpAddr::V4(Ipv4Addr::LOCALHOST), 3000, &[100])
+            .listen_at(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 3000, &[100])
+            .udp(3000, &[100])
+            .process(100, "node", "node server.js");
+        let result = scan_fake(source, ScanOptions::default());
+
+        let binds: Vec<_> = result
+            .entries
+            .iter()
+            .map(|e| (e.protocol, e.local_addr.to_string(), e.pid))
+            .collect();
+        assert_eq!(
+            binds,
+            [
+                (Protocol::Tcp, "127.0.0.1".to_string(), 100),
+                (Protocol::Tcp, "::".to_string(), 100),
+                (Protocol::Udp, "0.0.0.0".to_string(), 100),
+            ]
+        );
+
+        // So `kav kill [::]:3000` finds the one bind it names
+        let target = KillTarget::parse("[::]:3000").unwrap();
+        let matches: Vec<_> = result
+            .entries
+            .iter()
+            .filter(|e| target.matches(e))
+            .collect();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].local_addr, IpAddr::V6(Ipv6Addr::UNSPECIFIED));
+    }
+
+    #[test]
+    fn dual_stack_binds_are_not_conflicts() {
+        let v4 = |a, b, c, d| IpAddr::V4(Ipv4Addr::new(a, b, c, d));
+        let any6 = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+        let conflicts = |source: FakeSource| -> Vec<Vec<u32>> {
+            let result = scan_fake(source, ScanOptions::default());
+            port_conflicts(&result.entries)
+                .iter()
+                .map(|g| g.iter().map(|e| e.pid).collect())
+                .collect()
+        };
+
+        // One process on both families
+        let nginx = FakeSource::default()
+            .listen_at(v4(0, 0, 0, 0), 8080, &[10])
+            .listen_at(any6, 8080, &[10])
+            .process(10, "nginx", "nginx");
+        assert!(conflicts(nginx).is_empty());
+        // A published container port: one docker-proxy per family
+        let published = || {
+            FakeSource::default()
+                .listen_at(v4(0, 0, 0, 0), 8080, &[20])
+                .listen_at(any6, 8080, &[21])
+                .process(20, "docker-proxy", "docker-proxy -proto tcp")
+                .process(21, "docker-proxy", "docker-proxy -proto tcp")
+        };
+        assert!(conflicts(published()).is_empty());
+        // Two unrelated servers split across the families do conflict
+        let split = FakeSource::default()
+            .listen_at(v4(127, 0, 0, 1), 8080, &[30])
+            .listen_at(any6, 8080, &[31])
+            .process(30, "node", "node server.js")
+            .process(31, "python3", "python3 -m http.server 8080");
+        assert_eq!(conflicts(split), [[30, 31]]);
+        // And so does a local server behind a published port
+        let shadowed = published()
+            .listen_at(v4(127, 0, 0, 1), 8080, &[40])
+            .process(40, "node", "node server.js");
+        assert_eq!(conflicts(shadowed), [[20, 21, 40]]);
+    }
+
+    #[test]
+    fn non_listening_tcp_needs_established() {
+        let source = || {
+            FakeSource::default()
+                .tcp(8080, TcpState::Listen, &[100])
+                .tcp(8080, TcpState::Established, &[100])
+                .tcp(443, TcpState::TimeWait, &[])
+                .process(100, "nginx", "nginx")
+        };
+
+        let listening = scan_fake(source(), ScanOptions::default());
+        assert_eq!(listening.entries.len(), 1);
+        assert_eq!(listening.entries[0].remote, None);
+
+        let all = scan_fake(
+            source(),
+            ScanOptions {
+                established: true,
+                ..ScanOptions::default()
+            },
+        );
+        assert_eq!(all.entries.len(), 3);
+        // TIME_WAIT outlived its process and is kept as PID 0, not counted unresolved
+        let orphan = all.entries.iter().find(|e| e.port == 443).unwrap();
+        assert_eq!((orphan.pid, orphan.process_name.as_str()), (0, NO_OWNER));
+        assert_eq!(all.unresolved, 0);
+    }
+
+    #[test]
+    fn killable_follows_process_owner() {
+        let mut source = FakeSource::default()
+            .tcp(22, TcpState::Listen, &[1])
+            .tcp(3000, TcpState::Listen, &[100])
+            .tcp(8080, TcpState::Listen, &[200])
+            .process(1, "sshd", "sshd")
+            .process(100, "node", "node server.js")
+            .process(200, "java", "java -jar app.jar");
+        source.processes.get_mut(&1).unwrap().uid = Some(0);
+        source.processes.get_mut(&100).unwrap().uid = Some(1000);
+        // PID 200's owner couldn't be read
+        let result = scan_fake(source, ScanOptions::default());
+
+        let killable = |me| {
+            let filter = Filter::new().killable(Some(me));
+            let ports: Vec<_> = result
+                .entries
+                .iter()
+                .filter(|e| filter.matches(e))
+                .map(|e| e.port)
+                .collect();
+            ports
+        };
+        assert_eq!(killable(Some(1000)), [3000, 8080]);
+        assert_eq!(killable(Some(0)), [22, 3000, 8080]);
+        assert_eq!(killable(None), [22, 3000, 8080]);
+    }
+
+    #[test]
+    fn missing_process_is_partial_and_unresolved() {
+        let source = FakeSource::default()
+            .tcp(9000, TcpState::Listen, &[200])
+            .tcp(9001, TcpState::Listen, &[]);
+        let result = scan_fake(source, ScanOptions::default());
+
+        // PID 200 exited before lookup; port 9001 has no visible owner at all
+        assert_eq!(result.entries.len(), 1);
+        let entry = &result.entries[0];
+        assert_eq!(entry.process_name, UNKNOWN_PROCESS);
+        assert!(entry.partial);
+        assert_eq!(entry.memory_percent, None);
+        assert_eq!(result.unresolved, 2);
+        assert!(result.underprivileged);
+    }
+
+    #[test]
+    fn ephemeral_udp_is_hidden_and_counted() {
+        let source = || {
+            FakeSource::default()
+                .udp(53, &[100])
+                .udp(50000, &[100])
+                .process(100, "dnsmasq", "dnsmasq")
+        };
+
+        let result = scan_fake(source(), ScanOptions::default());
+        let ports: Vec<_> = result.entries.iter().map(|e| e.port).collect();
+        assert_eq!(ports, [53]);
+        assert_eq!(result.hidden_udp, 1);
+
+        let all = scan_fake(
+            source(),
+            ScanOptions {
+                ephemeral_udp: true,
+                ..ScanOptions::default()
+            },
+        );
+        assert_eq!(all.entries.len(), 2);
+        assert_eq!(all.hidden_udp, 0);
+
+        // `kav check 50000` and friends see it without --all-udp
+        let named = scan_fake(source(), ScanOptions::for_named_port());
+        assert!(named.entries.iter().any(|e| e.port == 50000));
+    }
+
+    #[test]
+    fn protocol_and_thread_options() {
+        let source = || {
+            FakeSource::default()
+                .tcp(6379, TcpState::Listen, &[100])
+                .udp(6379, &[100])
+                .process(100, "redis-server", "redis-server *:6379")
+        };
+
+        let tcp_only = scan_fake(
+            source(),
+            ScanOptions {
+                udp: false,
+                include_threads: true,
+                total_sockets: true,
+                ..ScanOptions::default()
+            },
+        );
+        assert_eq!(tcp_only.entries.len(), 1);
+        assert_eq!(tcp_only.entries[0].protocol, Protocol::Tcp);
+        assert_eq!(tcp_only.entries[0].thread_count, Some(4));
+        assert_eq!(tcp_only.total_sockets, Some(2));
+    }
+
+    #[test]
+    fn ephemeral_udp_is_an_unknown_port_in_the_ephemeral_range() {
+        assert!(!is_ephemeral_udp(53));
+        assert!(!is_ephemeral_udp(5353));
+        assert!(!is_ephemeral_udp(EPHEMERAL_PORT_START - 1));
+        assert!(is_ephemeral_udp(50000));
+        assert!(is_ephemeral_udp(u16::MAX));
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::{bail, Context, Result};
+
+use crate::models::{PortEntry, Protocol};
+
+/// One line of a `kav check --from` file: `port[/proto] [expected service]`,
+/// e.g. `5432/tcp PostgreSQL` or just `3000`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expectation {
+    pub port: u16,
+    /// `None` accepts either protocol
+    pub protocol: Option<Protocol>,
+    /// `None` accepts any listener
+    pub service: Option<String>,
+}
+
+impl Expectation {
+    /// `5432/tcp PostgreSQL`, as written in the file
+    pub fn label(&self) -> String {
+        let mut label = self.port.to_string();
+        if let Some(protocol) = self.protocol {
+            label.push_str(&format!("/{}", protocol.to_string().to_lowercase()));
+        }
+        if let Some(service) = &self.service {
+            label.push_str(&format!(" {}", service));
+        }
+        label
+    }
+
+    /// The expected service names `e`: its known service (any case), or a
+    /// substring of its process name
+    fn is_served_by(&self, e: &PortEntry) -> bool {
+        let Some(expected) = &self.service else {
+            return true;
+        };
+        e.known_service
+            .is_some_and(|svc| svc.eq_ignore_ascii_case(expected))
+            || e.process_name
+                .to_lowercase()
+                .contains(&expected.to_lowercase())
+    }
+}
+
+/// Parse a spec file: one expectation per line; blank lines and `#` comments
+/// are skipped. Errors name the offending line.
+pub fn parse(text: &str) -> Result<Vec<Expectation>> {
+    let mut expectations = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (target, service) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let (port, protocol) = target.split_once('/').unwrap_or((target, ""));
+        let port = port
+            .parse()
+            .ok()
+            .filter(|&p: &u16| p > 0)
+            .with_context(|| format!("line {}: \"{}\" is not a valid port", n + 1, port))?;
+        let protocol = match protocol.to_lowercase().as_str() {
+            "" => None,
+            "tcp" => Some(Protocol::Tcp),
+            "udp" => Some(Protocol::Udp),
+            other => bail!(
+                "line {}: unknown protocol \"{}\" (tcp or udp)",
+                n + 1,
+                other
+            ),
+        };
+        let service = service.trim();
+        expectations.push(Expectation {
+            port,
+            protocol,
+            service: (!service.is_empty()).then(|| service.to_string()),
+        });
+    }
+    Ok(expectations)
+}
+
+/// How the scan measured up to one expectation
+pub enum Outcome<'a> {
+    /// Listening, and by the expected service when one was named
+    Present(&'a PortEntry),
+    /// Nothing listening on the port (with that protocol)
+    Absent,
+    /// Listening, but by something else
+    WrongService(&'a PortEntry),
+}
+
+impl Outcome<'_> {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Outcome::Present(_))
+    }
+}
+
+/// Compare `expected` against the scanned `entries`. With several listeners
+/// on the port, any one serving the expected service is enough.
+pub fn check<'a>(expected: &Expectation, entries: &'a [PortEntry]) -> Outcome<'a> {
+    let listeners: Vec<&PortEntry> = entries
+        .iter()
+        .filter(|e| {
+            e.port == expected.port
+                && e.remote.is_none()
+                && expected.protocol.is_none_or(|p| e.protocol == p)
+        })
+        .collect();
+    match listeners.iter().find(|e| expected.is_served_by(e)) {
+        Some(e) => Outcome::Present(e),
+        None => match listeners.first() {
+            Some(e) => Outcome::WrongService(e),
+            None => Outcome::Absent,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ports_protocols_and_services() {
+        let text = "\
+# expected on the CI runner
+3000
+5432/tcp PostgreSQL
+53/UDP   dns server  # resolver
+
+";
+        let spec = parse(text).unwrap();
+        assert_eq!(spec.len(), 3);
+        assert_eq!(
+            spec[0],
+            Expectation {
+                port: 3000,
+                protocol: None,
+                service: None,
+            }
+        );
+        assert_eq!(spec[1].protocol, Some(Protocol::Tcp));
+        assert_eq!(spec[1].service.as_deref(), Some("PostgreSQL"));
+        assert_eq!(spec[2].protocol, Some(Protocol::Udp));
+        assert_eq!(spec[2].service.as_deref(), Some("dns server"));
+        assert_eq!(spec[1].label(), "5432/tcp PostgreSQL");
+
+        let err = parse("3000\nhttp nginx").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"));
+        assert!(parse("80/sctp").is_err());
+        assert!(parse("0").is_err());
+    }
+
+    #[test]
+    fn checks_presence_and_service() {
+        let entries = vec![
+            PortEntry {
+                known_service: Some("PostgreSQL"),
+                ..PortEntry::fixture(5432, 7, "postgres")
+            },
+            PortEntry::fixture(3000, 42, "node"),
+        ];
+        let spec = parse("5432 postgresql\n3000 nginx\n6379 Redis\n3000/udp").unwrap();
+        let outcomes: Vec<&str> = spec
+            .iter()
+            .map(|exp| match check(exp, &entries) {
+                Outcome::Present(_) => "present",
+                Outcome::Absent => "absent",
+                Outcome::WrongService(_) => "wrong",
+            })
+            .collect();
+        assert_eq!(outcomes, ["present", "wrong", "absent", "absent"]);
+    }
+}
@@ -0,0 +1,42 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::config::state_dir;
+
+/// Where the state file `name` lives, if there is a state directory
+pub fn path(name: &str) -> Option<PathBuf> {
+    state_dir().map(|d| d.join(name))
+}
+
+/// Read and parse a state file. A missing file is `Ok(None)`; one that exists
+/// but can't be read or parsed is an error naming it.
+pub fn load<T>(path: &Path, parse: impl FnOnce(&str) -> Result<T>) -> Result<Option<T>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    parse(&text)
+        .map(Some)
+        .with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Write `rows`, each one JSON object, as a JSON array to `path`, creating the
+/// state directory if needed
+pub fn save(path: &Path, rows: &[String]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let text = if rows.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    };
+
+    // Write beside it and rename, so a crash never leaves half a file
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, text).with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("writing {}", path.display()))?;
+    Ok(())
+}
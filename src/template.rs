@@ -0,0 +1,174 @@
+use anyhow::{bail, Result};
+
+use crate::models::PortEntry;
+
+/// Placeholders usable in `--template`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Port,
+    Proto,
+    Process,
+    Service,
+    Pid,
+    Cpu,
+    Mem,
+    Uptime,
+    Addr,
+}
+
+impl Field {
+    const ALL: [Field; 9] = [
+        Field::Port,
+        Field::Proto,
+        Field::Process,
+        Field::Service,
+        Field::Pid,
+        Field::Cpu,
+        Field::Mem,
+        Field::Uptime,
+        Field::Addr,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Field::Port => "port",
+            Field::Proto => "proto",
+            Field::Process => "process",
+            Field::Service => "service",
+            Field::Pid => "pid",
+            Field::Cpu => "cpu",
+            Field::Mem => "mem",
+            Field::Uptime => "uptime",
+            Field::Addr => "addr",
+        }
+    }
+
+    fn render(self, e: &PortEntry) -> String {
+        match self {
+            Field::Port => e.port.to_string(),
+            Field::Proto => e.protocol.to_string(),
+            Field::Process => e.process_name.clone(),
+            Field::Service => e.known_service.unwrap_or("-").to_string(),
+            Field::Pid => e.pid.to_string(),
+            Field::Cpu => format!("{:.1}%", e.cpu_percent),
+            Field::Mem => e.memory_display(),
+            Field::Uptime => e.uptime_display(),
+            Field::Addr => e.addr_display(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+/// A parsed `--template` such as `{port}\t{process}\t{pid}`. `{{` and `}}`
+/// are literal braces; `\t`, `\n` and `\\` are escapes, since shells pass
+/// them through verbatim.
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => bail!("unclosed `{{` in template"),
+                        }
+                    }
+                    let field = Field::ALL
+                        .into_iter()
+                        .find(|f| f.name() == name.trim())
+                        .ok_or_else(|| {
+                            let valid: Vec<_> = Field::ALL.iter().map(|f| f.name()).collect();
+                            anyhow::anyhow!(
+                                "unknown template field `{{{}}}` (valid: {})",
+                                name,
+                                valid.join(", ")
+                            )
+                        })?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => bail!("unmatched `}}` in template (use `}}}}` for a literal brace)"),
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, e: &PortEntry) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(s) => s.clone(),
+                Part::Field(f) => f.render(e),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_fields_escapes_and_braces() {
+        let entry = PortEntry {
+            known_service: Some("Vite"),
+            cpu_percent: 3.0,
+            ..PortEntry::fixture(5173, 42, "node")
+        };
+        let render = |s| Template::parse(s).unwrap().render(&entry);
+        assert_eq!(render(r"{port}\t{process}\t{pid}"), "5173\tnode\t42");
+        assert_eq!(render("{{{ service }}} {cpu} {proto}"), "{Vite} 3.0% TCP");
+        assert_eq!(render(r"a\\b\n\x"), "a\\b\n\\x");
+        assert_eq!(render(""), "");
+    }
+
+    #[test]
+    fn rejects_unknown_and_unbalanced_fields() {
+        let err = Template::parse("{port} {user}").unwrap_err().to_string();
+        assert!(err.contains("`{user}`"), "{}", err);
+        assert!(err.contains("valid: port, proto, process"), "{}", err);
+        assert!(Template::parse("{port").is_err());
+        assert!(Template::parse("port}").is_err());
+    }
+}
@@ -1,9 +1,101 @@
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
 use ratatui::style::Color;
+use ratatui::symbols::{border, scrollbar};
+
+use crate::models::{Protocol, ServiceCategory};
+
+/// Category color scheme, picked with `--palette`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Palette {
+    #[default]
+    Default,
+    /// Okabe–Ito hues plus a shape glyph per category, so color isn't the only cue
+    Colorblind,
+}
+
+/// How UDP rows are shown, picked with `--udp-style` or `[theme] udp_style`.
+/// Many users only care about TCP listeners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum UdpStyle {
+    #[default]
+    Normal,
+    /// Render UDP rows in the muted text color
+    Dim,
+    /// Leave UDP out unless asked for (`--protocol`, or the TUI protocol toggle)
+    Hide,
+}
+
+impl UdpStyle {
+    pub fn parse(s: &str) -> Option<Self> {
+        <Self as ValueEnum>::from_str(s, true).ok()
+    }
+}
+
+/// TUI border style, picked with `--border` or `[theme] border`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum BorderStyle {
+    #[default]
+    Plain,
+    Rounded,
+    /// `+`, `-` and `|` only, for fonts and SSH links that mangle box drawing
+    Ascii,
+    /// Blank borders; the layout and titles stay where they are
+    None,
+}
+
+impl BorderStyle {
+    pub fn parse(s: &str) -> Option<Self> {
+        <Self as ValueEnum>::from_str(s, true).ok()
+    }
+
+    pub fn symbols(self) -> border::Set {
+        match self {
+            BorderStyle::Plain => border::PLAIN,
+            BorderStyle::Rounded => border::ROUNDED,
+            BorderStyle::Ascii => border::Set {
+                top_left: "+",
+                top_right: "+",
+                bottom_left: "+",
+                bottom_right: "+",
+                vertical_left: "|",
+                vertical_right: "|",
+                horizontal_top: "-",
+                horizontal_bottom: "-",
+            },
+            BorderStyle::None => border::EMPTY,
+        }
+    }
 
-use crate::models::ServiceCategory;
+    /// Table scrollbar to match, drawn over the right border
+    pub fn scrollbar(self) -> scrollbar::Set {
+        match self {
+            BorderStyle::Ascii => scrollbar::Set {
+                track: "|",
+                thumb: "#",
+                begin: "^",
+                end: "v",
+            },
+            _ => scrollbar::VERTICAL,
+        }
+    }
+}
+
+/// User overrides from the `[theme]` config section
+#[derive(Debug, Clone, Default)]
+pub struct ThemeOverrides {
+    pub tcp: Option<Color>,
+    pub udp: Option<Color>,
+    pub udp_style: Option<UdpStyle>,
+    pub border: Option<BorderStyle>,
+}
 
 #[allow(dead_code)]
 pub struct Theme {
+    pub palette: Palette,
+    pub udp_style: UdpStyle,
+    pub border_style: BorderStyle,
     pub primary: Color,
     pub primary_dim: Color,
     pub bg_elevated: Color,
@@ -24,11 +116,16 @@ pub struct Theme {
     pub container: Color,
     pub browser: Color,
     pub system: Color,
+    pub tcp: Color,
+    pub udp: Color,
 }
 
 impl Default for Theme {
     fn default() -> Self {
         Self {
+            palette: Palette::Default,
+            udp_style: UdpStyle::Normal,
+            border_style: BorderStyle::Plain,
             primary: Color::Rgb(16, 185, 129),    // emerald-500
             primary_dim: Color::Rgb(5, 150, 105), // emerald-600
             bg_elevated: Color::Rgb(25, 25, 30),
@@ -49,11 +146,69 @@ impl Default for Theme {
             container: Color::Rgb(96, 165, 250), // blue
             browser: Color::Rgb(251, 146, 60),   // orange
             system: Color::Rgb(140, 140, 145),   // gray
+            tcp: Color::Rgb(6, 182, 212),        // cyan
+            udp: Color::Rgb(244, 114, 182),      // pink
         }
     }
 }
 
 impl Theme {
+    pub fn new(palette: Palette) -> Self {
+        match palette {
+            Palette::Default => Self::default(),
+            Palette::Colorblind => Self {
+                palette,
+                dev_server: Color::Rgb(86, 180, 233), // sky blue
+                database: Color::Rgb(230, 159, 0),    // orange
+                cache: Color::Rgb(204, 121, 167),     // reddish purple
+                container: Color::Rgb(240, 228, 66),  // yellow
+                browser: Color::Rgb(213, 94, 0),      // vermillion
+                system: Color::Rgb(140, 140, 145),    // gray
+                tcp: Color::Rgb(0, 114, 178),         // blue
+                udp: Color::Rgb(0, 158, 115),         // bluish green
+                ..Self::default()
+            },
+        }
+    }
+
+    /// Apply `[theme]` config on top of the palette
+    pub fn with_overrides(mut self, overrides: &ThemeOverrides) -> Self {
+        self.tcp = overrides.tcp.unwrap_or(self.tcp);
+        self.udp = overrides.udp.unwrap_or(self.udp);
+        self.udp_style = overrides.udp_style.unwrap_or(self.udp_style);
+        self.border_style = overrides.border.unwrap_or(self.border_style);
+        self
+    }
+
+    pub fn protocol_color(&self, protocol: Protocol) -> Color {
+        match protocol {
+            Protocol::Tcp => self.tcp,
+            Protocol::Udp => self.udp,
+        }
+    }
+
+    /// Whether rows of this protocol are drawn muted
+    pub fn is_dimmed(&self, protocol: Protocol) -> bool {
+        protocol == Protocol::Udp && self.udp_style == UdpStyle::Dim
+    }
+
+    /// Glyph + space to put before a service label, or "" when the palette
+    /// relies on color alone
+    pub fn category_prefix(&self, category: ServiceCategory) -> &'static str {
+        if self.palette == Palette::Default {
+            return "";
+        }
+        match category {
+            ServiceCategory::DevServer => "▲ ",
+            ServiceCategory::Database => "■ ",
+            ServiceCategory::Cache => "◆ ",
+            ServiceCategory::Container => "▣ ",
+            ServiceCategory::Browser => "● ",
+            ServiceCategory::System => "○ ",
+            ServiceCategory::Unknown => "",
+        }
+    }
+
     pub fn category_color(&self, category: ServiceCategory) -> Color {
         match category {
             ServiceCategory::DevServer => self.dev_server,
@@ -67,9 +222,15 @@ impl Theme {
     }
 }
 
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Choose the palette and apply config overrides; must run before the first
+/// `theme()` call to take effect
+pub fn init_theme(palette: Palette, overrides: &ThemeOverrides) {
+    let _ = THEME.set(Theme::new(palette).with_overrides(overrides));
+}
+
 /// Global theme instance
 pub fn theme() -> &'static Theme {
-    use std::sync::OnceLock;
-    static THEME: OnceLock<Theme> = OnceLock::new();
     THEME.get_or_init(Theme::default)
 }
@@ -0,0 +1,244 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::models::{PortEntry, ProcessNode};
+
+/// What a tree row points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeNode {
+    /// A process heading — either a port owner or one of its ancestors
+    Process { pid: u32, name: String },
+    /// A listening port, as an index into the entries slice
+    Entry(usize),
+}
+
+/// One rendered line of the process tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeRow {
+    pub depth: usize,
+    pub node: TreeNode,
+}
+
+/// Group entries under their parent process hierarchy.
+///
+/// Each port owner is linked up through its ancestors until PID 1 (init/launchd)
+/// or an unknown parent, so ports spawned by the same shell or `npm` invocation
+/// end up under one heading. `indices` selects (and orders) the entries to place.
+pub fn build_tree(
+    entries: &[PortEntry],
+    indices: &[usize],
+    parents: &HashMap<u32, ProcessNode>,
+) -> Vec<TreeRow> {
+    let mut ports: HashMap<u32, Vec<usize>> = HashMap::new();
+    let mut children: HashMap<u32, BTreeSet<u32>> = HashMap::new();
+    let mut roots: BTreeSet<u32> = BTreeSet::new();
+    let mut linked: HashSet<u32> = HashSet::new();
+
+    for &idx in indices {
+        let pid = entries[idx].pid;
+        ports.entry(pid).or_default().push(idx);
+
+        // Walk up until we hit a process that's already linked into the tree
+        let mut current = pid;
+        let mut walked = Vec::new();
+        while linked.insert(current) {
+            walked.push(current);
+            match parent_of(current, parents) {
+                // A PPID loop (possible after PID reuse): cut it here
+                Some(ppid) if walked.contains(&ppid) => {
+                    roots.insert(current);
+                    break;
+                }
+                Some(ppid) => {
+                    children.entry(ppid).or_default().insert(current);
+                    current = ppid;
+                }
+                None => {
+                    roots.insert(current);
+                    break;
+                }
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    for &root in &roots {
+        push_subtree(root, 0, entries, parents, &ports, &children, &mut rows);
+    }
+    rows
+}
+
+/// `pid` and every process below it, found breadth-first over the parent
+/// links in `parents`, ordered deepest first so children come before their
+/// parents and `pid` itself is last. Meant for killing, so it never includes
+/// init or kav (`own_pid`) and its ancestors: a tree rooted at one of those
+/// comes back empty, and a shell or supervisor above kav is left running.
+pub fn subtree(pid: u32, parents: &HashMap<u32, ProcessNode>, own_pid: u32) -> Vec<u32> {
+    let spared = spared(own_pid, parents);
+    if spared.contains(&pid) {
+        return Vec::new();
+    }
+
+    let mut children: HashMap<u32, BTreeSet<u32>> = HashMap::new();
+    for (&child, node) in parents {
+        if let Some(ppid) = node.ppid.filter(|&ppid| ppid != child) {
+            children.entry(ppid).or_default().insert(child);
+        }
+    }
+
+    let mut order = vec![pid];
+    let mut seen = HashSet::from([pid]);
+    let mut next = 0;
+    while let Some(&current) = order.get(next) {
+        for &child in children.get(&current).into_iter().flatten() {
+            if seen.insert(child) && !spared.contains(&child) {
+                order.push(child);
+            }
+        }
+        next += 1;
+    }
+    order.reverse();
+    order
+}
+
+/// PID 1, `own_pid` and every ancestor of it
+fn spared(own_pid: u32, parents: &HashMap<u32, ProcessNode>) -> HashSet<u32> {
+    let mut spared = HashSet::from([1, own_pid]);
+    let mut current = own_pid;
+    while let Some(ppid) = parents.get(&current).and_then(|node| node.ppid) {
+        if !spared.insert(ppid) {
+            break;
+        }
+        current = ppid;
+    }
+    spared
+}
+
+fn parent_of(pid: u32, parents: &HashMap<u32, ProcessNode>) -> Option<u32> {
+    parents
+        .get(&pid)
+        .and_then(|node| node.ppid)
+        .filter(|&ppid| ppid > 1 && ppid != pid)
+}
+
+fn push_subtree(
+    pid: u32,
+    depth: usize,
+    entries: &[PortEntry],
+    parents: &HashMap<u32, ProcessNode>,
+    ports: &HashMap<u32, Vec<usize>>,
+    children: &HashMap<u32, BTreeSet<u32>>,
+    rows: &mut Vec<TreeRow>,
+) {
+    let owned = ports.get(&pid);
+    let name = owned
+        .and_then(|idxs| idxs.first())
+        .map(|&idx| entries[idx].process_name.clone())
+        .or_else(|| parents.get(&pid).map(|node| node.name.clone()))
+        .unwrap_or_else(|| String::from("?"));
+
+    rows.push(TreeRow {
+        depth,
+        node: TreeNode::Process { pid, name },
+    });
+
+    for &idx in owned.into_iter().flatten() {
+        rows.push(TreeRow {
+            depth: depth + 1,
+            node: TreeNode::Entry(idx),
+        });
+    }
+
+    for &child in children.get(&pid).into_iter().flatten() {
+        push_subtree(child, depth + 1, entries, parents, ports, children, rows);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(ppid: u32, name: &str) -> ProcessNode {
+        ProcessNode {
+            ppid: Some(ppid),
+            name: name.to_string(),
+        }
+    }
+
+    fn process(depth: usize, pid: u32, name: &str) -> TreeRow {
+        TreeRow {
+            depth,
+            node: TreeNode::Process {
+                pid,
+                name: name.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn ppid_loop_and_missing_parent_keep_every_port() {
+        let entries = vec![
+            PortEntry::fixture(3000, 10, "node"),
+            PortEntry::fixture(5432, 20, "postgres"),
+        ];
+        // 10 and 11 name each other as parent; 20's parent 99 has exited
+        let parents = HashMap::from([
+            (10, node(11, "node")),
+            (11, node(10, "npm")),
+            (20, node(99, "postgres")),
+        ]);
+
+        let rows = build_tree(&entries, &[0, 1], &parents);
+        assert_eq!(
+            rows,
+            [
+                process(0, 11, "npm"),
+                process(1, 10, "node"),
+                TreeRow {
+                    depth: 2,
+                    node: TreeNode::Entry(0),
+                },
+                process(0, 99, "?"),
+                process(1, 20, "postgres"),
+                TreeRow {
+                    depth: 2,
+                    node: TreeNode::Entry(1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn subtree_survives_loops_and_missing_parents() {
+        let parents = HashMap::from([
+            (10, node(11, "node")),
+            (11, node(10, "npm")),
+            (12, node(10, "esbuild")),
+            // 20's parent 99 has exited
+            (20, node(99, "worker")),
+            (21, node(20, "worker")),
+        ]);
+        assert_eq!(subtree(10, &parents, 500), [12, 11, 10]);
+        assert_eq!(subtree(20, &parents, 500), [21, 20]);
+        assert_eq!(subtree(99, &parents, 500), [21, 20, 99]);
+        assert_eq!(subtree(42, &parents, 500), [42]);
+    }
+
+    #[test]
+    fn subtree_spares_init_and_kav_with_its_ancestors() {
+        // systemd(1) > tmux(100) > zsh(200) > kav(500), and zsh > vite(300) > esbuild(301)
+        let parents = HashMap::from([
+            (100, node(1, "tmux")),
+            (200, node(100, "zsh")),
+            (300, node(200, "vite")),
+            (301, node(300, "esbuild")),
+            (500, node(200, "kav")),
+            (501, node(500, "ssh")),
+        ]);
+        for root in [1, 100, 200, 500] {
+            assert!(subtree(root, &parents, 500).is_empty(), "PID {}", root);
+        }
+        assert_eq!(subtree(300, &parents, 500), [301, 300]);
+        // Another kav's tree is fair game
+        assert_eq!(subtree(200, &parents, 999), [501, 301, 500, 300, 200]);
+    }
+}
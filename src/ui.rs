@@ -1,141 +1,712 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{self, Event, KeyCode, KeyEvent},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    widgets::{
+        Block, Borders, Cell, Clear, Gauge, Paragraph, Row, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Table, TableState, Wrap,
+    },
     Frame, Terminal,
 };
 
-use crate::models::{PortEntry, SortField};
-use crate::scanner::{kill_process, scan_ports};
-use crate::theme::theme;
+use crate::asn::AsnDb;
+use crate::config::{add_ignore, Config, IgnoreRule, Protection, Watch};
+use crate::filter::Filter;
+use crate::history::FirstSeen;
+use crate::keymap::{Action, KeyMap};
+use crate::models::{
+    duration_display, port_conflicts, proxy_backend, proxy_frontends, sort_entries, PortEntry,
+    ProcessNode, Protocol, ServiceCategory, SocketState, SortField,
+};
+use crate::notes::Notes;
+use crate::output::{truncate, write_csv, write_json};
+use crate::restart::{self, Launch};
+use crate::scanner::{
+    current_uid, kill_process, process_parents, process_user_and_cwd, scan_with, LiveSource,
+    ScanOptions, ScanSource, SystemUsage,
+};
+use crate::theme::{theme, UdpStyle};
+use crate::tree::{build_tree, subtree, TreeNode, TreeRow};
 
 const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
 
+/// Bounds for the base interval set with the faster / slower keys; both are
+/// reached from the default by halving or doubling
+const MIN_REFRESH: Duration = Duration::from_millis(250);
+const MAX_REFRESH: Duration = Duration::from_secs(32);
+
+/// Status messages kept for the `L` log; older ones are dropped
+const LOG_LIMIT: usize = 200;
+
+/// Whether a status message reports a failure, for the log's colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MsgKind {
+    Info,
+    Error,
+}
+
+/// System gauges turn yellow, then red, past these shares of CPU or memory
+const GAUGE_WARNING: f64 = 0.7;
+const GAUGE_CRITICAL: f64 = 0.9;
+
+/// Unchanged auto-refreshes in a row before the interval starts to stretch
+const IDLE_SCANS: u32 = 3;
+
+/// No keypress for this long counts as idle
+const IDLE_INPUT: Duration = Duration::from_secs(10);
+
+/// PROCESS column width unless `[columns] process` says otherwise
+const PROCESS_COLUMN_WIDTH: u16 = 14;
+
+/// Faster re-scans while a killed process hasn't disappeared yet
+const KILL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a killed process may linger before we suggest a force kill
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const SPINNER_TICK: Duration = Duration::from_millis(100);
+
+/// Startup settings for the TUI, from command-line flags
+pub struct TuiOptions<'a> {
+    pub include_self: bool,
+    pub ephemeral_udp: bool,
+    pub include_threads: bool,
+    /// `--show-inode`: socket inode in the detail pane (Linux)
+    pub show_inode: bool,
+    /// `--yolo`: force kill without a confirmation
+    pub yolo: bool,
+    /// `--read-only`: inspect only; kill and restart keys do nothing
+    pub read_only: bool,
+    pub total_sockets: bool,
+    pub show_ignored: bool,
+    pub geoip: Option<&'a Path>,
+    pub sort_secondary: SortField,
+    /// `--ascii`: plain numbers instead of the inline CPU/MEM bars
+    pub ascii: bool,
+}
+
+/// Process picked for the full-screen view, plus what scans don't collect
+struct Zoom {
+    pid: u32,
+    user: Option<String>,
+    cwd: Option<PathBuf>,
+}
+
 struct App {
     entries: Vec<PortEntry>,
-    filtered: Vec<usize>, // indices into entries
+    unresolved: usize,            // sockets the last scan couldn't map to a process
+    stale: bool,                  // the last scan failed; the table shows the one before
+    underprivileged: bool,        // other users' sockets are hidden from us
+    total_sockets: Option<usize>, // every open socket, when --total-sockets asked for it
+    hidden_udp: usize,            // ephemeral UDP client sockets the last scan left out
+    filtered: Vec<usize>,         // indices into entries
     table_state: TableState,
     filter_text: String,
     filter_active: bool,
     show_tcp: bool,
     show_udp: bool,
+    show_established: bool,
+    hide_loopback: bool,
+    killable_only: bool, // only processes we may signal
+    my_uid: Option<u32>, // who we run as, for killable_only; None where unknown
+    unknown_only: bool,  // only entries with no recognized service
+    include_self: bool,
+    ephemeral_udp: bool,
+    include_threads: bool,
+    show_inode: bool,
+    read_only: bool, // --read-only: no kills or restarts from the TUI
+    count_sockets: bool,
     sort_field: SortField,
+    sort_secondary: SortField, // tie-breaker for sort_field
+    ascii: bool,               // no block characters in the table
     show_detail: bool,
-    confirm_kill: Option<usize>, // index of entry to confirm kill
+    show_gauges: bool,           // system CPU and memory gauges above the table
+    source: Box<dyn ScanSource>, // kept between scans so CPU figures have a previous sample
+    usage: Option<SystemUsage>,  // whole-system load at the last scan
+    zoom: Option<Zoom>,          // full-screen view of one process, replacing the table
+    tree_view: bool,
+    parents: HashMap<u32, ProcessNode>,
+    tree_rows: Vec<TreeRow>,
+    fingerprint: Option<u64>, // socket/PID set of the last scan; None forces a full rebuild
+    refresh_interval: Duration, // auto-refresh period; doubles while idle, up to max_refresh
+    base_refresh: Duration,   // refresh_interval when not idle; + and - halve and double it
+    max_refresh: Duration,    // idle cap; zero when [refresh] idle_backoff is off
+    unchanged_scans: u32,     // auto-refreshes in a row that found the same sockets and owners
+    last_input: Instant,      // last keypress, for telling whether the user is idle
+    follow_pid: Option<u32>,  // selection stays on this PID across refreshes and sorts
+    confirm_kill: Option<PortEntry>, // entry to kill, as it was when the dialog opened
+    confirm_force: bool,      // the pending confirmation is for a force kill
+    confirm_tree: Vec<u32>,   // for a tree kill: every PID to kill, children first
+    confirm_input: String,    // port number typed to confirm a protected kill
+    marked: HashSet<u32>,     // PIDs marked for a batch kill
+    confirm_marked: Vec<u32>, // marked PIDs awaiting a batch-kill confirmation
+    sort_menu: Option<usize>, // open sort picker, with the highlighted row of SortField::ALL
+    command_input: Option<String>, // `:` command line being typed
+    note_input: Option<(PortEntry, String)>, // note being typed for an entry
+    notes: Notes,             // free-text notes on port + process, kept across runs
+    confirm_restart: Option<(PortEntry, Launch)>, // entry to restart and the command it'd re-run
+    restart_job: Option<(PortEntry, Receiver<Result<u32>>)>, // restart running in the background
+    process_width: u16,       // PROCESS column, from `[columns] process`
+    show_cmd_col: bool,       // PROCESS column shows the command line instead of the name
+    protect: Protection,
+    watch: Watch,            // `[watch]`: ports starred in the table, maybe sorted first
+    ignore: Vec<IgnoreRule>, // config ignore list, extended with the ignore key
+    show_ignored: bool,      // --show-ignored: keep ignore-listed entries visible
+    ignored: usize,          // entries the ignore list hid on the last filter pass
+    conflicts: HashSet<(Protocol, u16)>, // ports bound on different addresses by unrelated processes
+    asn: Option<AsnDb>,                  // labels remote peers in the detail pane (--geoip)
+    history: Option<FirstSeen>,          // first-seen dates across runs; None when [history] is off
     status_msg: Option<(String, Instant)>,
+    log: VecDeque<(Instant, String, MsgKind)>, // every status message, oldest first
+    show_log: bool,
+    export_prompt: bool, // export key pressed; the next key picks CSV or JSON
+    peek: bool,          // full process, service and command of the selected row, by the row
+    pending_print: Option<String>, // line to write to the normal screen's scrollback
+    pending_kills: HashMap<u32, PendingKill>, // PIDs signalled but not yet gone from a scan
+    keymap: KeyMap,
     should_quit: bool,
 }
 
 impl App {
-    fn new() -> Self {
+    fn new(keymap: KeyMap, config: Config, opts: &TuiOptions) -> Self {
         Self {
             entries: Vec::new(),
+            unresolved: 0,
+            stale: false,
+            underprivileged: false,
+            total_sockets: None,
+            hidden_udp: 0,
             filtered: Vec::new(),
             table_state: TableState::default(),
             filter_text: String::new(),
             filter_active: false,
             show_tcp: true,
-            show_udp: true,
+            show_udp: theme().udp_style != UdpStyle::Hide,
+            show_established: false,
+            hide_loopback: false,
+            killable_only: false,
+            unknown_only: false,
+            my_uid: current_uid(),
+            include_self: opts.include_self,
+            ephemeral_udp: opts.ephemeral_udp,
+            include_threads: opts.include_threads,
+            show_inode: opts.show_inode,
+            read_only: opts.read_only,
+            count_sockets: opts.total_sockets,
             sort_field: SortField::Port,
+            sort_secondary: opts.sort_secondary,
+            ascii: opts.ascii,
             show_detail: false,
+            show_gauges: false,
+            source: Box::new(LiveSource::default()),
+            usage: None,
+            zoom: None,
+            tree_view: false,
+            parents: HashMap::new(),
+            tree_rows: Vec::new(),
+            fingerprint: None,
+            refresh_interval: REFRESH_INTERVAL,
+            base_refresh: REFRESH_INTERVAL,
+            max_refresh: if config.refresh.idle_backoff {
+                Duration::from_secs(config.refresh.max_interval_secs).max(REFRESH_INTERVAL)
+            } else {
+                Duration::ZERO
+            },
+            unchanged_scans: 0,
+            last_input: Instant::now(),
+            follow_pid: None,
             confirm_kill: None,
+            confirm_force: false,
+            confirm_tree: Vec::new(),
+            confirm_input: String::new(),
+            marked: HashSet::new(),
+            confirm_marked: Vec::new(),
+            sort_menu: None,
+            command_input: None,
+            note_input: None,
+            notes: Notes::load(),
+            confirm_restart: None,
+            restart_job: None,
+            show_cmd_col: false,
+            process_width: config
+                .columns
+                .process
+                .map_or(PROCESS_COLUMN_WIDTH, |w| w as u16),
+            history: config
+                .history
+                .enabled
+                .then(|| FirstSeen::load(config.history.forget_after_days)),
+            protect: Protection {
+                confirm_force_kill: config.protect.confirm_force_kill && !opts.yolo,
+                ..config.protect
+            },
+            watch: config.watch,
+            ignore: config.ignore,
+            show_ignored: opts.show_ignored,
+            conflicts: HashSet::new(),
+            ignored: 0,
+            asn: None,
             status_msg: None,
+            log: VecDeque::new(),
+            show_log: false,
+            export_prompt: false,
+            peek: false,
+            pending_print: None,
+            pending_kills: HashMap::new(),
+            keymap,
             should_quit: false,
         }
     }
 
     fn refresh(&mut self) {
-        match scan_ports(self.show_tcp, self.show_udp) {
-            Ok(entries) => {
-                self.entries = entries;
+        let opts = ScanOptions {
+            tcp: self.show_tcp,
+            udp: self.show_udp,
+            established: self.show_established,
+            include_self: self.include_self,
+            ephemeral_udp: self.ephemeral_udp,
+            include_threads: self.include_threads,
+            total_sockets: self.count_sockets,
+        };
+        match scan_with(self.source.as_mut(), &opts) {
+            Ok(result) => {
+                self.stale = false;
+                self.usage = self.source.usage();
+                self.unresolved = result.unresolved;
+                self.underprivileged = result.underprivileged;
+                self.total_sockets = result.total_sockets;
+                self.hidden_udp = result.hidden_udp;
+                self.record_sightings(&result.entries);
+
+                // Same sockets and owners as last time: only the numbers moved, so
+                // update them in place and keep the current order, filter and tree.
+                // CPU and memory sorts depend on those numbers, so they always re-sort;
+                // uptimes all grow together, so that order holds too.
+                let fingerprint = fingerprint(&result.entries);
+                let order_is_stable = matches!(
+                    self.sort_field,
+                    SortField::Port | SortField::ProcessName | SortField::Uptime
+                );
+                if self.fingerprint == Some(fingerprint) && order_is_stable {
+                    self.update_metrics(result.entries);
+                    self.settle_kills();
+                    return;
+                }
+                self.fingerprint = Some(fingerprint);
+
+                let selected = self.selected_key();
+                self.entries = result.entries;
+                let entries = &self.entries;
+                self.marked
+                    .retain(|pid| entries.iter().any(|e| e.pid == *pid));
+                self.conflicts = port_conflicts(&self.entries)
+                    .iter()
+                    .map(|g| (g[0].protocol, g[0].port))
+                    .collect();
+                if self.tree_view {
+                    self.parents = process_parents();
+                }
                 self.sort_entries();
                 self.apply_filter();
+                if let Some((pid, port)) = selected {
+                    self.reselect(pid, port);
+                }
+                self.settle_kills();
             }
+            // Keep the last good results on screen, marked stale, rather than nothing
             Err(e) => {
-                self.status_msg = Some((format!("Scan error: {}", e), Instant::now()));
+                self.stale = true;
+                self.notify(MsgKind::Error, format!("Scan error: {:#}", e));
+            }
+        }
+    }
+
+    /// Show `msg` in the status bar for a few seconds and keep it in the log
+    fn notify(&mut self, kind: MsgKind, msg: impl Into<String>) {
+        let msg = msg.into();
+        let now = Instant::now();
+        if self.log.len() == LOG_LIMIT {
+            self.log.pop_front();
+        }
+        self.log.push_back((now, msg.clone(), kind));
+        self.status_msg = Some((msg, now));
+    }
+
+    /// Auto-refresh backoff: stretch the interval after `IDLE_SCANS` unchanged
+    /// scans with no recent keypress, and go back to the base on any change
+    fn note_scan(&mut self, changed: bool) {
+        if changed || self.stale {
+            self.unchanged_scans = 0;
+            self.refresh_interval = self.base_refresh;
+            return;
+        }
+        self.unchanged_scans = self.unchanged_scans.saturating_add(1);
+        if self.unchanged_scans >= IDLE_SCANS && self.last_input.elapsed() >= IDLE_INPUT {
+            let cap = self.max_refresh.max(self.base_refresh);
+            self.refresh_interval = (self.refresh_interval * 2).min(cap);
+        }
+    }
+
+    /// Halve (`faster`) or double the base refresh interval, within
+    /// `MIN_REFRESH` and `MAX_REFRESH`
+    fn change_rate(&mut self, faster: bool) {
+        let base = if faster {
+            self.base_refresh / 2
+        } else {
+            self.base_refresh * 2
+        };
+        self.base_refresh = base.clamp(MIN_REFRESH, MAX_REFRESH);
+        self.refresh_interval = self.base_refresh;
+        self.notify(
+            MsgKind::Info,
+            format!("Refreshing every {}", interval_display(self.base_refresh)),
+        );
+    }
+
+    /// A keypress: the user is back, so refresh at the base rate again
+    fn note_input(&mut self) {
+        self.last_input = Instant::now();
+        self.unchanged_scans = 0;
+        self.refresh_interval = self.base_refresh;
+    }
+
+    /// Add the scan to the first-seen history. A failed save turns history
+    /// off for the session rather than repeating the error on every refresh.
+    fn record_sightings(&mut self, entries: &[PortEntry]) {
+        let Some(history) = &mut self.history else {
+            return;
+        };
+        history.observe(entries);
+        if let Err(e) = history.save(false) {
+            self.notify(MsgKind::Error, format!("First-seen history off: {:#}", e));
+            self.history = None;
+        }
+    }
+
+    /// Report a finished background restart
+    fn poll_restart(&mut self) {
+        let Some((entry, rx)) = &self.restart_job else {
+            return;
+        };
+        let (kind, msg) = match rx.try_recv() {
+            Err(mpsc::TryRecvError::Empty) => return,
+            Ok(Ok(pid)) => (
+                MsgKind::Info,
+                format!(
+                    "Restarted {} on port {} as PID {}",
+                    entry.process_name, entry.port, pid
+                ),
+            ),
+            Ok(Err(e)) => (MsgKind::Error, format!("Restart failed: {:#}", e)),
+            Err(mpsc::TryRecvError::Disconnected) => (MsgKind::Error, "Restart failed".to_string()),
+        };
+        self.restart_job = None;
+        self.notify(kind, msg);
+        self.refresh();
+    }
+
+    /// Note a signalled PID and the ports it listened on, for `settle_kills`
+    fn track_kill(&mut self, pid: u32) {
+        let mut ports: Vec<u16> = self
+            .entries
+            .iter()
+            .filter(|e| e.pid == pid && e.remote.is_none())
+            .map(|e| e.port)
+            .collect();
+        ports.sort_unstable();
+        ports.dedup();
+        let since = Instant::now();
+        self.pending_kills.insert(pid, PendingKill { since, ports });
+    }
+
+    /// Forget killed PIDs the latest scan no longer shows, and give up on
+    /// ones that outlived `KILL_TIMEOUT`. A port that's already held again
+    /// by another PID is reported as restarted, so a supervisor bringing the
+    /// service straight back doesn't look like a failed kill.
+    fn settle_kills(&mut self) {
+        let entries = &self.entries;
+        let gone: Vec<u32> = self
+            .pending_kills
+            .keys()
+            .filter(|&&pid| !entries.iter().any(|e| e.pid == pid))
+            .copied()
+            .collect();
+        for pid in gone {
+            let Some(kill) = self.pending_kills.remove(&pid) else {
+                continue;
+            };
+            let successors: Vec<(u16, u32)> = kill
+                .ports
+                .iter()
+                .filter_map(|&port| {
+                    self.entries
+                        .iter()
+                        .find(|e| {
+                            e.port == port
+                                && e.remote.is_none()
+                                && !self.pending_kills.contains_key(&e.pid)
+                        })
+                        .map(|e| (port, e.pid))
+                })
+                .collect();
+            for (port, new_pid) in successors {
+                self.notify(
+                    MsgKind::Info,
+                    format!("Port {} restarted (new PID {})", port, new_pid),
+                );
+            }
+        }
+
+        let stuck: Vec<u32> = self
+            .pending_kills
+            .iter()
+            .filter(|(_, kill)| kill.since.elapsed() >= KILL_TIMEOUT)
+            .map(|(&pid, _)| pid)
+            .collect();
+        for pid in stuck {
+            self.pending_kills.remove(&pid);
+            self.notify(
+                MsgKind::Error,
+                format!(
+                    "PID {} is still running after {}s; try force kill ({})",
+                    pid,
+                    KILL_TIMEOUT.as_secs(),
+                    self.keymap.label(Action::ForceKill)
+                ),
+            );
+        }
+    }
+
+    /// Copy CPU, memory and uptime from a fresh scan of the same sockets
+    fn update_metrics(&mut self, fresh: Vec<PortEntry>) {
+        let fresh: HashMap<_, PortEntry> = fresh.into_iter().map(|e| (entry_key(&e), e)).collect();
+        for e in &mut self.entries {
+            if let Some(new) = fresh.get(&entry_key(e)) {
+                e.cpu_percent = new.cpu_percent;
+                e.memory_mb = new.memory_mb;
+                e.memory_percent = new.memory_percent;
+                e.thread_count = new.thread_count;
+                e.backlog = new.backlog;
+                e.inode = new.inode;
+                e.uptime = new.uptime;
             }
         }
     }
 
     fn sort_entries(&mut self) {
-        match self.sort_field {
-            SortField::Port => self.entries.sort_by_key(|e| e.port),
-            SortField::ProcessName => self.entries.sort_by(|a, b| {
-                a.process_name
-                    .to_lowercase()
-                    .cmp(&b.process_name.to_lowercase())
-            }),
-            SortField::Cpu => self
-                .entries
-                .sort_by(|a, b| b.cpu_percent.partial_cmp(&a.cpu_percent).unwrap()),
-            SortField::Memory => self
-                .entries
-                .sort_by(|a, b| b.memory_mb.partial_cmp(&a.memory_mb).unwrap()),
+        sort_entries(&mut self.entries, self.sort_field, self.sort_secondary);
+        self.watch.float_to_top(&mut self.entries);
+    }
+
+    /// Re-sort after the sort field changed, keeping the same entry selected
+    fn resort(&mut self) {
+        let selected = self.selected_key();
+        self.sort_entries();
+        self.apply_filter();
+        if let Some((pid, port)) = selected {
+            self.reselect(pid, port);
+        }
+    }
+
+    /// `(pid, port)` of the selected row, to find it again after reordering
+    fn selected_key(&self) -> Option<(u32, u16)> {
+        self.selected_entry().map(|e| (e.pid, e.port))
+    }
+
+    /// Select the row of `pid` on `port` if it's still shown; the table
+    /// scrolls it into view on the next draw
+    fn reselect(&mut self, pid: u32, port: u16) {
+        let is_it = |idx: usize| self.entries[idx].pid == pid && self.entries[idx].port == port;
+        let row = if self.tree_view {
+            self.tree_rows.iter().position(|row| match row.node {
+                TreeNode::Entry(idx) => is_it(idx),
+                TreeNode::Process { .. } => false,
+            })
+        } else {
+            self.filtered.iter().position(|&idx| is_it(idx))
+        };
+        if row.is_some() {
+            self.table_state.select(row);
         }
     }
 
     fn apply_filter(&mut self) {
-        let query = self.filter_text.to_lowercase();
+        let rules = if self.show_ignored {
+            Vec::new()
+        } else {
+            self.ignore.clone()
+        };
+        let filter = Filter::new()
+            .text(&self.filter_text)
+            .hide_loopback(self.hide_loopback)
+            .killable(self.killable_only.then_some(self.my_uid))
+            .unknown_only(self.unknown_only)
+            .ignore(rules);
+        self.ignored = self.entries.iter().filter(|e| filter.is_ignored(e)).count();
         self.filtered = self
             .entries
             .iter()
             .enumerate()
-            .filter(|(_, e)| {
-                if query.is_empty() {
-                    return true;
-                }
-                e.port.to_string().contains(&query)
-                    || e.process_name.to_lowercase().contains(&query)
-                    || e.known_service
-                        .map(|s| s.to_lowercase().contains(&query))
-                        .unwrap_or(false)
-            })
+            .filter(|(_, e)| filter.matches(e))
             .map(|(i, _)| i)
             .collect();
 
+        self.tree_rows = if self.tree_view {
+            build_tree(&self.entries, &self.filtered, &self.parents)
+        } else {
+            Vec::new()
+        };
+
+        if let Some(pid) = self.follow_pid {
+            if !self.entries.iter().any(|e| e.pid == pid) {
+                self.follow_pid = None;
+                self.notify(
+                    MsgKind::Info,
+                    format!("PID {} is gone; stopped following", pid),
+                );
+            } else if let Some(row) = self.row_of_pid(pid) {
+                self.table_state.select(Some(row));
+                return;
+            }
+        }
+
         // Keep selection in bounds
+        let rows = self.row_count();
         if let Some(selected) = self.table_state.selected() {
-            if selected >= self.filtered.len() {
-                self.table_state.select(if self.filtered.is_empty() {
-                    None
-                } else {
-                    Some(self.filtered.len() - 1)
-                });
+            if selected >= rows {
+                self.table_state
+                    .select(if rows == 0 { None } else { Some(rows - 1) });
             }
-        } else if !self.filtered.is_empty() {
+        } else if rows > 0 {
             self.table_state.select(Some(0));
         }
     }
 
+    /// Table row showing the first entry owned by `pid`, if it passes the filter
+    fn row_of_pid(&self, pid: u32) -> Option<usize> {
+        if self.tree_view {
+            self.tree_rows.iter().position(|row| match row.node {
+                TreeNode::Entry(idx) => self.entries[idx].pid == pid,
+                TreeNode::Process { .. } => false,
+            })
+        } else {
+            self.filtered
+                .iter()
+                .position(|&idx| self.entries[idx].pid == pid)
+        }
+    }
+
+    /// Number of table rows (tree view adds process heading rows)
+    fn row_count(&self) -> usize {
+        if self.tree_view {
+            self.tree_rows.len()
+        } else {
+            self.filtered.len()
+        }
+    }
+
+    /// PIDs owning the rows that pass the filter; PID 0 has nothing to kill
+    fn visible_pids(&self) -> HashSet<u32> {
+        self.filtered
+            .iter()
+            .map(|&idx| self.entries[idx].pid)
+            .filter(|&pid| pid != 0)
+            .collect()
+    }
+
+    /// Mark every visible process; marks on hidden rows stay as they are
+    fn mark_all_visible(&mut self) {
+        self.marked.extend(self.visible_pids());
+    }
+
+    /// Flip the marks of visible processes, leaving hidden ones alone
+    fn invert_marks(&mut self) {
+        for pid in self.visible_pids() {
+            if !self.marked.remove(&pid) {
+                self.marked.insert(pid);
+            }
+        }
+    }
+
+    /// PROCESS column text after `prefix` (the tree view's indent): the name,
+    /// or the command cut to the column when `show_cmd_col` is on and it's known
+    fn process_text(&self, prefix: &str, e: &PortEntry) -> String {
+        if self.show_cmd_col && !e.process_cmd.is_empty() {
+            truncate(
+                &format!("{}{}", prefix, e.process_cmd),
+                self.process_width as usize,
+            )
+        } else {
+            format!("{}{}", prefix, e.process_name)
+        }
+    }
+
+    /// Listening on a port that an unrelated process also binds on another address
+    fn is_conflict(&self, e: &PortEntry) -> bool {
+        e.remote.is_none() && self.conflicts.contains(&(e.protocol, e.port))
+    }
+
+    /// What the PORT cell flags: a conflict outranks a watched port
+    fn port_badge(&self, e: &PortEntry) -> PortBadge {
+        if self.is_conflict(e) {
+            PortBadge::Conflict
+        } else if self.watch.covers(e) {
+            PortBadge::Watched
+        } else {
+            PortBadge::None
+        }
+    }
+
+    /// Index into entries of the selected row, if it is a port row
+    fn selected_index(&self) -> Option<usize> {
+        let row = self.table_state.selected()?;
+        if self.tree_view {
+            match self.tree_rows.get(row)?.node {
+                TreeNode::Entry(idx) => Some(idx),
+                TreeNode::Process { .. } => None,
+            }
+        } else {
+            self.filtered.get(row).copied()
+        }
+    }
+
     fn selected_entry(&self) -> Option<&PortEntry> {
-        self.table_state
-            .selected()
-            .and_then(|i| self.filtered.get(i))
-            .map(|&idx| &self.entries[idx])
+        self.selected_index().map(|idx| &self.entries[idx])
     }
 
     fn move_selection(&mut self, delta: i32) {
-        if self.filtered.is_empty() {
+        let rows = self.row_count();
+        if rows == 0 {
             return;
         }
         let current = self.table_state.selected().unwrap_or(0) as i32;
-        let new = (current + delta).clamp(0, self.filtered.len() as i32 - 1) as usize;
+        let new = (current + delta).clamp(0, rows as i32 - 1) as usize;
         self.table_state.select(Some(new));
     }
 }
 
-pub fn run_tui() -> Result<()> {
+pub fn run_tui(opts: &TuiOptions) -> Result<()> {
+    // Load key bindings first so config errors print on a normal screen
+    let config = Config::load()?;
+    let keymap = KeyMap::from_config(&config.keys)?;
+    let asn = opts.geoip.map(AsnDb::load).transpose()?;
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -143,7 +714,12 @@ pub fn run_tui() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
+    let mut app = App::new(keymap, config, opts);
+    app.asn = asn;
+    if let Some(e) = app.notes.load_error() {
+        let msg = format!("Notes not loaded: {}; new notes won't be saved", e);
+        app.notify(MsgKind::Error, msg);
+    }
     app.refresh();
 
     let mut last_refresh = Instant::now();
@@ -151,20 +727,37 @@ pub fn run_tui() -> Result<()> {
     loop {
         terminal.draw(|f| draw(f, &mut app))?;
 
-        // Poll for events with timeout for auto-refresh
-        let timeout = REFRESH_INTERVAL
+        // Poll for events with timeout for auto-refresh; re-scan faster and keep
+        // the spinner moving while a kill is pending
+        let interval = if app.pending_kills.is_empty() {
+            app.refresh_interval
+        } else {
+            KILL_POLL_INTERVAL
+        };
+        let mut timeout = interval
             .checked_sub(last_refresh.elapsed())
             .unwrap_or(Duration::ZERO);
+        if !app.pending_kills.is_empty() || app.restart_job.is_some() {
+            timeout = timeout.min(SPINNER_TICK);
+        }
 
         if event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
+                app.note_input();
                 handle_key(&mut app, key);
             }
         }
 
+        if let Some(line) = app.pending_print.take() {
+            print_to_scrollback(&mut terminal, &line)?;
+        }
+        app.poll_restart();
+
         // Auto-refresh
-        if last_refresh.elapsed() >= REFRESH_INTERVAL {
+        if last_refresh.elapsed() >= interval {
+            let before = app.fingerprint;
             app.refresh();
+            app.note_scan(app.fingerprint != before);
             last_refresh = Instant::now();
         }
 
@@ -173,6 +766,11 @@ pub fn run_tui() -> Result<()> {
         }
     }
 
+    // Keep last-seen times current; nothing to report to once the TUI is gone
+    if let Some(history) = &mut app.history {
+        let _ = history.save(true);
+    }
+
     // Restore terminal
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
@@ -181,115 +779,217 @@ pub fn run_tui() -> Result<()> {
     Ok(())
 }
 
+/// Briefly leave the alternate screen to print a line where it stays in the
+/// terminal's scrollback (handy over SSH, where there's no clipboard).
+fn print_to_scrollback(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    line: &str,
+) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    println!("{}", line);
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    enable_raw_mode()?;
+    terminal.clear()?;
+    Ok(())
+}
+
+/// One-line summary of an entry for pasting into chat or issues
+fn share_line(e: &PortEntry) -> String {
+    format!(
+        "{} {} {} {} {}",
+        e.port,
+        e.protocol,
+        e.process_name,
+        e.pid,
+        e.known_service.unwrap_or("-")
+    )
+}
+
+/// What makes two scans "the same" for the refresh fast path
+fn entry_key(e: &PortEntry) -> impl Hash + Eq {
+    (
+        e.protocol,
+        e.local_addr,
+        e.port,
+        e.pid,
+        e.remote,
+        e.state,
+        e.process_name.clone(),
+    )
+}
+
+/// Order-independent hash of the scan's sockets and owners
+fn fingerprint(entries: &[PortEntry]) -> u64 {
+    entries.iter().fold(0u64, |acc, e| {
+        let mut hasher = DefaultHasher::new();
+        entry_key(e).hash(&mut hasher);
+        acc.wrapping_add(hasher.finish())
+    })
+}
+
 fn handle_key(app: &mut App, key: KeyEvent) {
     // Kill confirmation dialog takes priority
-    if let Some(idx) = app.confirm_kill {
-        match key.code {
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                let entry = &app.entries[app.filtered[idx]];
-                let pid = entry.pid;
-                let name = entry.process_name.clone();
-                let port = entry.port;
-                match kill_process(pid, false) {
-                    Ok(()) => {
-                        app.status_msg = Some((
-                            format!("Killed {} (PID {}) on port {}", name, pid, port),
-                            Instant::now(),
-                        ));
-                        app.refresh();
-                    }
-                    Err(e) => {
-                        app.status_msg = Some((format!("Kill failed: {}", e), Instant::now()));
-                    }
+    if let Some(target) = app.confirm_kill.take() {
+        if app.protect.covers(&target) {
+            // Protected entries need the port number typed out
+            match key.code {
+                KeyCode::Char(c) if c.is_ascii_digit() && app.confirm_input.len() < 5 => {
+                    app.confirm_input.push(c);
+                    app.confirm_kill = Some(target);
+                    return;
                 }
-                app.confirm_kill = None;
-            }
-            _ => {
-                app.confirm_kill = None;
+                KeyCode::Backspace => {
+                    app.confirm_input.pop();
+                    app.confirm_kill = Some(target);
+                    return;
+                }
+                KeyCode::Enter if app.confirm_input == target.port.to_string() => {
+                    kill_confirmed(app, &target);
+                }
+                _ => {}
             }
+        } else if let KeyCode::Char('y') | KeyCode::Char('Y') = key.code {
+            kill_confirmed(app, &target);
+        }
+        app.confirm_force = false;
+        app.confirm_tree.clear();
+        app.confirm_input.clear();
+        return;
+    }
+
+    // Batch kill of the marked processes: y confirms, anything else cancels
+    if !app.confirm_marked.is_empty() {
+        let pids = std::mem::take(&mut app.confirm_marked);
+        if let KeyCode::Char('y') | KeyCode::Char('Y') = key.code {
+            kill_marked(app, &pids, app.confirm_force);
+        }
+        app.confirm_force = false;
+        return;
+    }
+
+    // Restart confirmation: y runs it in the background, anything else cancels
+    if let Some((entry, launch)) = app.confirm_restart.take() {
+        if let KeyCode::Char('y') | KeyCode::Char('Y') = key.code {
+            let (tx, rx) = mpsc::channel();
+            let job_entry = entry.clone();
+            std::thread::spawn(move || {
+                let _ = tx.send(restart::restart(&job_entry, &launch));
+            });
+            app.notify(
+                MsgKind::Info,
+                format!("Restarting {} on port {}…", entry.process_name, entry.port),
+            );
+            app.restart_job = Some((entry, rx));
         }
         return;
     }
 
-    // Ctrl+key shortcuts (work in ALL modes: normal + filter)
-    if key.modifiers.contains(KeyModifiers::CONTROL) {
+    // Export format: c or j writes the file, anything else cancels
+    if app.export_prompt {
+        app.export_prompt = false;
         match key.code {
-            KeyCode::Char('c') | KeyCode::Char('q') => {
-                app.should_quit = true;
-                return;
-            }
-            KeyCode::Char('x') => {
-                // Kill with confirmation
-                if let Some(selected) = app.table_state.selected() {
-                    if selected < app.filtered.len() {
-                        app.confirm_kill = Some(selected);
-                    }
-                }
-                return;
-            }
-            KeyCode::Char('k') => {
-                // Force kill (SIGKILL), no confirmation
-                if let Some(selected) = app.table_state.selected() {
-                    if selected < app.filtered.len() {
-                        let entry = &app.entries[app.filtered[selected]];
-                        let pid = entry.pid;
-                        let name = entry.process_name.clone();
-                        let port = entry.port;
-                        match kill_process(pid, true) {
-                            Ok(()) => {
-                                app.status_msg = Some((
-                                    format!("Force killed {} (PID {}) on port {}", name, pid, port),
-                                    Instant::now(),
-                                ));
-                                app.refresh();
-                            }
-                            Err(e) => {
-                                app.status_msg =
-                                    Some((format!("Kill failed: {}", e), Instant::now()));
-                            }
-                        }
-                    }
-                }
-                return;
+            KeyCode::Char('c') => export_view(app, ExportFormat::Csv),
+            KeyCode::Char('j') => export_view(app, ExportFormat::Json),
+            _ => {}
+        }
+        return;
+    }
+
+    // Message log and peek popup: any key closes them
+    if app.show_log || app.peek {
+        app.show_log = false;
+        app.peek = false;
+        return;
+    }
+
+    // Sort picker: arrows move, Enter picks, anything else closes it
+    if let Some(cursor) = app.sort_menu {
+        let last = SortField::ALL.len() - 1;
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => app.sort_menu = Some(cursor.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => app.sort_menu = Some((cursor + 1).min(last)),
+            KeyCode::Enter => {
+                app.sort_menu = None;
+                app.sort_field = SortField::ALL[cursor];
+                app.resort();
             }
-            KeyCode::Char('d') => {
-                app.show_detail = !app.show_detail;
-                return;
+            _ => app.sort_menu = None,
+        }
+        return;
+    }
+
+    // Command line: Enter runs it, Esc (or backspacing past the `:`) cancels
+    if let Some(input) = app.command_input.as_mut() {
+        match key.code {
+            KeyCode::Esc => app.command_input = None,
+            KeyCode::Enter => {
+                let line = app.command_input.take().unwrap_or_default();
+                run_command(app, &line);
             }
-            KeyCode::Char('s') => {
-                app.sort_field = app.sort_field.next();
-                app.sort_entries();
-                app.apply_filter();
-                return;
+            KeyCode::Backspace if input.is_empty() => app.command_input = None,
+            KeyCode::Backspace => {
+                input.pop();
             }
-            KeyCode::Char('t') => {
-                // Cycle: TCP+UDP → TCP only → UDP only → TCP+UDP
-                match (app.show_tcp, app.show_udp) {
-                    (true, true) => {
-                        app.show_udp = false;
-                    }
-                    (true, false) => {
-                        app.show_tcp = false;
-                        app.show_udp = true;
-                    }
-                    (false, true) => {
-                        app.show_tcp = true;
-                    }
-                    (false, false) => {
-                        app.show_tcp = true;
-                        app.show_udp = true;
-                    }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+        return;
+    }
+
+    // Note input: Enter saves (blank removes the note), Esc cancels
+    if let Some((_, text)) = app.note_input.as_mut() {
+        match key.code {
+            KeyCode::Esc => app.note_input = None,
+            KeyCode::Enter => {
+                if let Some((entry, text)) = app.note_input.take() {
+                    let (kind, msg) = match app.notes.set(&entry, &text) {
+                        Ok(()) if text.trim().is_empty() => (
+                            MsgKind::Info,
+                            format!("Removed the note on port {}", entry.port),
+                        ),
+                        Ok(()) => (
+                            MsgKind::Info,
+                            format!("Noted port {} ({})", entry.port, entry.process_name),
+                        ),
+                        Err(e) => (MsgKind::Error, format!("Couldn't save note: {:#}", e)),
+                    };
+                    app.notify(kind, msg);
                 }
-                app.refresh();
-                return;
             }
-            KeyCode::Char('r') => {
-                app.refresh();
-                app.status_msg = Some(("Refreshed".to_string(), Instant::now()));
-                return;
+            KeyCode::Backspace => {
+                text.pop();
             }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+        return;
+    }
+
+    // Zoomed in on a process: Esc (or the zoom key again) goes back to the list
+    if app.zoom.is_some() {
+        match app.keymap.action(&key) {
+            _ if key.code == KeyCode::Esc => app.zoom = None,
+            Some((Action::Zoom, _)) => app.zoom = None,
+            Some((action @ (Action::Quit | Action::Refresh), _)) => run_action(app, action),
             _ => {}
         }
+        return;
+    }
+
+    // Esc clears marks before it can quit
+    if key.code == KeyCode::Esc && !app.filter_active && !app.marked.is_empty() {
+        app.marked.clear();
+        return;
+    }
+
+    // Bound actions. Modified keys (e.g. Ctrl+X) work in ALL modes: normal + filter;
+    // plain keys only outside filter input so they can still be typed.
+    if let Some((action, combo)) = app.keymap.action(&key) {
+        if combo.is_modified() || !app.filter_active {
+            run_action(app, action);
+            return;
+        }
     }
 
     // Filter input mode
@@ -311,61 +1011,614 @@ fn handle_key(app: &mut App, key: KeyEvent) {
             }
             _ => {}
         }
-        return;
-    }
-
-    // Normal mode
-    match key.code {
-        KeyCode::Esc => app.should_quit = true,
-        KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
-        KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
-        KeyCode::Char('/') => {
-            app.filter_active = true;
-        }
-        _ => {}
     }
 }
 
-fn draw(f: &mut Frame, app: &mut App) {
-    let size = f.area();
-
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3), // header + filter
-            Constraint::Min(5),    // table
-            Constraint::Length(1), // status bar
-        ])
-        .split(size);
+#[derive(Clone, Copy)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
 
-    draw_header(f, app, chunks[0]);
+/// Write the rows in view, in their current order, to a timestamped file in
+/// the working directory, e.g. `kav-export-20250114-153012.csv`
+fn export_view(app: &mut App, format: ExportFormat) {
+    let entries: Vec<PortEntry> = app
+        .filtered
+        .iter()
+        .map(|&idx| app.entries[idx].clone())
+        .collect();
+    let mut buf = Vec::new();
+    let (written, ext) = match format {
+        ExportFormat::Csv => (write_csv(&mut buf, &entries).map_err(Into::into), "csv"),
+        ExportFormat::Json => (write_json(&mut buf, &entries), "json"),
+    };
+    let path = format!(
+        "kav-export-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        ext
+    );
+    match written.and_then(|()| std::fs::write(&path, buf).map_err(Into::into)) {
+        Ok(()) => app.notify(
+            MsgKind::Info,
+            format!(
+                "Exported {} row{} to {}",
+                entries.len(),
+                if entries.len() == 1 { "" } else { "s" },
+                path
+            ),
+        ),
+        Err(e) => app.notify(MsgKind::Error, format!("Export failed: {:#}", e)),
+    }
+}
 
-    if app.show_detail {
-        let detail_layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-            .split(chunks[1]);
-        draw_table(f, app, detail_layout[0]);
-        draw_detail(f, app, detail_layout[1]);
+/// Kill the entry the dialog opened on, if its process still holds the port.
+/// Refreshes keep running under the dialog, so the rows may have moved or
+/// gone since.
+fn kill_confirmed(app: &mut App, target: &PortEntry) {
+    if !app
+        .entries
+        .iter()
+        .any(|e| e.pid == target.pid && e.port == target.port)
+    {
+        let msg = format!(
+            "{} (PID {}) no longer holds port {}; nothing killed",
+            target.process_name, target.pid, target.port
+        );
+        app.notify(MsgKind::Error, msg);
+        return;
+    }
+    if app.confirm_tree.is_empty() {
+        kill_entry(app, target, app.confirm_force);
     } else {
-        draw_table(f, app, chunks[1]);
+        let pids = std::mem::take(&mut app.confirm_tree);
+        kill_tree(app, target, &pids);
     }
+}
 
-    draw_status_bar(f, app, chunks[2]);
-
-    // Kill confirmation overlay
-    if let Some(idx) = app.confirm_kill {
-        if let Some(&entry_idx) = app.filtered.get(idx) {
-            let entry = &app.entries[entry_idx];
-            draw_kill_confirm(f, entry);
+/// Kill `pids` (the entry's process and everything below it) children first,
+/// then report how many went and which PIDs didn't
+fn kill_tree(app: &mut App, entry: &PortEntry, pids: &[u32]) {
+    let name = &entry.process_name;
+    let pid = entry.pid;
+    let mut failed = Vec::new();
+    for &target in pids {
+        match kill_process(target, false) {
+            Ok(()) => {
+                app.track_kill(target);
+            }
+            Err(_) => failed.push(format!("PID {}", target)),
         }
     }
+    let kind = if failed.is_empty() {
+        MsgKind::Info
+    } else {
+        MsgKind::Error
+    };
+    let msg = if failed.is_empty() {
+        format!(
+            "Killed {} (PID {}) and {} child process{}",
+            name,
+            pid,
+            pids.len() - 1,
+            if pids.len() == 2 { "" } else { "es" }
+        )
+    } else {
+        format!(
+            "Killed {} of {} processes under {}; failed: {}",
+            pids.len() - failed.len(),
+            pids.len(),
+            name,
+            failed.join(", ")
+        )
+    };
+    app.notify(kind, msg);
+    app.refresh();
 }
 
-fn draw_header(f: &mut Frame, app: &App, area: Rect) {
-    let t = theme();
+/// Kill every marked process, skipping ones holding a protected port (those
+/// need the typed confirmation one at a time), then report a tally
+fn kill_marked(app: &mut App, pids: &[u32], force: bool) {
+    let mut killed = 0;
+    let mut protected = 0;
+    let mut failed = Vec::new();
+    for &pid in pids {
+        let owned = |e: &&PortEntry| e.pid == pid;
+        if app
+            .entries
+            .iter()
+            .filter(owned)
+            .any(|e| app.protect.covers(e))
+        {
+            protected += 1;
+            continue;
+        }
+        match kill_process(pid, force) {
+            Ok(()) => {
+                killed += 1;
+                app.track_kill(pid);
+            }
+            Err(_) => failed.push(format!("PID {}", pid)),
+        }
+    }
+    app.marked.clear();
 
-    let tcp_label = if app.show_tcp { "TCP ✓" } else { "TCP ✗" };
+    let mut msg = format!(
+        "{} {} of {} marked process{}",
+        if force { "Force killed" } else { "Killed" },
+        killed,
+        pids.len(),
+        if pids.len() == 1 { "" } else { "es" }
+    );
+    if protected > 0 {
+        msg.push_str(&format!("; skipped {} protected", protected));
+    }
+    let mut kind = MsgKind::Info;
+    if !failed.is_empty() {
+        msg.push_str(&format!("; failed: {}", failed.join(", ")));
+        kind = MsgKind::Error;
+    }
+    app.notify(kind, msg);
+    app.refresh();
+}
+
+fn kill_entry(app: &mut App, entry: &PortEntry, force: bool) {
+    let pid = entry.pid;
+    let name = &entry.process_name;
+    let port = entry.port;
+    match kill_process(pid, force) {
+        Ok(()) => {
+            app.track_kill(pid);
+            app.notify(
+                MsgKind::Info,
+                format!(
+                    "{} {} (PID {}) on port {}",
+                    if force { "Force killed" } else { "Killed" },
+                    name,
+                    pid,
+                    port
+                ),
+            );
+            app.refresh();
+        }
+        Err(e) => {
+            app.notify(MsgKind::Error, format!("Kill failed: {}", e));
+        }
+    }
+}
+
+fn run_action(app: &mut App, action: Action) {
+    match action {
+        Action::Quit => app.should_quit = true,
+        Action::Kill | Action::ForceKill | Action::KillTree | Action::Restart if app.read_only => {
+            app.notify(
+                MsgKind::Error,
+                format!("Read-only mode: {} is disabled", action.name()),
+            );
+        }
+        Action::Kill | Action::ForceKill if !app.marked.is_empty() => {
+            // Marked processes are killed together, after one confirmation
+            let mut pids: Vec<u32> = app.marked.iter().copied().collect();
+            pids.sort_unstable();
+            app.confirm_marked = pids;
+            app.confirm_force = action == Action::ForceKill;
+        }
+        Action::Kill => {
+            // Kill with confirmation
+            app.confirm_kill = app.selected_entry().cloned();
+            app.confirm_force = false;
+        }
+        Action::KillTree => {
+            // Kill the process and everything it started, with confirmation
+            if let Some(idx) = app.selected_index().filter(|&i| app.entries[i].pid != 0) {
+                let e = &app.entries[idx];
+                let tree = subtree(e.pid, &process_parents(), std::process::id());
+                if tree.is_empty() {
+                    let msg = format!(
+                        "Not killing the tree of {} (PID {}): it is init or runs kav itself",
+                        e.process_name, e.pid
+                    );
+                    app.notify(MsgKind::Error, msg);
+                    return;
+                }
+                app.confirm_tree = tree;
+                app.confirm_kill = Some(e.clone());
+                app.confirm_force = false;
+            }
+        }
+        Action::ForceKill => {
+            // Force kill (SIGKILL), confirmed first unless turned off; protected
+            // entries always need the typed port
+            if let Some(e) = app.selected_entry().cloned() {
+                if app.protect.confirm_force_kill || app.protect.covers(&e) {
+                    app.confirm_kill = Some(e);
+                    app.confirm_force = true;
+                } else {
+                    kill_entry(app, &e, true);
+                }
+            }
+        }
+        Action::Restart => {
+            let Some(e) = app.selected_entry() else {
+                return;
+            };
+            let msg = if app.restart_job.is_some() {
+                "A restart is already running".to_string()
+            } else if app.protect.covers(e) {
+                format!(
+                    "Port {} is protected; restart it with `kav restart {}`",
+                    e.port, e.port
+                )
+            } else {
+                match Launch::capture(e.pid) {
+                    Ok(launch) => {
+                        app.confirm_restart = Some((e.clone(), launch));
+                        return;
+                    }
+                    Err(err) => format!("Restart failed: {:#}", err),
+                }
+            };
+            app.notify(MsgKind::Error, msg);
+        }
+        Action::PrintSelected => {
+            if let Some(e) = app.selected_entry() {
+                let line = share_line(e);
+                app.notify(
+                    MsgKind::Info,
+                    format!("Printed port {} to the terminal scrollback", e.port),
+                );
+                app.pending_print = Some(line);
+            }
+        }
+        Action::Ignore => {
+            if let Some(e) = app.selected_entry() {
+                let rule = IgnoreRule {
+                    port: e.port,
+                    process: e.process_name.clone(),
+                };
+                let (kind, msg) = match add_ignore(&rule) {
+                    Ok(path) => {
+                        app.ignore.push(rule.clone());
+                        app.apply_filter();
+                        let msg = format!(
+                            "Ignoring {} (saved to {}){}",
+                            rule,
+                            path.display(),
+                            if app.show_ignored {
+                                "; still shown with --show-ignored"
+                            } else {
+                                ""
+                            }
+                        );
+                        (MsgKind::Info, msg)
+                    }
+                    Err(e) => (
+                        MsgKind::Error,
+                        format!("Couldn't save ignore rule: {:#}", e),
+                    ),
+                };
+                app.notify(kind, msg);
+            }
+        }
+        Action::Note => {
+            if let Some(e) = app.selected_entry() {
+                let text = app.notes.get(e).unwrap_or_default().to_string();
+                app.note_input = Some((e.clone(), text));
+            }
+        }
+        Action::Mark => {
+            if let Some(pid) = app.selected_entry().map(|e| e.pid).filter(|&p| p != 0) {
+                if !app.marked.remove(&pid) {
+                    app.marked.insert(pid);
+                }
+                app.move_selection(1);
+            }
+        }
+        Action::MarkAll => app.mark_all_visible(),
+        Action::InvertMarks => app.invert_marks(),
+        Action::ToggleDetail => {
+            app.show_detail = !app.show_detail;
+        }
+        Action::ToggleGauges => {
+            app.show_gauges = !app.show_gauges;
+        }
+        Action::MessageLog => {
+            app.show_log = true;
+        }
+        Action::Export => {
+            app.export_prompt = true;
+        }
+        Action::Peek => {
+            app.peek = app.selected_entry().is_some();
+        }
+        Action::Zoom => {
+            if let Some(pid) = app.selected_entry().map(|e| e.pid).filter(|&p| p != 0) {
+                let (user, cwd) = process_user_and_cwd(pid);
+                app.zoom = Some(Zoom { pid, user, cwd });
+            }
+        }
+        Action::ToggleCommand => {
+            app.show_cmd_col = !app.show_cmd_col;
+        }
+        Action::ToggleTree => {
+            // Rows are laid out differently, so find the entry before switching
+            let selected = app.selected_key();
+            app.tree_view = !app.tree_view;
+            app.fingerprint = None;
+            app.refresh();
+            if let Some((pid, port)) = selected {
+                app.reselect(pid, port);
+            }
+        }
+        Action::HideLoopback => {
+            app.hide_loopback = !app.hide_loopback;
+            app.apply_filter();
+        }
+        Action::ToggleKillable => {
+            app.killable_only = !app.killable_only;
+            if app.killable_only && app.my_uid.is_none() {
+                app.notify(
+                    MsgKind::Info,
+                    "Process owners can't be read here; every entry counts as killable".to_string(),
+                );
+            }
+            app.apply_filter();
+        }
+        Action::UnknownOnly => {
+            app.unknown_only = !app.unknown_only;
+            app.apply_filter();
+        }
+        Action::ToggleEstablished => {
+            app.show_established = !app.show_established;
+            app.refresh();
+        }
+        Action::CycleSort => {
+            app.sort_field = app.sort_field.next();
+            app.resort();
+        }
+        Action::SortMenu => {
+            app.sort_menu = SortField::ALL.iter().position(|&f| f == app.sort_field);
+        }
+        Action::CycleProtocol => {
+            // Cycle: TCP+UDP → TCP only → UDP only → TCP+UDP
+            match (app.show_tcp, app.show_udp) {
+                (true, true) => {
+                    app.show_udp = false;
+                }
+                (true, false) => {
+                    app.show_tcp = false;
+                    app.show_udp = true;
+                }
+                (false, true) => {
+                    app.show_tcp = true;
+                }
+                (false, false) => {
+                    app.show_tcp = true;
+                    app.show_udp = true;
+                }
+            }
+            app.refresh();
+        }
+        Action::Refresh => {
+            app.refresh();
+            app.notify(MsgKind::Info, "Refreshed".to_string());
+        }
+        Action::Faster => app.change_rate(true),
+        Action::Slower => app.change_rate(false),
+        Action::Filter => {
+            app.filter_active = true;
+        }
+        Action::Command => {
+            app.command_input = Some(String::new());
+        }
+        Action::Follow => {
+            if let Some(pid) = app.follow_pid.take() {
+                app.notify(MsgKind::Info, format!("Stopped following PID {}", pid));
+            } else if let Some(pid) = app.selected_entry().map(|e| e.pid).filter(|&p| p != 0) {
+                app.follow_pid = Some(pid);
+                app.notify(MsgKind::Info, format!("Following PID {}", pid));
+            }
+        }
+        Action::Up | Action::Down => {
+            // Moving by hand releases the follow lock
+            if let Some(pid) = app.follow_pid.take() {
+                app.notify(MsgKind::Info, format!("Stopped following PID {}", pid));
+            }
+            app.move_selection(if action == Action::Up { -1 } else { 1 });
+        }
+    }
+}
+
+const COMMAND_HELP: &str =
+    ":sort <field>  :filter [text]  :protocol tcp|udp|both  :<action name>  :quit";
+
+/// Run a line typed at the `:` prompt. Besides the commands that take an
+/// argument, any keymap action name works (e.g. `:kill`, `:tree`).
+fn run_command(app: &mut App, line: &str) {
+    let line = line.trim();
+    let (cmd, arg) = line
+        .split_once(char::is_whitespace)
+        .map(|(c, a)| (c, a.trim()))
+        .unwrap_or((line, ""));
+    let fail = |app: &mut App, msg: String| app.notify(MsgKind::Error, msg);
+
+    match cmd {
+        "" => {}
+        "q" => app.should_quit = true,
+        "help" => app.notify(MsgKind::Info, COMMAND_HELP),
+        "sort" => match <SortField as clap::ValueEnum>::from_str(arg, true) {
+            Ok(field) => {
+                app.sort_field = field;
+                app.resort();
+            }
+            Err(_) => fail(
+                app,
+                format!(
+                    "Unknown sort field '{}' (port, name, cpu, mem, mem-pct, uptime)",
+                    arg
+                ),
+            ),
+        },
+        "filter" => {
+            app.filter_text = arg.to_string();
+            app.apply_filter();
+        }
+        "protocol" | "proto" => {
+            let (tcp, udp) = match arg.to_lowercase().as_str() {
+                "tcp" => (true, false),
+                "udp" => (false, true),
+                "both" | "all" => (true, true),
+                _ => {
+                    fail(app, format!("Unknown protocol '{}' (tcp, udp, both)", arg));
+                    return;
+                }
+            };
+            app.show_tcp = tcp;
+            app.show_udp = udp;
+            app.refresh();
+        }
+        _ => match Action::from_name(cmd) {
+            Some(action) if arg.is_empty() => run_action(app, action),
+            Some(_) => fail(app, format!(":{} takes no arguments", cmd)),
+            None => fail(app, format!("Unknown command ':{}' (try :help)", cmd)),
+        },
+    }
+}
+
+fn draw(f: &mut Frame, app: &mut App) {
+    let size = f.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // header + filter
+            Constraint::Min(5),    // table
+            Constraint::Length(1), // category counts
+            Constraint::Length(1), // status bar
+        ])
+        .split(size);
+
+    draw_header(f, app, chunks[0]);
+
+    let mut body = chunks[1];
+    if let (true, Some(usage)) = (app.show_gauges, app.usage) {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(4)])
+            .split(body);
+        draw_gauges(f, usage, split[0]);
+        body = split[1];
+    }
+
+    if let Some(zoom) = &app.zoom {
+        draw_zoom(f, app, zoom, body);
+    } else if app.show_detail {
+        let detail_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(body);
+        draw_table(f, app, detail_layout[0]);
+        draw_detail(f, app, detail_layout[1]);
+    } else {
+        draw_table(f, app, body);
+    }
+
+    draw_category_counts(f, app, chunks[2]);
+    draw_status_bar(f, app, chunks[3]);
+
+    // Kill confirmation overlay
+    if let Some(entry) = &app.confirm_kill {
+        let typed = app
+            .protect
+            .covers(entry)
+            .then_some(app.confirm_input.as_str());
+        draw_kill_confirm(
+            f,
+            entry,
+            typed,
+            app.confirm_tree.len().saturating_sub(1),
+            app.confirm_force,
+        );
+    }
+
+    if !app.confirm_marked.is_empty() {
+        let protected = app
+            .confirm_marked
+            .iter()
+            .filter(|&&pid| {
+                app.entries
+                    .iter()
+                    .any(|e| e.pid == pid && app.protect.covers(e))
+            })
+            .count();
+        draw_batch_confirm(f, app.confirm_marked.len(), protected, app.confirm_force);
+    }
+
+    if let Some((entry, launch)) = &app.confirm_restart {
+        draw_restart_confirm(f, entry, launch);
+    }
+
+    if let Some(cursor) = app.sort_menu {
+        draw_sort_menu(f, app.sort_field, cursor);
+    }
+
+    if app.show_log {
+        draw_message_log(f, &app.log);
+    }
+}
+
+/// Whole-system CPU and memory, side by side, so a busy process can be
+/// judged against the machine
+fn draw_gauges(f: &mut Frame, usage: SystemUsage, area: Rect) {
+    let t = theme();
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let mem_ratio = if usage.total_memory > 0 {
+        usage.used_memory as f64 / usage.total_memory as f64
+    } else {
+        0.0
+    };
+    let gib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0 * 1024.0);
+    let gauges = [
+        (
+            (usage.cpu_percent as f64 / 100.0).clamp(0.0, 1.0),
+            format!("CPU {:.0}%", usage.cpu_percent),
+        ),
+        (
+            mem_ratio.clamp(0.0, 1.0),
+            format!(
+                "MEM {:.1} / {:.1} GB",
+                gib(usage.used_memory),
+                gib(usage.total_memory)
+            ),
+        ),
+    ];
+    for ((ratio, label), area) in gauges.into_iter().zip(halves.iter()) {
+        let color = if ratio >= GAUGE_CRITICAL {
+            t.error
+        } else if ratio >= GAUGE_WARNING {
+            t.warning
+        } else {
+            t.success
+        };
+        let gauge = Gauge::default()
+            .ratio(ratio)
+            .label(Span::styled(
+                label,
+                Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+            ))
+            .gauge_style(Style::default().fg(color).bg(t.bg_elevated));
+        f.render_widget(gauge, *area);
+    }
+}
+
+fn draw_header(f: &mut Frame, app: &App, area: Rect) {
+    let t = theme();
+
+    let tcp_label = if app.show_tcp { "TCP ✓" } else { "TCP ✗" };
     let udp_label = if app.show_udp { "UDP ✓" } else { "UDP ✗" };
     let filter_indicator = if app.filter_active { "▌" } else { "" };
 
@@ -402,15 +1655,111 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
             format!("  {} ports", app.filtered.len()),
             Style::default().fg(t.text_secondary),
         ),
+        Span::styled(
+            app.total_sockets
+                .map(|n| format!(" · {} total sockets", n))
+                .unwrap_or_default(),
+            Style::default().fg(t.text_muted),
+        ),
+        Span::styled(
+            if app.hidden_udp > 0 {
+                format!(" · {} UDP hidden", app.hidden_udp)
+            } else {
+                String::new()
+            },
+            Style::default().fg(t.text_muted),
+        ),
         Span::styled(
             format!("  Sort: {}", app.sort_field.label()),
             Style::default().fg(t.text_muted),
         ),
+        Span::styled(
+            if app.tree_view { "  Tree" } else { "" },
+            Style::default().fg(t.info),
+        ),
+        Span::styled(
+            if app.show_established { "  +Conns" } else { "" },
+            Style::default().fg(t.info),
+        ),
+        Span::styled(
+            if app.hide_loopback {
+                "  Exposed only"
+            } else {
+                ""
+            },
+            Style::default().fg(t.info),
+        ),
+        Span::styled(
+            if app.killable_only {
+                "  Killable only"
+            } else {
+                ""
+            },
+            Style::default().fg(t.info),
+        ),
+        Span::styled(
+            if app.unknown_only {
+                "  Unknown only"
+            } else {
+                ""
+            },
+            Style::default().fg(t.info),
+        ),
+        Span::styled(
+            app.follow_pid
+                .map(|pid| format!("  ⌖ PID {}", pid))
+                .unwrap_or_default(),
+            Style::default().fg(t.info),
+        ),
+        Span::styled(
+            if app.marked.is_empty() {
+                String::new()
+            } else {
+                format!("  ● {} marked", app.marked.len())
+            },
+            Style::default().fg(t.primary),
+        ),
+        Span::styled(
+            if app.refresh_interval > app.base_refresh {
+                format!("  idle: every {}", interval_display(app.refresh_interval))
+            } else if app.base_refresh != REFRESH_INTERVAL {
+                format!("  every {}", interval_display(app.base_refresh))
+            } else {
+                String::new()
+            },
+            Style::default().fg(t.text_muted),
+        ),
+        Span::styled(
+            if app.stale { "  ⟳ stale" } else { "" },
+            Style::default().fg(t.warning),
+        ),
+        Span::styled(
+            if app.ignored > 0 {
+                format!("  {} ignored", app.ignored)
+            } else {
+                String::new()
+            },
+            Style::default().fg(t.text_muted),
+        ),
+        Span::styled(
+            if app.unresolved > 0 {
+                format!("  ⚠ {} unresolved", app.unresolved)
+            } else {
+                String::new()
+            },
+            Style::default().fg(t.warning),
+        ),
+        Span::styled(
+            if app.underprivileged {
+                "  · run with sudo for complete results"
+            } else {
+                ""
+            },
+            Style::default().fg(t.warning),
+        ),
     ]);
 
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(t.border));
+    let block = bordered().border_style(Style::default().fg(t.border));
 
     let paragraph = Paragraph::new(header).block(block);
     f.render_widget(paragraph, area);
@@ -418,13 +1767,38 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
 
 fn draw_table(f: &mut Frame, app: &mut App, area: Rect) {
     let t = theme();
+    // Same lowercasing as Filter::text, so highlights line up with what matched
+    let query = app.filter_text.to_lowercase();
+    // Memory bars are relative to the biggest entry on screen
+    let heat = (!app.ascii).then(|| {
+        app.filtered
+            .iter()
+            .map(|&idx| app.entries[idx].memory_mb)
+            .fold(1.0, f64::max)
+    });
+    let bar = if heat.is_some() { 2 } else { 0 };
 
+    let process_header = if app.show_cmd_col {
+        "COMMAND"
+    } else {
+        "PROCESS"
+    };
     let header_cells = [
-        "PORT", "PROTO", "PROCESS", "SERVICE", "PID", "CPU", "MEM", "UPTIME",
+        "",
+        "PORT",
+        "PROTO",
+        "STATE",
+        process_header,
+        "SERVICE",
+        "PID",
+        "CPU",
+        "MEM",
+        "MEM%",
+        "UPTIME",
     ]
-    .iter()
+    .into_iter()
     .map(|h| {
-        Cell::from(*h).style(
+        Cell::from(h).style(
             Style::default()
                 .fg(t.text_secondary)
                 .add_modifier(Modifier::BOLD),
@@ -432,52 +1806,75 @@ fn draw_table(f: &mut Frame, app: &mut App, area: Rect) {
     });
     let header = Row::new(header_cells).height(1);
 
-    let rows: Vec<Row> = app
-        .filtered
-        .iter()
-        .map(|&idx| {
-            let e = &app.entries[idx];
-            let service_text = e.known_service.unwrap_or("—");
-            let cat_color = t.category_color(e.category);
-            let cpu_color = if e.cpu_percent > 50.0 {
-                t.error
-            } else if e.cpu_percent > 20.0 {
-                t.warning
-            } else {
-                t.text
-            };
-
-            Row::new(vec![
-                Cell::from(e.port.to_string()).style(Style::default().fg(t.text)),
-                Cell::from(e.protocol.to_string()).style(Style::default().fg(t.text_secondary)),
-                Cell::from(e.process_name.clone()).style(Style::default().fg(cat_color)),
-                Cell::from(service_text).style(Style::default().fg(cat_color)),
-                Cell::from(e.pid.to_string()).style(Style::default().fg(t.text_muted)),
-                Cell::from(format!("{:.1}%", e.cpu_percent)).style(Style::default().fg(cpu_color)),
-                Cell::from(e.memory_display()).style(Style::default().fg(t.text)),
-                Cell::from(e.uptime_display()).style(Style::default().fg(t.text_muted)),
-            ])
-        })
-        .collect();
+    let rows: Vec<Row> = if app.tree_view {
+        app.tree_rows
+            .iter()
+            .map(|row| {
+                let indent = "  ".repeat(row.depth);
+                match &row.node {
+                    TreeNode::Process { pid, name } => Row::new(vec![
+                        Cell::from(""),
+                        Cell::from(""),
+                        Cell::from(""),
+                        Cell::from(""),
+                        Cell::from(format!("{}{}", indent, name)).style(
+                            Style::default()
+                                .fg(t.text_secondary)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Cell::from(""),
+                        Cell::from(pid.to_string()).style(Style::default().fg(t.text_muted)),
+                    ]),
+                    TreeNode::Entry(idx) => {
+                        let e = &app.entries[*idx];
+                        entry_row(
+                            e,
+                            app.process_text(&format!("{}└ ", indent), e),
+                            gutter(app, e),
+                            app.pending_kills.get(&e.pid).map(|k| k.since),
+                            &query,
+                            app.port_badge(e),
+                            heat,
+                        )
+                    }
+                }
+            })
+            .collect()
+    } else {
+        app.filtered
+            .iter()
+            .map(|&idx| {
+                let e = &app.entries[idx];
+                entry_row(
+                    e,
+                    app.process_text("", e),
+                    gutter(app, e),
+                    app.pending_kills.get(&e.pid).map(|k| k.since),
+                    &query,
+                    app.port_badge(e),
+                    heat,
+                )
+            })
+            .collect()
+    };
 
     let widths = [
-        Constraint::Length(7),  // PORT
-        Constraint::Length(6),  // PROTO
-        Constraint::Length(14), // PROCESS
-        Constraint::Length(16), // SERVICE
-        Constraint::Length(7),  // PID
-        Constraint::Length(7),  // CPU
-        Constraint::Length(9),  // MEM
-        Constraint::Length(8),  // UPTIME
+        Constraint::Length(1),                 // mark gutter
+        Constraint::Length(7),                 // PORT
+        Constraint::Length(6),                 // PROTO
+        Constraint::Length(10),                // STATE
+        Constraint::Length(app.process_width), // PROCESS
+        Constraint::Length(16),                // SERVICE
+        Constraint::Length(7),                 // PID
+        Constraint::Length(7 + bar),           // CPU
+        Constraint::Length(9 + bar),           // MEM
+        Constraint::Length(6),                 // MEM%
+        Constraint::Length(14),                // UPTIME
     ];
 
     let table = Table::new(rows, widths)
         .header(header)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(t.border)),
-        )
+        .block(bordered().border_style(Style::default().fg(t.border)))
         .row_highlight_style(
             Style::default()
                 .bg(t.selection_bg)
@@ -486,43 +1883,564 @@ fn draw_table(f: &mut Frame, app: &mut App, area: Rect) {
         );
 
     f.render_stateful_widget(table, area, &mut app.table_state);
+    draw_scrollbar(f, app, area);
+
+    if let (true, Some(row)) = (app.peek, app.table_state.selected()) {
+        if let Some(e) = app.selected_entry() {
+            // Borders and the header row sit above the first visible row
+            let row_y = area.y + 2 + row.saturating_sub(app.table_state.offset()) as u16;
+            draw_peek(f, e, area, row_y);
+        }
+    }
+
+    if app.filtered.is_empty() {
+        draw_empty_state(f, app, area);
+    }
 }
 
-fn draw_detail(f: &mut Frame, app: &App, area: Rect) {
+/// The selected row's process, service and command in full, in a box sized
+/// to fit them, just under the row (or over it near the bottom)
+fn draw_peek(f: &mut Frame, e: &PortEntry, table: Rect, row_y: u16) {
     let t = theme();
+    let fields = [
+        ("Process", e.process_name.clone()),
+        (
+            "Service",
+            e.service_label().unwrap_or_else(|| "—".to_string()),
+        ),
+        (
+            "Command",
+            if e.process_cmd.is_empty() {
+                "—".to_string()
+            } else {
+                e.process_cmd.clone()
+            },
+        ),
+    ];
+    // "  Process  " before each value, one space after it, and the borders
+    const LABEL: usize = 11;
+    let widest = fields
+        .iter()
+        .map(|(_, value)| LABEL + value.chars().count() + 1)
+        .max()
+        .unwrap_or(0);
+    let width = (widest as u16 + 2)
+        .min(table.width.saturating_sub(2))
+        .max(20);
+    let inner = width.saturating_sub(2).max(1) as usize;
+    let height = fields
+        .iter()
+        .map(|(_, value)| (LABEL + value.chars().count()).div_ceil(inner) as u16)
+        .sum::<u16>()
+        + 2;
 
-    let content = if let Some(entry) = app.selected_entry() {
-        vec![
-            Line::from(vec![
-                Span::styled("Port: ", Style::default().fg(t.text_secondary)),
-                Span::styled(
-                    format!("{} ({})", entry.port, entry.protocol),
-                    Style::default().fg(t.text).add_modifier(Modifier::BOLD),
-                ),
-            ]),
-            Line::from(vec![
-                Span::styled("Address: ", Style::default().fg(t.text_secondary)),
-                Span::styled(entry.addr_display(), Style::default().fg(t.text)),
-            ]),
-            Line::from(vec![
-                Span::styled("Process: ", Style::default().fg(t.text_secondary)),
-                Span::styled(
-                    &entry.process_name,
-                    Style::default().fg(t.category_color(entry.category)),
-                ),
-            ]),
-            Line::from(vec![
-                Span::styled("PID: ", Style::default().fg(t.text_secondary)),
-                Span::styled(entry.pid.to_string(), Style::default().fg(t.text)),
-            ]),
+    let frame = f.area();
+    let height = height.min(frame.height);
+    let y = if row_y + 1 + height <= frame.bottom() {
+        row_y + 1
+    } else {
+        row_y.saturating_sub(height)
+    };
+    let popup = Rect::new(table.x + 1, y, width, height).intersection(frame);
+
+    f.render_widget(Clear, popup);
+    let text: Vec<Line> = fields
+        .into_iter()
+        .map(|(label, value)| {
             Line::from(vec![
-                Span::styled("Service: ", Style::default().fg(t.text_secondary)),
                 Span::styled(
-                    entry.known_service.unwrap_or("Unknown"),
-                    Style::default().fg(t.category_color(entry.category)),
+                    format!("  {:<8} ", label),
+                    Style::default().fg(t.text_muted),
                 ),
-            ]),
-            Line::from(""),
+                Span::styled(value, Style::default().fg(t.text)),
+            ])
+        })
+        .collect();
+    let block = bordered().border_style(Style::default().fg(t.border_focus));
+    f.render_widget(
+        Paragraph::new(text).block(block).wrap(Wrap { trim: false }),
+        popup,
+    );
+}
+
+/// A box in the configured border style
+fn bordered() -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_set(theme().border_style.symbols())
+}
+
+/// Scrollbar over the table's right border, only when the rows don't all fit.
+/// Call after rendering the table, so the offset is current.
+fn draw_scrollbar(f: &mut Frame, app: &mut App, area: Rect) {
+    // Borders and the header row take three lines
+    let visible = area.height.saturating_sub(3) as usize;
+    let total = app.row_count();
+    if visible == 0 || total <= visible {
+        return;
+    }
+
+    // The thumb sits at the end once the last row is on screen
+    let mut state = ScrollbarState::new(total - visible + 1)
+        .viewport_content_length(visible)
+        .position(app.table_state.offset());
+    let track = Rect {
+        y: area.y + 2,
+        height: visible as u16,
+        ..area
+    };
+    f.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .symbols(theme().border_style.scrollbar())
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_style(Style::default().fg(theme().border))
+            .thumb_style(Style::default().fg(theme().text_secondary)),
+        track,
+        &mut state,
+    );
+}
+
+/// Centered hint inside an empty table, telling "nothing is listening" apart
+/// from "the filters hid everything"
+fn draw_empty_state(f: &mut Frame, app: &App, area: Rect) {
+    let t = theme();
+    let message = if app.entries.is_empty() {
+        "No listening ports found".to_string()
+    } else if !app.filter_text.is_empty() {
+        format!(
+            "No ports match '{}' — press {} to edit the filter",
+            app.filter_text,
+            app.keymap.label(Action::Filter)
+        )
+    } else if app.killable_only {
+        format!(
+            "Nothing here is yours to kill — press {} to show every port",
+            app.keymap.label(Action::ToggleKillable)
+        )
+    } else if app.unknown_only {
+        format!(
+            "Every port here was recognized — press {} to show them all",
+            app.keymap.label(Action::UnknownOnly)
+        )
+    } else if app.ignored == app.entries.len() {
+        format!(
+            "All {} ports are on the ignore list — run with --show-ignored to see them",
+            app.ignored
+        )
+    } else {
+        format!(
+            "The rest are loopback-only — press {} to show them",
+            app.keymap.label(Action::HideLoopback)
+        )
+    };
+
+    // Inside the border, below the header row
+    if area.height < 4 {
+        return;
+    }
+    let line_area = Rect {
+        x: area.x + 1,
+        y: area.y + 2 + (area.height - 4) / 2,
+        width: area.width.saturating_sub(2),
+        height: 1,
+    };
+    f.render_widget(
+        Paragraph::new(message)
+            .style(Style::default().fg(t.text_secondary))
+            .alignment(Alignment::Center),
+        line_area,
+    );
+}
+
+/// `gutter` is the narrow first cell (see [`gutter`]).
+/// `killing` is when a kill was sent to this entry's process, if one is pending
+/// `query` is the lowercased filter text; where it matches, the PORT, STATE,
+/// PROCESS and SERVICE cells highlight the matching part. `badge` flags the
+/// port as also bound elsewhere by an unrelated process, or as watched. `heat` is the largest
+/// memory figure in view, scaling the bars before CPU and MEM; `None` leaves
+/// them out (`--ascii`).
+fn entry_row(
+    e: &PortEntry,
+    process_text: String,
+    gutter: Cell<'static>,
+    killing: Option<Instant>,
+    query: &str,
+    badge: PortBadge,
+    heat: Option<f64>,
+) -> Row<'static> {
+    let t = theme();
+    let process_text = match killing {
+        Some(since) => {
+            let frame = since.elapsed().as_millis() / SPINNER_TICK.as_millis();
+            let spinner = SPINNER_FRAMES[frame as usize % SPINNER_FRAMES.len()];
+            format!("{} killing… {}", spinner, process_text)
+        }
+        None => process_text,
+    };
+    let service_text = match e.service_label() {
+        Some(svc) => format!("{}{}", t.category_prefix(e.category), svc),
+        None => "—".to_string(),
+    };
+    // Dimmed rows draw every cell in the muted color; container port
+    // forwarders are dimmed too, being plumbing rather than a service
+    let dimmed = t.is_dimmed(e.protocol) || e.is_container_proxy();
+    let fg = |color: Color| Style::default().fg(if dimmed { t.text_muted } else { color });
+    let cat_color = t.category_color(e.category);
+    let cpu_color = if e.cpu_percent > 50.0 {
+        t.error
+    } else if e.cpu_percent > 20.0 {
+        t.warning
+    } else {
+        t.text
+    };
+
+    let hit = Style::default()
+        .fg(t.warning)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let matched = |text: String, style: Style| Cell::from(highlight(text, query, style, hit));
+
+    Row::new(vec![
+        gutter,
+        match badge {
+            PortBadge::Conflict => matched(format!("{} ⚠", e.port), fg(t.warning)),
+            // Watched ports stand out even on dimmed rows
+            PortBadge::Watched => matched(
+                format!("{} ★", e.port),
+                Style::default().fg(t.primary).add_modifier(Modifier::BOLD),
+            ),
+            PortBadge::None => matched(e.port.to_string(), fg(t.text)),
+        },
+        Cell::from(e.protocol.to_string()).style(fg(t.protocol_color(e.protocol))),
+        matched(
+            e.state_display().to_string(),
+            fg(match e.state {
+                Some(s) if s.is_lingering() => t.warning,
+                Some(SocketState::Listen) | None => t.text_muted,
+                Some(_) => t.info,
+            }),
+        ),
+        matched(
+            process_text,
+            if killing.is_some() {
+                Style::default().fg(t.warning)
+            } else if e.partial {
+                fg(t.text_muted)
+            } else {
+                fg(cat_color)
+            },
+        ),
+        matched(service_text, fg(cat_color)),
+        Cell::from(e.pid.to_string()).style(fg(t.text_muted)),
+        match heat {
+            Some(_) => Cell::from(Line::from(vec![
+                Span::styled(
+                    heat_bar(e.cpu_percent as f64 / 100.0).to_string(),
+                    fg(cpu_color),
+                ),
+                Span::styled(format!(" {:.1}%", e.cpu_percent), fg(cpu_color)),
+            ])),
+            None => Cell::from(format!("{:.1}%", e.cpu_percent)).style(fg(cpu_color)),
+        },
+        match heat {
+            Some(max_mem) => Cell::from(Line::from(vec![
+                Span::styled(heat_bar(e.memory_mb / max_mem).to_string(), fg(t.info)),
+                Span::styled(format!(" {}", e.memory_display()), fg(t.text)),
+            ])),
+            None => Cell::from(e.memory_display()).style(fg(t.text)),
+        },
+        Cell::from(e.memory_percent_display()).style(fg(t.text_muted)),
+        if e.is_idle() {
+            Cell::from(format!("{} · idle", e.uptime_display())).style(fg(t.warning))
+        } else {
+            Cell::from(e.uptime_display()).style(fg(t.text_muted))
+        },
+    ])
+}
+
+/// A signalled process the scans haven't seen go yet
+struct PendingKill {
+    since: Instant,
+    /// Ports it listened on, to spot a supervisor restarting it
+    ports: Vec<u16>,
+}
+
+/// Flag shown after the number in the PORT cell
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PortBadge {
+    None,
+    /// `⚠`: an unrelated process binds the port on another address
+    Conflict,
+    /// `★`: listed under `[watch] ports`
+    Watched,
+}
+
+/// A refresh interval: `500ms` below a second, else whole seconds
+fn interval_display(d: Duration) -> String {
+    if d < Duration::from_secs(1) {
+        format!("{}ms", d.as_millis())
+    } else {
+        format!("{}s", d.as_secs())
+    }
+}
+
+/// First table cell: `●` when the process is marked for a batch kill, else a
+/// dim `✎` when the entry has a note
+fn gutter(app: &App, e: &PortEntry) -> Cell<'static> {
+    let t = theme();
+    if app.marked.contains(&e.pid) {
+        Cell::from("●").style(Style::default().fg(t.primary))
+    } else if app.notes.get(e).is_some() {
+        Cell::from("✎").style(Style::default().fg(t.text_muted))
+    } else {
+        Cell::from("")
+    }
+}
+
+/// One block character, `▁` to `█`, for a value between 0 and 1
+fn heat_bar(fraction: f64) -> char {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let level = (fraction.clamp(0.0, 1.0) * (BLOCKS.len() - 1) as f64).round();
+    BLOCKS[level as usize]
+}
+
+/// Split `text` around case-insensitive matches of `query` (already lowercased),
+/// drawing the matches in `hit` and the rest in `base`
+fn highlight(text: String, query: &str, base: Style, hit: Style) -> Line<'static> {
+    let query: Vec<char> = query.chars().collect();
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    if query.is_empty() || query.len() > chars.len() {
+        return Line::from(Span::styled(text, base));
+    }
+
+    let mut spans = Vec::new();
+    let mut plain_start = 0;
+    let mut i = 0;
+    while i + query.len() <= chars.len() {
+        let is_match = chars[i..i + query.len()]
+            .iter()
+            .zip(&query)
+            .all(|(&(_, c), &q)| c.to_lowercase().eq(q.to_lowercase()));
+        if !is_match {
+            i += 1;
+            continue;
+        }
+        let start = chars[i].0;
+        let end = chars
+            .get(i + query.len())
+            .map_or(text.len(), |&(byte, _)| byte);
+        if start > plain_start {
+            spans.push(Span::styled(text[plain_start..start].to_string(), base));
+        }
+        spans.push(Span::styled(text[start..end].to_string(), hit));
+        plain_start = end;
+        i += query.len();
+    }
+    if plain_start < text.len() {
+        spans.push(Span::styled(text[plain_start..].to_string(), base));
+    }
+    Line::from(spans)
+}
+
+/// Full-screen process view: who and where it runs at the top, then every
+/// socket it holds in the last scan (connections too, with Conns on)
+fn draw_zoom(f: &mut Frame, app: &App, zoom: &Zoom, area: Rect) {
+    let t = theme();
+    let mut sockets: Vec<&PortEntry> = app.entries.iter().filter(|e| e.pid == zoom.pid).collect();
+    sockets.sort_by_key(|e| (e.port, e.remote));
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(6), Constraint::Min(3)])
+        .split(area);
+
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(t.text_secondary));
+    let value = |text: String| Span::styled(text, Style::default().fg(t.text));
+    let (name, cmd, category) = match sockets.first() {
+        Some(e) => (e.process_name.clone(), e.process_cmd.clone(), e.category),
+        None => (
+            "(exited)".to_string(),
+            String::new(),
+            ServiceCategory::Unknown,
+        ),
+    };
+    let info = vec![
+        Line::from(vec![
+            label("Process: "),
+            Span::styled(
+                name.clone(),
+                Style::default()
+                    .fg(t.category_color(category))
+                    .add_modifier(Modifier::BOLD),
+            ),
+            label("  PID "),
+            value(zoom.pid.to_string()),
+        ]),
+        Line::from(vec![
+            label("User: "),
+            value(zoom.user.clone().unwrap_or_else(|| "—".to_string())),
+        ]),
+        Line::from(vec![
+            label("Directory: "),
+            value(
+                zoom.cwd
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| "—".to_string()),
+            ),
+        ]),
+        Line::from(vec![
+            label("Command: "),
+            value(if cmd.is_empty() {
+                "—".to_string()
+            } else {
+                cmd
+            }),
+        ]),
+    ];
+    f.render_widget(
+        Paragraph::new(info).block(
+            bordered()
+                .title(format!(" {} — Esc to go back ", name))
+                .border_style(Style::default().fg(t.border_focus)),
+        ),
+        layout[0],
+    );
+
+    let header = Row::new(
+        ["PORT", "PROTO", "STATE", "LOCAL", "REMOTE", "SERVICE"]
+            .iter()
+            .map(|h| {
+                Cell::from(*h).style(
+                    Style::default()
+                        .fg(t.text_secondary)
+                        .add_modifier(Modifier::BOLD),
+                )
+            }),
+    );
+    let rows: Vec<Row> = sockets
+        .iter()
+        .map(|e| {
+            Row::new(vec![
+                Cell::from(e.port.to_string()).style(Style::default().fg(t.text)),
+                Cell::from(e.protocol.to_string())
+                    .style(Style::default().fg(t.protocol_color(e.protocol))),
+                Cell::from(e.state_display()).style(Style::default().fg(t.text_muted)),
+                Cell::from(std::net::SocketAddr::new(e.local_addr, e.port).to_string())
+                    .style(Style::default().fg(t.text)),
+                Cell::from(
+                    e.remote
+                        .map(|r| r.to_string())
+                        .unwrap_or_else(|| "—".to_string()),
+                )
+                .style(Style::default().fg(t.info)),
+                Cell::from(e.service_label().unwrap_or_else(|| "—".to_string()))
+                    .style(Style::default().fg(t.category_color(e.category))),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(6),
+        Constraint::Length(12),
+        Constraint::Length(28),
+        Constraint::Length(28),
+        Constraint::Min(10),
+    ];
+    let title = format!(
+        " {} socket{}{} ",
+        sockets.len(),
+        if sockets.len() == 1 { "" } else { "s" },
+        if app.show_established {
+            ""
+        } else {
+            " (listening; Conns adds connections)"
+        }
+    );
+    f.render_widget(
+        Table::new(rows, widths).header(header).block(
+            bordered()
+                .title(title)
+                .border_style(Style::default().fg(t.border)),
+        ),
+        layout[1],
+    );
+}
+
+fn draw_detail(f: &mut Frame, app: &App, area: Rect) {
+    let t = theme();
+
+    let content = if let Some(entry) = app.selected_entry() {
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("Port: ", Style::default().fg(t.text_secondary)),
+                Span::styled(
+                    format!("{} ({})", entry.port, entry.protocol),
+                    Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("Address: ", Style::default().fg(t.text_secondary)),
+                Span::styled(entry.addr_display(), Style::default().fg(t.text)),
+            ]),
+            Line::from(vec![
+                Span::styled("State: ", Style::default().fg(t.text_secondary)),
+                Span::styled(entry.state_display(), Style::default().fg(t.text)),
+                Span::styled(
+                    entry
+                        .remote
+                        .map(|r| format!("  → {}", r))
+                        .unwrap_or_default(),
+                    Style::default().fg(t.text_secondary),
+                ),
+                Span::styled(
+                    entry
+                        .remote
+                        .and_then(|r| app.asn.as_ref()?.lookup(r.ip()))
+                        .map(|rec| format!("  ({})", rec))
+                        .unwrap_or_default(),
+                    Style::default().fg(t.info),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("Process: ", Style::default().fg(t.text_secondary)),
+                Span::styled(
+                    &entry.process_name,
+                    Style::default().fg(t.category_color(entry.category)),
+                ),
+                Span::styled(
+                    if entry.partial {
+                        "  (exited or not visible)"
+                    } else {
+                        ""
+                    },
+                    Style::default().fg(t.text_muted),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("PID: ", Style::default().fg(t.text_secondary)),
+                Span::styled(entry.pid.to_string(), Style::default().fg(t.text)),
+            ]),
+            Line::from(vec![
+                Span::styled("PPID: ", Style::default().fg(t.text_secondary)),
+                Span::styled(
+                    entry
+                        .ppid
+                        .map(|p| p.to_string())
+                        .unwrap_or_else(|| "—".to_string()),
+                    Style::default().fg(t.text),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("Service: ", Style::default().fg(t.text_secondary)),
+                Span::styled(
+                    entry
+                        .service_label()
+                        .unwrap_or_else(|| "Unknown".to_string()),
+                    Style::default().fg(t.category_color(entry.category)),
+                ),
+            ]),
+            Line::from(""),
             Line::from(vec![
                 Span::styled("CPU: ", Style::default().fg(t.text_secondary)),
                 Span::styled(
@@ -533,11 +2451,116 @@ fn draw_detail(f: &mut Frame, app: &App, area: Rect) {
             Line::from(vec![
                 Span::styled("Memory: ", Style::default().fg(t.text_secondary)),
                 Span::styled(entry.memory_display(), Style::default().fg(t.text)),
+                Span::styled(
+                    entry
+                        .memory_percent
+                        .map(|pct| format!("  ({:.1}% of RAM)", pct))
+                        .unwrap_or_default(),
+                    Style::default().fg(t.text_muted),
+                ),
             ]),
             Line::from(vec![
                 Span::styled("Uptime: ", Style::default().fg(t.text_secondary)),
                 Span::styled(entry.uptime_display(), Style::default().fg(t.text)),
+                Span::styled(
+                    if entry.is_idle() { "  (idle)" } else { "" },
+                    Style::default().fg(t.warning),
+                ),
             ]),
+        ];
+        if let Some(note) = app.notes.get(entry) {
+            lines.push(Line::from(vec![
+                Span::styled("Note: ", Style::default().fg(t.text_secondary)),
+                Span::styled(note.to_string(), Style::default().fg(t.info)),
+            ]));
+        }
+        if let Some(first) = app.history.as_ref().and_then(|h| h.first_seen(entry)) {
+            let ago = (chrono::Local::now() - first).to_std().unwrap_or_default();
+            lines.push(Line::from(vec![
+                Span::styled("First seen: ", Style::default().fg(t.text_secondary)),
+                Span::styled(
+                    first.format("%Y-%m-%d %H:%M").to_string(),
+                    Style::default().fg(t.text),
+                ),
+                Span::styled(
+                    format!("  ({} ago)", duration_display(ago)),
+                    Style::default().fg(t.text_muted),
+                ),
+            ]));
+        }
+        if entry.protocol == Protocol::Tcp && entry.state == Some(SocketState::Listen) {
+            lines.push(Line::from(vec![
+                Span::styled("Accept queue: ", Style::default().fg(t.text_secondary)),
+                Span::styled(
+                    entry.backlog_display(),
+                    Style::default().fg(if entry.backlog.is_some_and(|b| b.is_full()) {
+                        t.error
+                    } else {
+                        t.text
+                    }),
+                ),
+                Span::styled(
+                    if entry.backlog.is_some_and(|b| b.is_full()) {
+                        "  (full: new connections are dropped)"
+                    } else {
+                        ""
+                    },
+                    Style::default().fg(t.error),
+                ),
+            ]));
+        }
+        if app.include_threads {
+            lines.push(Line::from(vec![
+                Span::styled("Threads: ", Style::default().fg(t.text_secondary)),
+                Span::styled(entry.thread_display(), Style::default().fg(t.text)),
+            ]));
+        }
+        if let Some(target) = entry.proxy_target() {
+            let link = match proxy_backend(entry, &app.entries) {
+                Some(b) => vec![
+                    Span::styled(
+                        format!("{} (PID {})", b.process_name, b.pid),
+                        Style::default().fg(t.category_color(b.category)),
+                    ),
+                    Span::styled(format!(" on {}", target), Style::default().fg(t.text)),
+                ],
+                None => vec![
+                    Span::styled(target.to_string(), Style::default().fg(t.text)),
+                    Span::styled(
+                        "  (backend not visible from here)",
+                        Style::default().fg(t.text_muted),
+                    ),
+                ],
+            };
+            let mut spans = vec![Span::styled(
+                "Forwards to: ",
+                Style::default().fg(t.text_secondary),
+            )];
+            spans.extend(link);
+            lines.push(Line::from(spans));
+        }
+        let fronts = proxy_frontends(entry, &app.entries);
+        if !fronts.is_empty() {
+            let via: Vec<String> = fronts
+                .iter()
+                .map(|p| format!("{} (PID {}) on :{}", p.process_name, p.pid, p.port))
+                .collect();
+            lines.push(Line::from(vec![
+                Span::styled("Behind: ", Style::default().fg(t.text_secondary)),
+                Span::styled(via.join(", "), Style::default().fg(t.text)),
+            ]));
+        }
+        if let Some(inode) = entry.inode.filter(|_| app.show_inode) {
+            lines.push(Line::from(vec![
+                Span::styled("Inode: ", Style::default().fg(t.text_secondary)),
+                Span::styled(inode.to_string(), Style::default().fg(t.text)),
+                Span::styled(
+                    format!("  (socket:[{}] in /proc/{}/fd)", inode, entry.pid),
+                    Style::default().fg(t.text_muted),
+                ),
+            ]));
+        }
+        lines.extend([
             Line::from(""),
             Line::from(Span::styled(
                 "Command:",
@@ -547,7 +2570,8 @@ fn draw_detail(f: &mut Frame, app: &App, area: Rect) {
                 &entry.process_cmd,
                 Style::default().fg(t.text_muted),
             )),
-        ]
+        ]);
+        lines
     } else {
         vec![Line::from(Span::styled(
             "No port selected",
@@ -555,18 +2579,76 @@ fn draw_detail(f: &mut Frame, app: &App, area: Rect) {
         ))]
     };
 
-    let block = Block::default()
+    let block = bordered()
         .title(" Detail ")
-        .borders(Borders::ALL)
         .border_style(Style::default().fg(t.border));
 
     let paragraph = Paragraph::new(content).block(block);
     f.render_widget(paragraph, area);
 }
 
+/// What the visible rows are made of, e.g. `dev:4 database:2 cache:1`, each
+/// count in its category color
+fn draw_category_counts(f: &mut Frame, app: &App, area: Rect) {
+    let t = theme();
+    let mut spans = Vec::new();
+    for category in ServiceCategory::ALL {
+        let count = app
+            .filtered
+            .iter()
+            .filter(|&&idx| app.entries[idx].category == category)
+            .count();
+        if count > 0 {
+            spans.push(Span::styled(
+                format!(" {}:{}", category.label(), count),
+                Style::default().fg(t.category_color(category)),
+            ));
+        }
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
 fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
     let t = theme();
 
+    if let Some(input) = &app.command_input {
+        let line = Line::from(vec![
+            Span::styled(format!(" :{}", input), Style::default().fg(t.text)),
+            Span::styled("█", Style::default().fg(t.primary)),
+        ]);
+        f.render_widget(Paragraph::new(line), area);
+        return;
+    }
+
+    if app.export_prompt {
+        let line = Line::from(vec![
+            Span::styled(
+                format!(" Export {} rows as: ", app.filtered.len()),
+                Style::default().fg(t.text_secondary),
+            ),
+            Span::styled("c", Style::default().fg(t.primary)),
+            Span::styled(" CSV  ", Style::default().fg(t.text)),
+            Span::styled("j", Style::default().fg(t.primary)),
+            Span::styled(" JSON  ", Style::default().fg(t.text)),
+            Span::styled("(any other key cancels)", Style::default().fg(t.text_muted)),
+        ]);
+        f.render_widget(Paragraph::new(line), area);
+        return;
+    }
+
+    if let Some((entry, text)) = &app.note_input {
+        let line = Line::from(vec![
+            Span::styled(
+                format!(" Note on {} ({}): ", entry.port, entry.process_name),
+                Style::default().fg(t.text_secondary),
+            ),
+            Span::styled(text.clone(), Style::default().fg(t.text)),
+            Span::styled("█", Style::default().fg(t.primary)),
+        ]);
+        f.render_widget(Paragraph::new(line), area);
+        return;
+    }
+
     // Show status message if recent (within 3 seconds)
     if let Some((ref msg, ref when)) = app.status_msg {
         if when.elapsed() < Duration::from_secs(3) {
@@ -579,92 +2661,649 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
         }
     }
 
-    let shortcuts = Line::from(vec![
-        Span::styled(
-            " /",
-            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(" Filter  ", Style::default().fg(t.text_muted)),
-        Span::styled(
-            "^X",
-            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(" Kill  ", Style::default().fg(t.text_muted)),
-        Span::styled(
-            "^K",
-            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(" Force  ", Style::default().fg(t.text_muted)),
-        Span::styled(
-            "^D",
-            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(" Detail  ", Style::default().fg(t.text_muted)),
-        Span::styled(
-            "^S",
-            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(" Sort  ", Style::default().fg(t.text_muted)),
-        Span::styled(
-            "^T",
-            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(" Proto  ", Style::default().fg(t.text_muted)),
-        Span::styled(
-            "^R",
-            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(" Refresh  ", Style::default().fg(t.text_muted)),
-        Span::styled(
-            "^Q",
+    let mut spans = Vec::new();
+    if app.read_only {
+        spans.push(Span::styled(
+            " READ-ONLY ",
+            Style::default()
+                .fg(t.text)
+                .bg(t.warning)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    let hints = [
+        (Action::Filter, "Filter"),
+        (Action::Kill, "Kill"),
+        (Action::ForceKill, "Force"),
+        (Action::Mark, "Mark"),
+        (Action::Restart, "Restart"),
+        (Action::ToggleDetail, "Detail"),
+        (Action::SortMenu, "Sort"),
+        (Action::CycleProtocol, "Proto"),
+        (Action::ToggleTree, "Tree"),
+        (Action::ToggleEstablished, "Conns"),
+        (Action::HideLoopback, "Exposed"),
+        (Action::PrintSelected, "Print"),
+        (Action::Ignore, "Ignore"),
+        (Action::Follow, "Follow"),
+        (Action::Refresh, "Refresh"),
+        (Action::Quit, "Quit"),
+    ];
+
+    // Keys that would only say "disabled" are left out
+    let hints: Vec<_> = hints
+        .into_iter()
+        .filter(|(action, _)| {
+            !(app.read_only && matches!(action, Action::Kill | Action::ForceKill | Action::Restart))
+        })
+        .collect();
+    for (i, (action, label)) in hints.iter().enumerate() {
+        let key = app.keymap.label(*action);
+        spans.push(Span::styled(
+            if i == 0 { format!(" {}", key) } else { key },
             Style::default().fg(t.text).add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(" Quit", Style::default().fg(t.text_muted)),
-    ]);
+        ));
+        spans.push(Span::styled(
+            if i + 1 == hints.len() {
+                format!(" {}", label)
+            } else {
+                format!(" {}  ", label)
+            },
+            Style::default().fg(t.text_muted),
+        ));
+    }
+    let shortcuts = Line::from(spans);
 
     f.render_widget(Paragraph::new(shortcuts), area);
 }
 
-fn draw_kill_confirm(f: &mut Frame, entry: &PortEntry) {
+/// Centered list of sort fields: `●` marks the current one, the cursor row is highlighted
+fn draw_sort_menu(f: &mut Frame, current: SortField, cursor: usize) {
     let t = theme();
     let area = f.area();
 
-    // Center a dialog box
-    let dialog_width = 50u16.min(area.width.saturating_sub(4));
-    let dialog_height = 5u16;
+    let dialog_width = 40u16.min(area.width.saturating_sub(4));
+    let dialog_height = SortField::ALL.len() as u16 + 4;
+    let x = (area.width.saturating_sub(dialog_width)) / 2;
+    let y = (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(Clear, dialog_area);
+
+    let mut text: Vec<Line> = SortField::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, &field)| {
+            let marker = if field == current { "●" } else { " " };
+            let style = if i == cursor {
+                Style::default()
+                    .bg(t.selection_bg)
+                    .fg(t.selection_fg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(t.text)
+            };
+            Line::from(Span::styled(
+                format!(" {} {:<width$}", marker, field.label(), width = 34),
+                style,
+            ))
+        })
+        .collect();
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        " ↑/↓ move · Enter sort · Esc close",
+        Style::default().fg(t.text_muted),
+    )));
+
+    let block = bordered()
+        .title(" Sort by ")
+        .border_style(Style::default().fg(t.border_focus));
+
+    f.render_widget(Paragraph::new(text).block(block), dialog_area);
+}
+
+/// This session's status messages, newest first, with how long ago each was
+fn draw_message_log(f: &mut Frame, log: &VecDeque<(Instant, String, MsgKind)>) {
+    let t = theme();
+    let area = f.area();
+
+    let dialog_width = 90u16.min(area.width.saturating_sub(4));
+    let dialog_height = 24u16.min(area.height.saturating_sub(2));
+    let x = (area.width.saturating_sub(dialog_width)) / 2;
+    let y = (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(Clear, dialog_area);
+
+    // Borders, the blank line and the footer
+    let rows = dialog_height.saturating_sub(4) as usize;
+    let mut text: Vec<Line> = if log.is_empty() {
+        vec![Line::from(Span::styled(
+            " No messages yet",
+            Style::default().fg(t.text_muted),
+        ))]
+    } else {
+        log.iter()
+            .rev()
+            .take(rows)
+            .map(|(when, msg, kind)| {
+                let color = match kind {
+                    MsgKind::Info => t.text,
+                    MsgKind::Error => t.error,
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!(" {:>7} ago  ", duration_display(when.elapsed())),
+                        Style::default().fg(t.text_muted),
+                    ),
+                    Span::styled(msg.clone(), Style::default().fg(color)),
+                ])
+            })
+            .collect()
+    };
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        format!(" {} messages, newest first · any key closes", log.len()),
+        Style::default().fg(t.text_muted),
+    )));
+
+    let block = bordered()
+        .title(" Messages ")
+        .border_style(Style::default().fg(t.border_focus));
+
+    f.render_widget(Paragraph::new(text).block(block), dialog_area);
+}
+
+/// Restart re-runs whatever the process was started with, so spell it out first
+fn draw_restart_confirm(f: &mut Frame, entry: &PortEntry, launch: &Launch) {
+    let t = theme();
+    let area = f.area();
+
+    let dialog_width = 72u16.min(area.width.saturating_sub(4));
+    let dialog_height = 8u16;
     let x = (area.width.saturating_sub(dialog_width)) / 2;
     let y = (area.height.saturating_sub(dialog_height)) / 2;
     let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
 
     f.render_widget(Clear, dialog_area);
 
+    let inner = dialog_width.saturating_sub(6) as usize;
+    let cwd = launch
+        .cwd
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "(unknown directory)".to_string());
     let text = vec![
         Line::from(""),
         Line::from(vec![
             Span::styled(
-                "  Kill ",
-                Style::default().fg(t.error).add_modifier(Modifier::BOLD),
+                "  Restart ",
+                Style::default().fg(t.warning).add_modifier(Modifier::BOLD),
             ),
             Span::styled(
                 entry.process_name.to_string(),
                 Style::default().fg(t.text).add_modifier(Modifier::BOLD),
             ),
             Span::styled(
-                format!(" (PID {}) on port {}?", entry.pid, entry.port),
+                format!(" (PID {}) on port {}? This re-runs:", entry.pid, entry.port),
                 Style::default().fg(t.text_secondary),
             ),
         ]),
+        Line::from(Span::styled(
+            format!("    {}", truncate(&launch.command_line(), inner)),
+            Style::default().fg(t.text),
+        )),
+        Line::from(Span::styled(
+            format!("    in {}", truncate(&cwd, inner.saturating_sub(3))),
+            Style::default().fg(t.text_muted),
+        )),
+        Line::from(""),
         Line::from(Span::styled(
             "  y = confirm, any other key = cancel",
             Style::default().fg(t.text_muted),
         )),
     ];
 
-    let block = Block::default()
-        .title(" Confirm Kill ")
-        .borders(Borders::ALL)
+    let block = bordered()
+        .title(" Confirm Restart ")
+        .border_style(Style::default().fg(t.warning));
+
+    f.render_widget(Paragraph::new(text).block(block), dialog_area);
+}
+
+/// `typed` is the confirmation input so far when the entry is protected
+/// `children` is how many processes below it a tree kill takes along
+/// `force` marks it as a SIGKILL, in red
+fn draw_kill_confirm(
+    f: &mut Frame,
+    entry: &PortEntry,
+    typed: Option<&str>,
+    children: usize,
+    force: bool,
+) {
+    let t = theme();
+    let area = f.area();
+
+    // Center a dialog box
+    let dialog_width = 56u16.min(area.width.saturating_sub(4));
+    let dialog_height = if children > 0 { 6u16 } else { 5u16 };
+    let x = (area.width.saturating_sub(dialog_width)) / 2;
+    let y = (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(Clear, dialog_area);
+
+    let mut text = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  ", Style::default()),
+            kill_label(force),
+            Span::styled(" ", Style::default()),
+            Span::styled(
+                entry.process_name.to_string(),
+                Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(" (PID {}) on port {}?", entry.pid, entry.port),
+                Style::default().fg(t.text_secondary),
+            ),
+        ]),
+        match typed {
+            Some(input) => Line::from(vec![
+                Span::styled(
+                    format!("  Protected — type {} + Enter: ", entry.port),
+                    Style::default().fg(t.warning),
+                ),
+                Span::styled(
+                    format!("{}_", input),
+                    Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+                ),
+            ]),
+            None => Line::from(Span::styled(
+                "  y = confirm, any other key = cancel",
+                Style::default().fg(t.text_muted),
+            )),
+        },
+    ];
+    if children > 0 {
+        text.insert(
+            2,
+            Line::from(Span::styled(
+                format!(
+                    "  …and {} child process{} it started, children first",
+                    children,
+                    if children == 1 { "" } else { "es" }
+                ),
+                Style::default().fg(t.warning),
+            )),
+        );
+    }
+
+    let title = match (typed.is_some(), children > 0, force) {
+        (true, _, true) => " Confirm FORCE KILL (protected) ",
+        (true, _, false) => " Confirm Kill (protected) ",
+        (false, true, _) => " Confirm Tree Kill ",
+        (false, false, true) => " Confirm FORCE KILL ",
+        (false, false, false) => " Confirm Kill ",
+    };
+    let block = bordered()
+        .title(title)
         .border_style(Style::default().fg(t.error));
 
     let paragraph = Paragraph::new(text).block(block);
     f.render_widget(paragraph, dialog_area);
 }
+
+/// "Kill" for a confirmation dialog, or a red-backed "FORCE KILL" so a
+/// SIGKILL can't be mistaken for a normal kill
+fn kill_label(force: bool) -> Span<'static> {
+    let t = theme();
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    if force {
+        Span::styled(" FORCE KILL ", bold.fg(t.text).bg(t.error))
+    } else {
+        Span::styled("Kill", bold.fg(t.error))
+    }
+}
+
+/// One confirmation for killing every marked process; `protected` of them
+/// will be skipped
+fn draw_batch_confirm(f: &mut Frame, count: usize, protected: usize, force: bool) {
+    let t = theme();
+    let area = f.area();
+
+    let dialog_width = 56u16.min(area.width.saturating_sub(4));
+    let dialog_height = if protected > 0 { 6u16 } else { 5u16 };
+    let x = (area.width.saturating_sub(dialog_width)) / 2;
+    let y = (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(Clear, dialog_area);
+
+    let mut text = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  ", Style::default()),
+            kill_label(force),
+            Span::styled(
+                format!(
+                    " {} marked process{}?",
+                    count,
+                    if count == 1 { "" } else { "es" }
+                ),
+                Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(Span::styled(
+            "  y = confirm, any other key = cancel",
+            Style::default().fg(t.text_muted),
+        )),
+    ];
+    if protected > 0 {
+        text.insert(
+            2,
+            Line::from(Span::styled(
+                format!("  {} on a protected port will be skipped", protected),
+                Style::default().fg(t.warning),
+            )),
+        );
+    }
+
+    let block = bordered()
+        .title(if force {
+            " Confirm Batch FORCE KILL "
+        } else {
+            " Confirm Batch Kill "
+        })
+        .border_style(Style::default().fg(t.error));
+    f.render_widget(Paragraph::new(text).block(block), dialog_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use netstat2::TcpState;
+
+    use super::*;
+    use crate::scanner::fake::FakeSource;
+
+    fn idle_app(max_interval_secs: u64) -> App {
+        let mut config = Config::default();
+        config.refresh.max_interval_secs = max_interval_secs;
+        config.history.enabled = false;
+        let opts = TuiOptions {
+            include_self: false,
+            ephemeral_udp: false,
+            include_threads: false,
+            show_inode: false,
+            yolo: false,
+            read_only: false,
+            total_sockets: false,
+            show_ignored: false,
+            geoip: None,
+            sort_secondary: SortField::Port,
+            ascii: false,
+        };
+        let mut app = App::new(KeyMap::default(), config, &opts);
+        app.last_input = Instant::now() - IDLE_INPUT;
+        app
+    }
+
+    #[test]
+    fn backoff_starts_after_idle_scans_and_caps() {
+        let mut app = idle_app(10);
+        for _ in 1..IDLE_SCANS {
+            app.note_scan(false);
+            assert_eq!(app.refresh_interval, REFRESH_INTERVAL);
+        }
+        let intervals: Vec<_> = (0..4)
+            .map(|_| {
+                app.note_scan(false);
+                app.refresh_interval.as_secs()
+            })
+            .collect();
+        assert_eq!(intervals, [4, 8, 10, 10]);
+    }
+
+    #[test]
+    fn change_or_input_snaps_back() {
+        let mut app = idle_app(30);
+        for _ in 0..IDLE_SCANS + 2 {
+            app.note_scan(false);
+        }
+        assert!(app.refresh_interval > REFRESH_INTERVAL);
+        app.note_scan(true);
+        assert_eq!(
+            (app.refresh_interval, app.unchanged_scans),
+            (REFRESH_INTERVAL, 0)
+        );
+
+        for _ in 0..IDLE_SCANS + 2 {
+            app.note_scan(false);
+        }
+        app.note_input();
+        assert_eq!(app.refresh_interval, REFRESH_INTERVAL);
+        // Recent input holds the base rate however long nothing changes
+        for _ in 0..IDLE_SCANS * 2 {
+            app.note_scan(false);
+        }
+        assert_eq!(app.refresh_interval, REFRESH_INTERVAL);
+    }
+
+    #[test]
+    fn backoff_off_keeps_base_interval() {
+        let mut app = idle_app(30);
+        app.max_refresh = REFRESH_INTERVAL;
+        for _ in 0..IDLE_SCANS * 2 {
+            app.note_scan(false);
+        }
+        assert_eq!(app.refresh_interval, REFRESH_INTERVAL);
+    }
+
+    #[test]
+    fn rate_keys_halve_and_double_within_bounds() {
+        let mut app = idle_app(10);
+        let rates: Vec<_> = (0..4)
+            .map(|_| {
+                app.change_rate(true);
+                interval_display(app.refresh_interval)
+            })
+            .collect();
+        assert_eq!(rates, ["1s", "500ms", "250ms", "250ms"]);
+
+        for _ in 0..10 {
+            app.change_rate(false);
+        }
+        assert_eq!(app.base_refresh, MAX_REFRESH);
+        // Idle backoff never drops below the chosen base
+        for _ in 0..IDLE_SCANS * 2 {
+            app.note_scan(false);
+        }
+        assert_eq!(app.refresh_interval, MAX_REFRESH);
+    }
+
+    #[test]
+    fn mark_all_and_invert_only_touch_visible_rows() {
+        let mut app = idle_app(30);
+        app.entries = vec![
+            PortEntry::fixture(3000, 10, "node"),
+            PortEntry::fixture(3001, 11, "node"),
+            PortEntry::fixture(5432, 20, "postgres"),
+        ];
+        app.marked.insert(20);
+        app.filter_text = "node".to_string();
+        app.apply_filter();
+
+        app.mark_all_visible();
+        assert_eq!(app.marked, HashSet::from([10, 11, 20]));
+
+        app.invert_marks();
+        assert_eq!(app.marked, HashSet::from([20]));
+        app.invert_marks();
+        assert_eq!(app.marked, HashSet::from([10, 11, 20]));
+
+        // Esc clears the marks before the filter
+        handle_key(&mut app, KeyEvent::from(KeyCode::Esc));
+        assert!(app.marked.is_empty());
+        assert_eq!(app.filter_text, "node");
+    }
+
+    #[test]
+    fn read_only_refuses_kills_and_restarts() {
+        let mut app = idle_app(30);
+        app.read_only = true;
+        app.entries = vec![PortEntry::fixture(3000, 10, "node")];
+        app.apply_filter();
+        for action in [
+            Action::Kill,
+            Action::ForceKill,
+            Action::KillTree,
+            Action::Restart,
+        ] {
+            run_action(&mut app, action);
+            assert!(app.confirm_kill.is_none() && app.confirm_restart.is_none());
+        }
+        app.marked.insert(10);
+        run_action(&mut app, Action::Kill);
+        assert!(app.confirm_marked.is_empty());
+        assert!(app
+            .status_msg
+            .is_some_and(|(msg, _)| msg.starts_with("Read-only")));
+    }
+
+    #[test]
+    fn typed_confirmation_follows_the_entry_not_the_row() {
+        // PIDs above Linux's pid_max, so nothing real is ever signalled
+        let mut app = idle_app(30);
+        app.protect.ports = vec![5432];
+        app.entries = vec![
+            PortEntry::fixture(5432, 4_200_001, "postgres"),
+            PortEntry::fixture(3000, 4_200_002, "node"),
+        ];
+        app.apply_filter();
+        app.table_state.select(Some(0));
+        run_action(&mut app, Action::Kill);
+
+        // A refresh while the port is typed: postgres is gone and node has
+        // taken its row
+        app.entries = vec![PortEntry::fixture(3000, 4_200_002, "node")];
+        app.apply_filter();
+        for c in "5432".chars() {
+            handle_key(&mut app, KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.confirm_kill.as_ref().map(|e| e.pid), Some(4_200_001));
+        handle_key(&mut app, KeyEvent::from(KeyCode::Enter));
+
+        assert!(app.confirm_kill.is_none());
+        assert_eq!(
+            app.status_msg.as_ref().unwrap().0,
+            "postgres (PID 4200001) no longer holds port 5432; nothing killed"
+        );
+    }
+
+    #[test]
+    fn kill_confirmation_survives_a_reordering_refresh() {
+        // PIDs above Linux's pid_max, so nothing real is ever signalled
+        let (node, vite) = (4_200_001, 4_200_002);
+        // (pid, port, name, CPU%) of each listener
+        let scan = |listeners: &[(u32, u16, &str, f32)]| {
+            let mut source = FakeSource::default();
+            for &(pid, port, name, cpu) in listeners {
+                source = source
+                    .tcp(port, TcpState::Listen, &[pid])
+                    .process(pid, name, name);
+                source.processes.get_mut(&pid).unwrap().cpu_percent = cpu;
+            }
+            Box::new(source)
+        };
+        let mut app = idle_app(30);
+        app.sort_field = SortField::Cpu;
+        app.source = scan(&[(node, 3000, "node", 50.0), (vite, 5173, "vite", 1.0)]);
+        app.refresh();
+        app.table_state.select(Some(0));
+        run_action(&mut app, Action::ForceKill);
+        assert_eq!(app.confirm_kill.as_ref().map(|e| e.pid), Some(node));
+
+        // The next scan puts vite in node's row while the dialog is open
+        app.source = scan(&[(node, 3000, "node", 1.0), (vite, 5173, "vite", 50.0)]);
+        app.refresh();
+        assert_eq!(app.entries[0].pid, vite);
+        handle_key(&mut app, KeyEvent::from(KeyCode::Char('y')));
+        assert_eq!(
+            app.status_msg.as_ref().unwrap().0,
+            format!("Kill failed: Process with PID {} not found", node)
+        );
+
+        // A scan that drops the entry leaves nothing to kill
+        app.reselect(node, 3000);
+        run_action(&mut app, Action::ForceKill);
+        app.source = scan(&[(vite, 5173, "vite", 50.0)]);
+        app.refresh();
+        handle_key(&mut app, KeyEvent::from(KeyCode::Char('y')));
+        assert!(app.confirm_kill.is_none());
+        assert_eq!(
+            app.status_msg.as_ref().unwrap().0,
+            "node (PID 4200001) no longer holds port 3000; nothing killed"
+        );
+    }
+
+    #[test]
+    fn resort_keeps_the_selected_entry() {
+        let mut app = idle_app(30);
+        app.entries = vec![
+            PortEntry::fixture(3000, 10, "zeta"),
+            PortEntry::fixture(5432, 20, "alpha"),
+            PortEntry::fixture(8080, 30, "mid"),
+        ];
+        app.sort_field = SortField::Port;
+        app.resort();
+        app.table_state.select(Some(0));
+
+        run_action(&mut app, Action::CycleSort);
+        assert_eq!(app.sort_field, SortField::ProcessName);
+        assert_eq!(app.table_state.selected(), Some(2));
+        assert_eq!(app.selected_key(), Some((10, 3000)));
+    }
+
+    #[test]
+    fn message_log_keeps_the_latest() {
+        let mut app = idle_app(30);
+        for i in 0..LOG_LIMIT + 5 {
+            app.notify(MsgKind::Info, format!("message {}", i));
+        }
+        app.notify(MsgKind::Error, "Kill failed");
+
+        assert_eq!(app.log.len(), LOG_LIMIT);
+        assert_eq!(app.log[0].1, "message 6");
+        let (_, last, kind) = app.log.back().unwrap();
+        assert_eq!((last.as_str(), *kind), ("Kill failed", MsgKind::Error));
+        assert_eq!(app.status_msg.as_ref().unwrap().0, "Kill failed");
+    }
+
+    #[test]
+    fn killed_port_taken_by_a_new_pid_reads_as_restarted() {
+        let mut app = idle_app(30);
+        app.entries = vec![PortEntry::fixture(3000, 10, "node")];
+        app.track_kill(10);
+        app.entries = vec![PortEntry::fixture(3000, 11, "node")];
+        app.settle_kills();
+        assert!(app.pending_kills.is_empty());
+        assert_eq!(
+            app.status_msg.as_ref().unwrap().0,
+            "Port 3000 restarted (new PID 11)"
+        );
+
+        // The same PID outliving the timeout is a failed kill instead
+        app.entries = vec![PortEntry::fixture(4000, 20, "vite")];
+        app.track_kill(20);
+        app.pending_kills.get_mut(&20).unwrap().since -= KILL_TIMEOUT;
+        app.settle_kills();
+        assert!(app.pending_kills.is_empty());
+        assert!(app
+            .status_msg
+            .as_ref()
+            .unwrap()
+            .0
+            .starts_with("PID 20 is still running"));
+    }
+}
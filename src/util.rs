@@ -1,13 +1,75 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
 use crate::models::ServiceCategory;
+use crate::ports;
+
+/// Common dev server ports checked by `kav dev` (extendable via `[dev] ports`)
+pub const DEV_PORTS: &[u16] = &[3000, 3001, 4000, 4200, 5000, 5173, 5174, 8000, 8080, 8888];
+
+/// Container runtimes' port forwarders. They hold a published port on the
+/// host only to relay traffic into a container, so the real service is
+/// whatever runs inside it.
+const CONTAINER_PROXIES: &[&str] = &["docker-proxy", "com.docker.backend", "containerd-shim"];
+
+/// Service label for container port forwarders whose target isn't known
+pub const CONTAINER_PROXY_LABEL: &str = "(docker bridge)";
+
+/// Whether `process_name` is container networking plumbing (see `CONTAINER_PROXIES`)
+pub fn is_container_proxy(process_name: &str) -> bool {
+    let name = process_name.to_lowercase();
+    CONTAINER_PROXIES.iter().any(|p| name.contains(p))
+}
+
+/// Where a docker-proxy forwards its port, e.g. `172.17.0.2:80`, read from
+/// its `-container-ip` / `-container-port` arguments
+pub fn container_proxy_addr(cmd: &str) -> Option<std::net::SocketAddr> {
+    let mut args = cmd.split_whitespace();
+    let (mut ip, mut port) = (None, None);
+    while let Some(arg) = args.next() {
+        match arg.trim_start_matches('-') {
+            "container-ip" => ip = args.next(),
+            "container-port" => port = args.next(),
+            _ => {}
+        }
+    }
+    let port: u16 = port?.parse().ok()?;
+    Some(std::net::SocketAddr::new(ip?.parse().ok()?, port))
+}
+
+/// Frameworks recognized from the command line of a generic runtime. A rule
+/// applies only when the process name starts with its runtime (`python3.12`
+/// counts as `python`) and the lowercased command contains every one of its
+/// substrings; the first match wins. A launcher run straight from its shebang
+/// carries its own name instead, so those match on the name alone.
+const COMMAND_RULES: &[(&str, &[&str], &str)] = &[
+    ("java", &["org.springframework.boot"], "Spring Boot"),
+    ("java", &["spring-boot"], "Spring Boot"),
+    ("python", &["gunicorn"], "Gunicorn"),
+    ("python", &["uvicorn"], "Uvicorn"),
+    ("ruby", &["puma"], "Rails"),
+    ("ruby", &["rails s"], "Rails"), // also `rails server`
+    ("gunicorn", &[], "Gunicorn"),
+    ("uvicorn", &[], "Uvicorn"),
+    ("puma", &[], "Rails"),
+];
 
-/// Known port-to-service mappings for common developer tools
-pub fn identify_service(port: u16, process_name: &str) -> (Option<&'static str>, ServiceCategory) {
+/// Known port-to-service mappings for common developer tools. `cmd` is the
+/// full command line, when known, for telling frameworks apart.
+pub fn identify_service(
+    port: u16,
+    process_name: &str,
+    cmd: &str,
+) -> (Option<&'static str>, ServiceCategory) {
     // First try process name detection (more reliable than port)
     let name_lower = process_name.to_lowercase();
 
     if let Some(result) = identify_by_process_name(&name_lower) {
         return result;
     }
+    if let Some(label) = identify_by_command(&name_lower, cmd) {
+        return (Some(label), ServiceCategory::DevServer);
+    }
 
     // Fall back to well-known port mappings
     identify_by_port(port)
@@ -33,7 +95,10 @@ fn identify_by_process_name(name: &str) -> Option<(Option<&'static str>, Service
         return Some((Some("Memcached"), ServiceCategory::Cache));
     }
 
-    // Containers
+    // Containers. Port forwarders first, or they'd pass for the runtime itself
+    if is_container_proxy(name) {
+        return Some((Some(CONTAINER_PROXY_LABEL), ServiceCategory::Container));
+    }
     if name.contains("docker") || name.contains("containerd") {
         return Some((Some("Docker"), ServiceCategory::Container));
     }
@@ -104,49 +169,178 @@ fn identify_by_process_name(name: &str) -> Option<(Option<&'static str>, Service
     None
 }
 
+fn identify_by_command(name: &str, cmd: &str) -> Option<&'static str> {
+    let cmd = cmd.to_lowercase();
+    COMMAND_RULES
+        .iter()
+        .find(|(runtime, needles, _)| {
+            name.starts_with(runtime) && needles.iter().all(|n| cmd.contains(n))
+        })
+        .map(|(_, _, label)| *label)
+}
+
 fn identify_by_port(port: u16) -> (Option<&'static str>, ServiceCategory) {
-    match port {
-        // Dev servers
-        3000 => (Some("Next.js / Rails"), ServiceCategory::DevServer),
-        3001 => (Some("React Dev"), ServiceCategory::DevServer),
-        4000 => (Some("Phoenix"), ServiceCategory::DevServer),
-        4200 => (Some("Angular"), ServiceCategory::DevServer),
-        5173 | 5174 => (Some("Vite"), ServiceCategory::DevServer),
-        8000 => (Some("Django / FastAPI"), ServiceCategory::DevServer),
-        8080 => (Some("HTTP Alt"), ServiceCategory::DevServer),
-        8443 => (Some("HTTPS Alt"), ServiceCategory::DevServer),
-        8888 => (Some("Jupyter"), ServiceCategory::DevServer),
-        9000 => (Some("PHP-FPM"), ServiceCategory::DevServer),
-        19006 => (Some("Expo"), ServiceCategory::DevServer),
-
-        // Databases
-        3306 => (Some("MySQL"), ServiceCategory::Database),
-        5432 => (Some("PostgreSQL"), ServiceCategory::Database),
-        5433 => (Some("PostgreSQL Alt"), ServiceCategory::Database),
-        27017 => (Some("MongoDB"), ServiceCategory::Database),
-        26257 => (Some("CockroachDB"), ServiceCategory::Database),
-
-        // Cache / brokers
-        6379 => (Some("Redis"), ServiceCategory::Cache),
-        11211 => (Some("Memcached"), ServiceCategory::Cache),
-        9092 => (Some("Kafka"), ServiceCategory::Cache),
-        5672 => (Some("RabbitMQ"), ServiceCategory::Cache),
-        15672 => (Some("RabbitMQ UI"), ServiceCategory::Cache),
-
-        // Container / orchestration
-        2375 | 2376 => (Some("Docker"), ServiceCategory::Container),
-
-        // Dev tools
-        11434 => (Some("Ollama"), ServiceCategory::DevServer),
-
-        // System
-        22 => (Some("SSH"), ServiceCategory::System),
-        80 => (Some("HTTP"), ServiceCategory::System),
-        443 => (Some("HTTPS"), ServiceCategory::System),
-        53 => (Some("DNS"), ServiceCategory::System),
-        9090 => (Some("Prometheus"), ServiceCategory::System),
-        2019 => (Some("Caddy Admin"), ServiceCategory::System),
-
-        _ => (None, ServiceCategory::Unknown),
+    match ports::lookup(port) {
+        Some((label, category)) => (Some(label), category),
+        None => (system_service(port), ServiceCategory::Unknown),
+    }
+}
+
+/// Last-resort label from the system services database (`/etc/services`),
+/// read once on first use. Missing or unreadable files just yield no labels.
+fn system_service(port: u16) -> Option<&'static str> {
+    static SERVICES: OnceLock<HashMap<u16, String>> = OnceLock::new();
+    SERVICES
+        .get_or_init(|| {
+            services_path()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .map(|text| parse_services(&text))
+                .unwrap_or_default()
+        })
+        .get(&port)
+        .map(String::as_str)
+}
+
+#[cfg(windows)]
+fn services_path() -> Option<std::path::PathBuf> {
+    let root = std::env::var_os("SystemRoot")?;
+    Some(std::path::Path::new(&root).join(r"System32\drivers\etc\services"))
+}
+
+#[cfg(not(windows))]
+fn services_path() -> Option<std::path::PathBuf> {
+    Some(std::path::PathBuf::from("/etc/services"))
+}
+
+/// Parse `name port/proto [aliases] [# comment]` lines into port -> name.
+/// When TCP and UDP disagree on a port's name, the TCP one wins.
+fn parse_services(text: &str) -> HashMap<u16, String> {
+    let mut services: HashMap<u16, String> = HashMap::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let (Some(name), Some(port_proto)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let Some((port, proto)) = port_proto.split_once('/') else {
+            continue;
+        };
+        let Ok(port) = port.parse::<u16>() else {
+            continue;
+        };
+        if proto.eq_ignore_ascii_case("tcp") {
+            services.insert(port, name.to_string());
+        } else {
+            services.entry(port).or_insert_with(|| name.to_string());
+        }
+    }
+    services
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn services_file_parsing() {
+        let text = "\
+# Network services, Internet style
+ssh             22/tcp                          # SSH Remote Login Protocol
+domain          53/tcp
+domain          53/udp
+bootps          67/udp
+syslog          514/udp
+shell           514/tcp         cmd             # no passwords used
+http            80/tcp          www www-http
+broken          http/tcp
+toobig          70000/tcp
+noproto         8080
+";
+        let services = parse_services(text);
+        assert_eq!(services[&22], "ssh");
+        assert_eq!(services[&53], "domain");
+        // Aliases are ignored; the first name is the label
+        assert_eq!(services[&80], "http");
+        // A UDP-only port still gets a name, but TCP wins where both exist,
+        // whichever comes first
+        assert_eq!(services[&67], "bootps");
+        assert_eq!(services[&514], "shell");
+        // Malformed ports are skipped
+        assert_eq!(services.len(), 5);
+        assert!(!services.contains_key(&8080));
+    }
+
+    #[test]
+    fn container_proxies_and_their_targets() {
+        assert!(is_container_proxy("docker-proxy"));
+        assert!(is_container_proxy("com.docker.backend"));
+        assert!(!is_container_proxy("dockerd"));
+
+        let target = "172.17.0.2:80".parse().ok();
+        assert_eq!(
+            container_proxy_addr(
+                "/usr/bin/docker-proxy -proto tcp -host-ip 0.0.0.0 -host-port 8080 \
+                 -container-ip 172.17.0.2 -container-port 80"
+            ),
+            target
+        );
+        // Either order, and with Go's `--flag` spelling
+        assert_eq!(
+            container_proxy_addr("docker-proxy --container-port 80 --container-ip 172.17.0.2"),
+            target
+        );
+        assert_eq!(
+            container_proxy_addr("docker-proxy -container-ip fd00::2 -container-port 80"),
+            "[fd00::2]:80".parse().ok()
+        );
+        assert_eq!(
+            container_proxy_addr("docker-proxy -container-ip 172.17.0.2"),
+            None
+        );
+        assert_eq!(
+            container_proxy_addr("docker-proxy -container-ip 172.17.0.2 -container-port http"),
+            None
+        );
+        assert_eq!(container_proxy_addr("docker-proxy -container-port"), None);
+    }
+
+    #[test]
+    fn frameworks_need_their_runtime() {
+        let label = |name, cmd| identify_by_command(name, cmd);
+        assert_eq!(
+            label(
+                "java",
+                "java -cp app.jar org.springframework.boot.loader.JarLauncher"
+            ),
+            Some("Spring Boot")
+        );
+        assert_eq!(
+            label(
+                "python3.12",
+                "/venv/bin/python3 /venv/bin/gunicorn app:wsgi"
+            ),
+            Some("Gunicorn")
+        );
+        assert_eq!(
+            label("gunicorn", "gunicorn: master [app:wsgi]"),
+            Some("Gunicorn")
+        );
+        assert_eq!(
+            label("ruby", "puma 6.4.0 (tcp://0.0.0.0:3000) [app]"),
+            Some("Rails")
+        );
+        // Any other jar, or any node script, says nothing about the framework
+        assert_eq!(
+            label("java", "java -jar /opt/jenkins/jenkins.war app.jar"),
+            None
+        );
+        assert_eq!(label("node", "node server.js"), None);
+        // Matching words in someone else's command line don't count
+        assert_eq!(label("vim", "vim gunicorn.conf.py"), None);
+        assert_eq!(label("bash", "bash -c 'uvicorn main:app'"), None);
+        assert_eq!(
+            identify_service(8000, "less", "less uvicorn.log"),
+            (Some("Django / FastAPI"), ServiceCategory::DevServer)
+        );
     }
 }
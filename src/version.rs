@@ -0,0 +1,44 @@
+use std::io::{self, Write};
+
+/// `kav version`: build and platform details for bug reports, since socket and
+/// process enumeration work differently on every OS
+pub fn print() -> io::Result<()> {
+    let mut w = io::stdout().lock();
+    writeln!(
+        w,
+        "kav {} (built {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("BUILD_DATE")
+    )?;
+    for (label, value) in details() {
+        writeln!(w, "  {:<15} {}", format!("{}:", label), value)?;
+    }
+    Ok(())
+}
+
+fn details() -> Vec<(&'static str, String)> {
+    let linux = cfg!(any(target_os = "linux", target_os = "android"));
+    let supported = |yes: bool| if yes { "yes" } else { "no" }.to_string();
+    vec![
+        ("Target", env!("BUILD_TARGET").to_string()),
+        ("sysinfo", env!("SYSINFO_VERSION").to_string()),
+        ("netstat2", env!("NETSTAT2_VERSION").to_string()),
+        ("Sockets", socket_backend().to_string()),
+        ("Accept queues", supported(cfg!(target_os = "linux"))),
+        ("Thread counts", supported(linux)),
+        ("Socket inodes", supported(linux)),
+    ]
+}
+
+/// How netstat2 enumerates sockets and their owners on this target
+fn socket_backend() -> &'static str {
+    if cfg!(any(target_os = "linux", target_os = "android")) {
+        "netlink sock_diag, owners from /proc/<pid>/fd"
+    } else if cfg!(any(target_os = "macos", target_os = "ios")) {
+        "libproc (proc_pidfdinfo)"
+    } else if cfg!(windows) {
+        "IP Helper (GetExtendedTcpTable / GetExtendedUdpTable)"
+    } else {
+        "unsupported"
+    }
+}